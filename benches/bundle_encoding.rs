@@ -0,0 +1,82 @@
+//! Compare JSON and MessagePack parse time for a full-size bundle.
+//!
+//! Run with `cargo bench --features binary --bench bundle_encoding`.
+
+use fak::{
+    CapabilityManifest, CostLedger, ExecutionTrace, InvariantSpec, PolicyIR, ProofBundle,
+    ProofEngine, ProofType,
+};
+use std::collections::HashMap;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 20;
+
+fn full_bundle() -> ProofBundle {
+    let engine = ProofEngine::new();
+    let steps = (0..200)
+        .map(|i| serde_json::json!({"step": i, "action": "infer", "tokens": i * 17}))
+        .collect();
+    let trace = ExecutionTrace::new("trace-bench".to_string(), steps, serde_json::Map::new());
+    let mut graph = HashMap::new();
+    graph.insert("admin".to_string(), vec!["read".to_string(), "write".to_string()]);
+    let caps = CapabilityManifest::new(
+        "cap-bench".to_string(),
+        "agent-bench".to_string(),
+        vec!["read".to_string()],
+        graph,
+        serde_json::Map::new(),
+    );
+    let entries = (0..200)
+        .map(|i| serde_json::json!({"op": "inference", "cost": 0.001 * i as f64}))
+        .collect();
+    let ledger = CostLedger::new("cost-bench".to_string(), entries, 19.9, serde_json::Map::new());
+    let policy = PolicyIR::new(
+        "policy-bench".to_string(),
+        serde_json::Map::new(),
+        vec![0; 256],
+        serde_json::Map::new(),
+    );
+
+    let witnesses: Vec<_> = (0..ProofBundle::MAX_WITNESSES)
+        .map(|i| {
+            let inv = InvariantSpec::new(
+                format!("inv_{}", i),
+                String::new(),
+                None,
+                None,
+                vec![],
+                ProofType::EconomicInvariance,
+            );
+            engine
+                .verify_invariants(&trace, &caps, &ledger, &policy, &[inv])
+                .expect("verify")
+        })
+        .collect();
+    engine.generate_bundle(&witnesses).expect("bundle")
+}
+
+fn time<F: FnMut()>(mut f: F) -> f64 {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / ITERATIONS as f64
+}
+
+fn main() {
+    let bundle = full_bundle();
+    let json = serde_json::to_vec(&bundle).expect("json");
+    let bytes = bundle.to_bytes().expect("msgpack");
+
+    let json_ms = time(|| {
+        let parsed: ProofBundle = serde_json::from_slice(&json).expect("parse");
+        parsed.verify_id().expect("id");
+    });
+    let bin_ms = time(|| {
+        ProofBundle::from_bytes(&bytes).expect("parse");
+    });
+
+    println!("witnesses: {}", bundle.witnesses.len());
+    println!("json:    {:>9} bytes, {:>8.3} ms/parse", json.len(), json_ms);
+    println!("msgpack: {:>9} bytes, {:>8.3} ms/parse", bytes.len(), bin_ms);
+}
@@ -0,0 +1,170 @@
+//! Portable tar archives of a bundle and the artifacts it references.
+//!
+//! An archive holds `bundle.json`, the compact form of the bundle with an
+//! empty artifact pool, and one `artifacts/<hash>.json` member per pooled
+//! artifact. Importing re-derives every artifact hash and the bundle ID, so
+//! an imported bundle verifies exactly like the one that was exported.
+
+use crate::artifacts::ArtifactManager;
+use crate::compact::CompactBundle;
+use crate::error::{FakError, FakResult};
+use crate::types::{compute_content_hash, ProofBundle};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+
+const BUNDLE_MEMBER: &str = "bundle.json";
+const ARTIFACT_DIR: &str = "artifacts/";
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+impl ArtifactManager {
+    /// Most members [`Self::import_bundle_archive`] accepts: the bundle and
+    /// four artifacts for each of the most witnesses a bundle may hold.
+    pub const MAX_ARCHIVE_MEMBERS: usize = 1 + 4 * ProofBundle::MAX_WITNESSES;
+
+    /// Largest member [`Self::import_bundle_archive`] accepts, in bytes.
+    pub const MAX_ARCHIVE_MEMBER_BYTES: usize = 64 * 1024 * 1024;
+
+    /// Write `bundle` and every artifact it references to `writer` as a tar,
+    /// gzipped when `gzip` is set.
+    ///
+    /// Member headers carry no timestamps, so exporting the same bundle
+    /// twice produces identical bytes.
+    pub fn export_bundle_archive<W: Write>(
+        &self,
+        bundle: &ProofBundle,
+        writer: W,
+        gzip: bool,
+    ) -> FakResult<()> {
+        let mut compact = bundle.compact()?;
+        let artifacts = std::mem::take(&mut compact.artifacts);
+        if gzip {
+            let encoder = GzEncoder::new(writer, Compression::default());
+            write_members(encoder, &compact, &artifacts)?.finish()?;
+        } else {
+            write_members(writer, &compact, &artifacts)?;
+        }
+        Ok(())
+    }
+
+    /// Load an archive written by
+    /// [`export_bundle_archive`](Self::export_bundle_archive).
+    ///
+    /// Gzip compression is detected from the stream header. Every artifact
+    /// member is checked against the hash in its file name and the bundle ID
+    /// is re-derived before anything is stored in this manager. Archives
+    /// with more than [`Self::MAX_ARCHIVE_MEMBERS`] members, or a member
+    /// larger than [`Self::MAX_ARCHIVE_MEMBER_BYTES`], are rejected with
+    /// `ResourceLimit` before they are read in full.
+    pub fn import_bundle_archive<R: Read>(&self, reader: R) -> FakResult<ProofBundle> {
+        let mut reader = BufReader::new(reader);
+        let compact = if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            read_members(GzDecoder::new(reader))?
+        } else {
+            read_members(reader)?
+        };
+
+        let bundle = compact.expand()?;
+        bundle.verify_id()?;
+        for artifact in compact.artifacts.values() {
+            self.store_artifact(artifact)?;
+        }
+        Ok(bundle)
+    }
+}
+
+fn write_members<W: Write>(
+    writer: W,
+    compact: &CompactBundle,
+    artifacts: &BTreeMap<String, serde_json::Value>,
+) -> FakResult<W> {
+    let mut builder = tar::Builder::new(writer);
+    append_member(&mut builder, BUNDLE_MEMBER, &serde_json::to_vec_pretty(compact)?)?;
+    for (hash, artifact) in artifacts {
+        let path = format!("{}{}.json", ARTIFACT_DIR, hash);
+        append_member(&mut builder, &path, &serde_json::to_vec(artifact)?)?;
+    }
+    Ok(builder.into_inner()?)
+}
+
+fn append_member<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    data: &[u8],
+) -> FakResult<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    header.set_cksum();
+    builder.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+fn read_members<R: Read>(reader: R) -> FakResult<CompactBundle> {
+    let mut archive = tar::Archive::new(reader);
+    let mut compact: Option<CompactBundle> = None;
+    let mut artifacts = BTreeMap::new();
+
+    for (index, entry) in archive.entries()?.enumerate() {
+        if index >= ArtifactManager::MAX_ARCHIVE_MEMBERS {
+            return Err(FakError::ResourceLimit {
+                resource: "archive_members".to_string(),
+                limit: ArtifactManager::MAX_ARCHIVE_MEMBERS,
+                actual: index + 1,
+            });
+        }
+        let entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let size = entry.header().size()?;
+        if size > ArtifactManager::MAX_ARCHIVE_MEMBER_BYTES as u64 {
+            return Err(FakError::ResourceLimit {
+                resource: "archive_member_bytes".to_string(),
+                limit: ArtifactManager::MAX_ARCHIVE_MEMBER_BYTES,
+                actual: usize::try_from(size).unwrap_or(usize::MAX),
+            });
+        }
+        let mut data = Vec::new();
+        entry
+            .take(ArtifactManager::MAX_ARCHIVE_MEMBER_BYTES as u64)
+            .read_to_end(&mut data)?;
+        let parse_error = |e: serde_json::Error| FakError::ParseError {
+            source: path.clone(),
+            message: e.to_string(),
+            line: None,
+            column: None,
+        };
+
+        if path == BUNDLE_MEMBER {
+            compact = Some(serde_json::from_slice(&data).map_err(parse_error)?);
+        } else if let Some(hash) = path
+            .strip_prefix(ARTIFACT_DIR)
+            .and_then(|name| name.strip_suffix(".json"))
+        {
+            let artifact: serde_json::Value = serde_json::from_slice(&data).map_err(parse_error)?;
+            let actual = compute_content_hash(&artifact);
+            if actual != hash {
+                return Err(FakError::IntegrityFailure {
+                    expected: hash.to_string(),
+                    artifact_id: path,
+                    actual,
+                });
+            }
+            artifacts.insert(actual, artifact);
+        } else {
+            return Err(FakError::Validation {
+                field: "archive".to_string(),
+                message: format!("unexpected archive member '{}'", path),
+            });
+        }
+    }
+
+    let mut compact = compact.ok_or_else(|| FakError::Validation {
+        field: "archive".to_string(),
+        message: format!("archive has no '{}' member", BUNDLE_MEMBER),
+    })?;
+    compact.artifacts = artifacts;
+    Ok(compact)
+}
@@ -2,66 +2,631 @@
 
 use crate::engine::ProofEngine;
 use crate::error::{FakError, FakResult};
+use crate::metadata::{normalize_artifact_timestamps, normalize_timestamps};
+pub use crate::store::{ArtifactStore, FileArtifactStore, MemoryArtifactStore};
 use crate::types::{
-    CapabilityManifest, CostLedger, ExecutionTrace, PolicyIR, ProofBundle,
-    compute_content_hash,
+    CapabilityManifest, CostLedger, ExecutionTrace, HashAlgo, PolicyIR, ProofBundle,
+    canonical_json, compute_salted_content_hash_with,
 };
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Kind of operation recorded in an [`ArtifactEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactEventKind {
+    Store,
+    Retrieve,
+    Remove,
+    Clear,
+    /// Removed to stay within the manager's capacity.
+    Evict,
+}
+
+/// Audit record passed to an artifact manager's event sink.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactEvent {
+    pub kind: ArtifactEventKind,
+    /// `None` for operations that are not tied to one artifact, such as `Clear`.
+    pub artifact_id: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub timestamp_secs: f64,
+}
+
+/// Callback receiving artifact events.
+pub type ArtifactEventSink = Box<dyn Fn(ArtifactEvent) + Send + Sync>;
+
+/// Immutable view of an artifact store at one point in time.
+///
+/// With the default in-memory store, snapshots share storage with the
+/// manager they came from; the store copies its map on the next write
+/// instead, so taking a snapshot is O(1) and later stores, removals or
+/// clears never show through.
+///
+/// A snapshot taken through [`ArtifactManager::snapshot`] also carries the
+/// manager's ID scheme (salt, hash algorithm, volatile keys), so artifacts
+/// resolved from it are checked against the IDs that manager assigns.
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactSnapshot {
+    artifacts: Arc<HashMap<String, serde_json::Value>>,
+    ids: IdScheme,
+}
+
+impl ArtifactSnapshot {
+    pub(crate) fn new(artifacts: Arc<HashMap<String, serde_json::Value>>) -> Self {
+        Self { artifacts, ids: IdScheme::default() }
+    }
+
+    pub(crate) fn with_ids(mut self, ids: IdScheme) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Look up an artifact by its ID.
+    pub fn get(&self, artifact_id: &str) -> Option<&serde_json::Value> {
+        self.artifacts.get(artifact_id)
+    }
+
+    /// ID `artifact` is held under in the store this snapshot was taken of.
+    pub fn artifact_id(&self, artifact: &serde_json::Value) -> FakResult<String> {
+        Ok(self.ids.content_hash(&*self.ids.normalize(artifact)?))
+    }
+
+    /// Whether `artifact_id` is the ID of `artifact` under this snapshot's
+    /// ID scheme, hashing with the algorithm a tagged ID names.
+    pub(crate) fn id_matches(&self, artifact_id: &str, artifact: &serde_json::Value) -> bool {
+        self.ids.matches(artifact_id, artifact)
+    }
+
+    pub fn contains(&self, artifact_id: &str) -> bool {
+        self.artifacts.contains_key(artifact_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.artifacts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.artifacts.is_empty()
+    }
+}
+
+/// Point-in-time summary of how much content addressing saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DedupStats {
+    /// Store calls for artifacts that are still held.
+    pub total_stores: u64,
+    /// Distinct artifacts held.
+    pub unique_artifacts: usize,
+    /// Store calls that hit an artifact already held.
+    pub duplicate_stores: u64,
+    /// Canonical JSON bytes those duplicate stores would otherwise have used.
+    pub bytes_saved: u64,
+}
+
+/// What [`ArtifactManager::store_artifact`] does when an artifact shares
+/// its identity with a held artifact but differs in volatile keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataConflict {
+    /// Keep the artifact stored first and count the store as a duplicate.
+    #[default]
+    KeepFirst,
+    /// Fail with [`FakError::IntegrityFailure`].
+    Reject,
+}
+
+/// Recency bookkeeping for a capacity-bounded manager.
+#[derive(Debug, Clone, Default)]
+struct Lru {
+    capacity: usize,
+    clock: u64,
+    ticks: HashMap<String, u64>,
+    /// `(tick, id)` pairs, least recently used first.
+    order: BTreeSet<(u64, String)>,
+    seeded: bool,
+}
+
+impl Lru {
+    /// Adopt artifacts already in `store` as least recently used, once.
+    fn seed(&mut self, store: &dyn ArtifactStore) -> FakResult<()> {
+        if !self.seeded {
+            for artifact_id in store.ids()? {
+                if !self.ticks.contains_key(&artifact_id) {
+                    self.ticks.insert(artifact_id.clone(), 0);
+                    self.order.insert((0, artifact_id));
+                }
+            }
+            self.seeded = true;
+        }
+        Ok(())
+    }
+
+    fn touch(&mut self, artifact_id: &str) {
+        self.forget(artifact_id);
+        self.clock += 1;
+        self.ticks.insert(artifact_id.to_string(), self.clock);
+        self.order.insert((self.clock, artifact_id.to_string()));
+    }
+
+    fn forget(&mut self, artifact_id: &str) {
+        if let Some(tick) = self.ticks.remove(artifact_id) {
+            self.order.remove(&(tick, artifact_id.to_string()));
+        }
+    }
+
+    /// Next artifact to evict while over capacity.
+    fn victim(&mut self) -> Option<String> {
+        if self.order.len() <= self.capacity {
+            return None;
+        }
+        let (_, artifact_id) = self.order.pop_first()?;
+        self.ticks.remove(&artifact_id);
+        Some(artifact_id)
+    }
+}
+
+/// Secondary index from `(key, value)` tags to the artifacts carrying them.
+#[derive(Debug, Clone, Default)]
+struct TagIndex {
+    by_artifact: HashMap<String, HashMap<String, String>>,
+    by_tag: HashMap<(String, String), BTreeSet<String>>,
+}
+
+impl TagIndex {
+    /// Add `tags` to `artifact_id`, replacing the value of any key it
+    /// already carries.
+    fn insert(&mut self, artifact_id: &str, tags: &HashMap<String, String>) {
+        let current = self.by_artifact.entry(artifact_id.to_string()).or_default();
+        for (key, value) in tags {
+            if let Some(old) = current.insert(key.clone(), value.clone()) {
+                let old = (key.clone(), old);
+                if let Some(ids) = self.by_tag.get_mut(&old) {
+                    ids.remove(artifact_id);
+                    if ids.is_empty() {
+                        self.by_tag.remove(&old);
+                    }
+                }
+            }
+            self.by_tag
+                .entry((key.clone(), value.clone()))
+                .or_default()
+                .insert(artifact_id.to_string());
+        }
+    }
+
+    fn forget(&mut self, artifact_id: &str) {
+        for tag in self.by_artifact.remove(artifact_id).unwrap_or_default() {
+            if let Some(ids) = self.by_tag.get_mut(&tag) {
+                ids.remove(artifact_id);
+                if ids.is_empty() {
+                    self.by_tag.remove(&tag);
+                }
+            }
+        }
+    }
+}
+
+/// How artifact IDs are derived: what of an artifact they cover and how it
+/// is hashed. Shared by [`ArtifactManager`], its snapshots and the async
+/// manager so that all of them agree on every ID.
+#[derive(Clone, Default, PartialEq)]
+pub(crate) struct IdScheme {
+    pub(crate) salt: Vec<u8>,
+    pub(crate) hash_algo: Option<HashAlgo>,
+    pub(crate) timestamp_keys: Vec<String>,
+    pub(crate) volatile_keys: Vec<String>,
+}
+
+impl IdScheme {
+    /// `artifact` as it is stored: with its timestamp keys normalized.
+    pub(crate) fn normalize<'a>(&self, artifact: &'a serde_json::Value) -> FakResult<Cow<'a, serde_json::Value>> {
+        if self.timestamp_keys.is_empty() {
+            return Ok(Cow::Borrowed(artifact));
+        }
+        let mut copy = artifact.clone();
+        normalize_artifact_timestamps(&mut copy, &self.timestamp_keys)?;
+        Ok(Cow::Owned(copy))
+    }
+
+    /// The part of `artifact` its ID covers: all of it but the volatile keys.
+    pub(crate) fn identity<'a>(&self, artifact: &'a serde_json::Value) -> Cow<'a, serde_json::Value> {
+        if self.volatile_keys.is_empty() {
+            return Cow::Borrowed(artifact);
+        }
+        let mut identity = artifact.clone();
+        for path in &self.volatile_keys {
+            remove_path(&mut identity, path);
+        }
+        Cow::Owned(identity)
+    }
+
+    /// ID of an already normalized `artifact`.
+    pub(crate) fn content_hash(&self, artifact: &serde_json::Value) -> String {
+        self.identity_hash(artifact, self.hash_algo)
+    }
+
+    pub(crate) fn full_content_hash(&self, artifact: &serde_json::Value) -> String {
+        compute_salted_content_hash_with(artifact, &self.salt, self.hash_algo)
+    }
+
+    fn identity_hash(&self, artifact: &serde_json::Value, algo: Option<HashAlgo>) -> String {
+        compute_salted_content_hash_with(&self.identity(artifact), &self.salt, algo)
+    }
+
+    /// Whether `artifact_id` is the ID of `artifact`, hashing with the
+    /// algorithm a tagged ID names and untagged SHA-256 otherwise.
+    pub(crate) fn matches(&self, artifact_id: &str, artifact: &serde_json::Value) -> bool {
+        self.identity_hash(artifact, HashAlgo::from_tagged(artifact_id)) == artifact_id
+    }
+
+    /// Fail unless `held`, found under `artifact_id`, has the same identity
+    /// as `artifact`: a hash collision, or a store written to by something
+    /// else.
+    pub(crate) fn check_same(
+        &self,
+        artifact_id: &str,
+        held: &serde_json::Value,
+        artifact: &serde_json::Value,
+    ) -> FakResult<()> {
+        if self.identity(held) != self.identity(artifact) {
+            return Err(FakError::IntegrityFailure {
+                artifact_id: artifact_id.to_string(),
+                expected: artifact_id.to_string(),
+                actual: self.content_hash(held),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for IdScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdScheme")
+            .field("salted", &!self.salt.is_empty())
+            .field("hash_algo", &self.hash_algo)
+            .field("timestamp_keys", &self.timestamp_keys)
+            .field("volatile_keys", &self.volatile_keys)
+            .finish()
+    }
+}
 
 /// Thread-safe artifact manager with content-addressable storage.
-#[derive(Debug)]
 pub struct ArtifactManager {
-    artifacts: Arc<RwLock<HashMap<String, serde_json::Value>>>,
+    store: Arc<dyn ArtifactStore>,
+    event_sink: Option<Arc<dyn Fn(ArtifactEvent) + Send + Sync>>,
+    log_reads: bool,
+    engine: ProofEngine,
+    /// Store calls per held artifact ID. Held across store mutations so the
+    /// counts always match the store's contents.
+    store_counts: Arc<RwLock<HashMap<String, u64>>>,
+    ids: IdScheme,
+    on_conflict: MetadataConflict,
+    /// Full-content hash per held artifact ID, kept when `volatile_keys` is
+    /// set; updated under the `store_counts` lock.
+    full_hashes: Arc<RwLock<HashMap<String, String>>>,
+    /// Tags given to [`Self::store_artifact_indexed`]; updated under the
+    /// `store_counts` lock.
+    tags: Arc<RwLock<TagIndex>>,
+    /// Present when the manager has a capacity; updated under the
+    /// `store_counts` lock when artifacts are added or removed.
+    lru: Option<Arc<RwLock<Lru>>>,
 }
 
 impl ArtifactManager {
-    /// Create a new artifact manager.
+    /// Create a new artifact manager backed by an in-memory store.
     pub fn new() -> Self {
         Self {
-            artifacts: Arc::new(RwLock::new(HashMap::new())),
+            store: Arc::new(MemoryArtifactStore::new()),
+            event_sink: None,
+            log_reads: false,
+            engine: ProofEngine::new(),
+            store_counts: Arc::new(RwLock::new(HashMap::new())),
+            ids: IdScheme::default(),
+            on_conflict: MetadataConflict::default(),
+            full_hashes: Arc::new(RwLock::new(HashMap::new())),
+            tags: Arc::default(),
+            lru: None,
+        }
+    }
+
+    /// Create an artifact manager that reports every mutating operation to `sink`.
+    pub fn with_event_sink(sink: ArtifactEventSink) -> Self {
+        Self {
+            event_sink: Some(Arc::from(sink)),
+            ..Self::new()
+        }
+    }
+
+    /// Also report retrievals to the event sink.
+    pub fn log_reads(mut self, enabled: bool) -> Self {
+        self.log_reads = enabled;
+        self
+    }
+
+    /// Use `engine` (and its validation hooks) when creating bundles.
+    pub fn with_engine(mut self, engine: ProofEngine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Hold at most `max_entries` artifacts (at least one), evicting the
+    /// least recently stored or retrieved artifact to make room.
+    ///
+    /// Recency ties are broken by artifact ID, lexicographically smallest
+    /// first, so eviction is deterministic. Ties only arise among artifacts
+    /// a persistent store already held when this manager first touched it,
+    /// which all count as least recently used. An evicted artifact is gone
+    /// from the store and its dedup history is forgotten; storing it again
+    /// simply re-inserts it under the same ID.
+    pub fn with_capacity(mut self, max_entries: usize) -> Self {
+        self.lru = Some(Arc::new(RwLock::new(Lru {
+            capacity: max_entries.max(1),
+            ..Lru::default()
+        })));
+        self
+    }
+
+    /// Keep artifacts in `store` instead of memory.
+    ///
+    /// Store counts start empty, so [`Self::dedup_stats`] only covers stores
+    /// made through this manager.
+    pub fn with_store(mut self, store: impl ArtifactStore + 'static) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Namespace artifact IDs with a per-deployment salt.
+    ///
+    /// The salt, prefixed with its length, is hashed ahead of the canonical
+    /// bytes of every artifact this manager stores or checks. Managers with
+    /// different salts therefore hash different byte strings for any
+    /// content, so their IDs on shared storage collide only if the hash
+    /// itself does. Only store IDs are salted; proof and bundle IDs are
+    /// unaffected.
+    pub fn with_salt(mut self, salt: impl Into<Vec<u8>>) -> Self {
+        self.ids.salt = salt.into();
+        self
+    }
+
+    /// Hash new artifacts with `algo` and tag their IDs with it
+    /// (`"blake3:…"`). Without this, IDs are untagged SHA-256.
+    ///
+    /// Integrity checks hash with whatever algorithm an ID's tag names, so
+    /// artifacts stored before the algorithm changed still validate.
+    pub fn with_hash_algo(mut self, algo: HashAlgo) -> Self {
+        self.ids.hash_algo = Some(algo);
+        self
+    }
+
+    /// Normalize these metadata keys to the canonical timestamp form before
+    /// hashing (see [`crate::metadata`]): in the `metadata` object of stored
+    /// artifacts, which are held in normalized form, and of bundle inputs.
+    pub fn with_timestamp_keys(mut self, keys: Vec<String>) -> Self {
+        self.ids.timestamp_keys = keys;
+        self
+    }
+
+    /// Leave these dotted paths (e.g. `metadata.captured_at`) out of store IDs.
+    ///
+    /// Artifacts that differ only at these paths then share one ID and are
+    /// deduplicated; what happens to the later copy is set by
+    /// [`Self::on_metadata_conflict`]. The trade-off is that an ID no longer
+    /// pins the whole artifact: anyone can change a volatile value without
+    /// changing the ID. To still catch that, the manager records a
+    /// full-content hash for every artifact it stores, and
+    /// [`Self::validate_artifact_integrity`] checks it as well. Those hashes
+    /// live in this manager only, so after a restart over a persistent store
+    /// only the identity subset can be checked.
+    pub fn with_volatile_keys(mut self, keys: Vec<String>) -> Self {
+        self.ids.volatile_keys = keys;
+        self
+    }
+
+    /// Set how stores that conflict on volatile keys are handled.
+    pub fn on_metadata_conflict(mut self, on_conflict: MetadataConflict) -> Self {
+        self.on_conflict = on_conflict;
+        self
+    }
+
+    /// Store ID of `artifact`: the content hash under this manager's salt,
+    /// taken over the artifact without its volatile keys.
+    pub fn content_hash(&self, artifact: &serde_json::Value) -> String {
+        self.ids.content_hash(artifact)
+    }
+
+    /// Content hash of the whole of `artifact`, volatile keys included.
+    pub fn full_content_hash(&self, artifact: &serde_json::Value) -> String {
+        self.ids.full_content_hash(artifact)
+    }
+
+    /// Full-content hash recorded for a held artifact, if any.
+    pub fn recorded_full_hash(&self, artifact_id: &str) -> FakResult<Option<String>> {
+        let full_hashes = self.full_hashes.read().map_err(|_| FakError::LockPoisoned {
+            resource: "full_hashes".to_string(),
+        })?;
+        Ok(full_hashes.get(artifact_id).cloned())
+    }
+
+    fn emit(&self, kind: ArtifactEventKind, artifact_id: Option<&str>) {
+        if let Some(sink) = &self.event_sink {
+            if kind == ArtifactEventKind::Retrieve && !self.log_reads {
+                return;
+            }
+            sink(ArtifactEvent {
+                kind,
+                artifact_id: artifact_id.map(str::to_string),
+                timestamp_secs: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0),
+            });
         }
     }
 
     /// Store an artifact and return its content-addressable ID.
+    ///
+    /// Storing an artifact that is already held is a no-op. If different
+    /// content is already held under the same ID, the store is left as it
+    /// is and an `IntegrityFailure` is returned.
     pub fn store_artifact(&self, artifact: &serde_json::Value) -> FakResult<String> {
-        let artifact_id = compute_content_hash(artifact);
-        let mut artifacts = self.artifacts.write().map_err(|_| FakError::LockPoisoned {
-            resource: "artifacts".to_string(),
+        self.store_tagged(artifact, None)
+    }
+
+    /// Store an artifact and index it under `tags`, e.g. `agent=agent-001`,
+    /// for [`Self::query_by_tag`].
+    ///
+    /// Tags are metadata beside the artifact: they do not affect its ID.
+    /// Storing an artifact again adds its new tags, replacing the value of
+    /// a key it already had. Removing, evicting or clearing an artifact
+    /// drops its tags.
+    pub fn store_artifact_indexed(
+        &self,
+        artifact: &serde_json::Value,
+        tags: HashMap<String, String>,
+    ) -> FakResult<String> {
+        self.store_tagged(artifact, Some(&tags))
+    }
+
+    /// IDs of held artifacts tagged `key=value`, sorted.
+    pub fn query_by_tag(&self, key: &str, value: &str) -> FakResult<Vec<String>> {
+        let tags = self.tags.read().map_err(|_| FakError::LockPoisoned {
+            resource: "tags".to_string(),
         })?;
-        artifacts.insert(artifact_id.clone(), artifact.clone());
+        Ok(tags
+            .by_tag
+            .get(&(key.to_string(), value.to_string()))
+            .map(|ids| ids.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    fn store_tagged(
+        &self,
+        artifact: &serde_json::Value,
+        tags: Option<&HashMap<String, String>>,
+    ) -> FakResult<String> {
+        let artifact = &*self.ids.normalize(artifact)?;
+        let artifact_id = self.content_hash(artifact);
+        let mut counts = self.counts_mut()?;
+        // Recorded only once the store holds the artifact, so a failed
+        // store leaves no full hash behind for an ID that is not held.
+        let mut new_full_hash = None;
+        if !self.ids.volatile_keys.is_empty() {
+            let full_hash = self.full_content_hash(artifact);
+            match self.full_hashes_mut()?.get(&artifact_id) {
+                Some(recorded) if *recorded != full_hash => {
+                    if self.on_conflict == MetadataConflict::Reject {
+                        return Err(FakError::IntegrityFailure {
+                            artifact_id,
+                            expected: recorded.clone(),
+                            actual: full_hash,
+                        });
+                    }
+                }
+                Some(_) => {}
+                None => new_full_hash = Some(full_hash),
+            }
+        }
+        if !self.store.put(&artifact_id, artifact)? {
+            self.check_held_matches(&artifact_id, artifact)?;
+        }
+        if let Some(full_hash) = new_full_hash {
+            self.full_hashes_mut()?.insert(artifact_id.clone(), full_hash);
+        }
+        *counts.entry(artifact_id.clone()).or_insert(0) += 1;
+        if let Some(tags) = tags {
+            self.tags_mut()?.insert(&artifact_id, tags);
+        }
+        let evicted = self.touch_and_evict(&artifact_id, &mut counts)?;
+        drop(counts);
+        self.emit(ArtifactEventKind::Store, Some(&artifact_id));
+        for artifact_id in &evicted {
+            self.emit(ArtifactEventKind::Evict, Some(artifact_id));
+        }
         Ok(artifact_id)
     }
 
+    /// Fail if the artifact already held under `artifact_id` is not
+    /// `artifact`, volatile keys aside: a hash collision, or a store written
+    /// to by something other than this manager.
+    fn check_held_matches(&self, artifact_id: &str, artifact: &serde_json::Value) -> FakResult<()> {
+        match self.store.get(artifact_id)? {
+            Some(held) => self.ids.check_same(artifact_id, &held, artifact),
+            None => Ok(()),
+        }
+    }
+
     /// Retrieve an artifact by its ID.
     pub fn retrieve_artifact(&self, artifact_id: &str) -> FakResult<serde_json::Value> {
-        let artifacts = self.artifacts.read().map_err(|_| FakError::LockPoisoned {
-            resource: "artifacts".to_string(),
+        let value = self.store.get(artifact_id)?.ok_or_else(|| FakError::ArtifactNotFound {
+            artifact_id: artifact_id.to_string(),
         })?;
-        match artifacts.get(artifact_id) {
-            Some(value) => Ok(value.clone()),
-            None => Err(FakError::ArtifactNotFound {
-                artifact_id: artifact_id.to_string(),
-            }),
+        if self.lru.is_some() {
+            // Under the `store_counts` lock, like removal and eviction, so an
+            // ID removed since the read is not put back into the LRU order.
+            let _counts = self.counts_mut()?;
+            if self.store.contains(artifact_id)? {
+                if let Some(mut lru) = self.lru_mut()? {
+                    lru.seed(self.store.as_ref())?;
+                    lru.touch(artifact_id);
+                }
+            }
+        }
+        self.emit(ArtifactEventKind::Retrieve, Some(artifact_id));
+        Ok(value)
+    }
+
+    /// Retrieve an artifact and deserialize it as `T`; content that does not
+    /// fit `T` is a `Serialization` error.
+    pub fn retrieve_artifact_as<T: serde::de::DeserializeOwned>(&self, artifact_id: &str) -> FakResult<T> {
+        Ok(serde_json::from_value(self.retrieve_artifact(artifact_id)?)?)
+    }
+
+    /// Remove an artifact, returning its content.
+    pub fn remove_artifact(&self, artifact_id: &str) -> FakResult<serde_json::Value> {
+        let mut counts = self.counts_mut()?;
+        let value = self.store.remove(artifact_id)?.ok_or_else(|| FakError::ArtifactNotFound {
+            artifact_id: artifact_id.to_string(),
+        })?;
+        counts.remove(artifact_id);
+        self.full_hashes_mut()?.remove(artifact_id);
+        self.tags_mut()?.forget(artifact_id);
+        if let Some(mut lru) = self.lru_mut()? {
+            lru.forget(artifact_id);
         }
+        drop(counts);
+        self.emit(ArtifactEventKind::Remove, Some(artifact_id));
+        Ok(value)
     }
 
     /// Check if an artifact exists.
     pub fn contains(&self, artifact_id: &str) -> FakResult<bool> {
-        let artifacts = self.artifacts.read().map_err(|_| FakError::LockPoisoned {
-            resource: "artifacts".to_string(),
-        })?;
-        Ok(artifacts.contains_key(artifact_id))
+        self.store.contains(artifact_id)
     }
 
     /// Validate artifact integrity by recomputing hash.
+    ///
+    /// The hash is recomputed with the algorithm a tagged ID names, and as
+    /// untagged SHA-256 otherwise. With volatile keys configured, the
+    /// full-content hash recorded when the artifact was stored must match
+    /// as well.
     pub fn validate_artifact_integrity(
         &self,
         artifact_id: &str,
         artifact: &serde_json::Value,
     ) -> bool {
-        let computed_id = compute_content_hash(artifact);
-        computed_id == artifact_id
+        if !self.ids.matches(artifact_id, artifact) {
+            return false;
+        }
+        let algo = HashAlgo::from_tagged(artifact_id);
+        match self.recorded_full_hash(artifact_id) {
+            Ok(Some(full_hash)) => {
+                full_hash == compute_salted_content_hash_with(artifact, &self.ids.salt, algo)
+            }
+            Ok(None) => true,
+            Err(_) => false,
+        }
     }
 
     /// Create a proof bundle from governance artifacts.
@@ -72,12 +637,23 @@ impl ArtifactManager {
         cost_ledger: &CostLedger,
         policy_ir: &PolicyIR,
     ) -> FakResult<ProofBundle> {
+        let normalized;
+        let (trace, capabilities, cost_ledger, policy_ir) = if self.ids.timestamp_keys.is_empty() {
+            (trace, capabilities, cost_ledger, policy_ir)
+        } else {
+            normalized = self.normalize_inputs(trace, capabilities, cost_ledger, policy_ir)?;
+            (&normalized.0, &normalized.1, &normalized.2, &normalized.3)
+        };
+
         // Validate inputs
         trace.validate()?;
-        capabilities.validate()?;
+        capabilities.validate_with_edge_limit(self.engine.config().max_authority_edges)?;
         cost_ledger.validate()?;
         policy_ir.validate()?;
 
+        // Prove first so validation hooks reject inputs before anything is stored
+        let witness = self.engine.verify_invariants(trace, capabilities, cost_ledger, policy_ir, &[])?;
+
         // Serialize artifacts
         let trace_json = serde_json::to_value(trace)?;
         let cap_json = serde_json::to_value(capabilities)?;
@@ -91,28 +667,43 @@ impl ArtifactManager {
         let policy_id = self.store_artifact(&policy_json)?;
 
         // Validate integrity
-        self.verify_integrity(&trace_id, &trace_json, "trace")?;
-        self.verify_integrity(&cap_id, &cap_json, "capability_manifest")?;
-        self.verify_integrity(&cost_id, &cost_json, "cost_ledger")?;
-        self.verify_integrity(&policy_id, &policy_json, "policy_ir")?;
+        self.verify_integrity(&trace_id, &trace_json)?;
+        self.verify_integrity(&cap_id, &cap_json)?;
+        self.verify_integrity(&cost_id, &cost_json)?;
+        self.verify_integrity(&policy_id, &policy_json)?;
 
-        // Generate proof
-        let engine = ProofEngine::new();
-        let witness = engine.verify_invariants(trace, capabilities, cost_ledger, policy_ir, &[])?;
-        engine.generate_bundle(&[witness])
+        self.engine.generate_bundle(&[witness])
     }
 
-    fn verify_integrity(
+    fn normalize_inputs(
         &self,
-        artifact_id: &str,
-        artifact: &serde_json::Value,
-        name: &str,
-    ) -> FakResult<()> {
-        if !self.validate_artifact_integrity(artifact_id, artifact) {
+        trace: &ExecutionTrace,
+        capabilities: &CapabilityManifest,
+        cost_ledger: &CostLedger,
+        policy_ir: &PolicyIR,
+    ) -> FakResult<(ExecutionTrace, CapabilityManifest, CostLedger, PolicyIR)> {
+        let (mut trace, mut capabilities, mut cost_ledger, mut policy_ir) =
+            (trace.clone(), capabilities.clone(), cost_ledger.clone(), policy_ir.clone());
+        for metadata in [
+            &mut trace.metadata,
+            &mut capabilities.metadata,
+            &mut cost_ledger.metadata,
+            &mut policy_ir.metadata,
+        ] {
+            normalize_timestamps(metadata, &self.ids.timestamp_keys)?;
+        }
+        Ok((trace, capabilities, cost_ledger, policy_ir))
+    }
+
+    fn verify_integrity(&self, artifact_id: &str, artifact: &serde_json::Value) -> FakResult<()> {
+        // Identity only: under `MetadataConflict::KeepFirst` a bundle input may
+        // legitimately differ from the held copy in volatile keys.
+        let actual = self.content_hash(artifact);
+        if actual != artifact_id {
             return Err(FakError::IntegrityFailure {
                 artifact_id: artifact_id.to_string(),
                 expected: artifact_id.to_string(),
-                actual: compute_content_hash(artifact),
+                actual,
             });
         }
         Ok(())
@@ -120,12 +711,101 @@ impl ArtifactManager {
 
     /// Clear all stored artifacts.
     pub fn clear(&self) -> FakResult<()> {
-        let mut artifacts = self.artifacts.write().map_err(|_| FakError::LockPoisoned {
-            resource: "artifacts".to_string(),
-        })?;
-        artifacts.clear();
+        let mut counts = self.counts_mut()?;
+        self.store.clear()?;
+        counts.clear();
+        self.full_hashes_mut()?.clear();
+        *self.tags_mut()? = TagIndex::default();
+        if let Some(mut lru) = self.lru_mut()? {
+            *lru = Lru {
+                capacity: lru.capacity,
+                ..Lru::default()
+            };
+        }
+        drop(counts);
+        self.emit(ArtifactEventKind::Clear, None);
         Ok(())
     }
+
+    fn counts_mut(&self) -> FakResult<RwLockWriteGuard<'_, HashMap<String, u64>>> {
+        self.store_counts.write().map_err(|_| FakError::LockPoisoned {
+            resource: "store_counts".to_string(),
+        })
+    }
+
+    fn lru_mut(&self) -> FakResult<Option<RwLockWriteGuard<'_, Lru>>> {
+        self.lru
+            .as_ref()
+            .map(|lru| {
+                lru.write().map_err(|_| FakError::LockPoisoned {
+                    resource: "lru".to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    /// Mark `artifact_id` most recently used and evict down to capacity.
+    /// Called with the `store_counts` lock held.
+    fn touch_and_evict(
+        &self,
+        artifact_id: &str,
+        counts: &mut HashMap<String, u64>,
+    ) -> FakResult<Vec<String>> {
+        let Some(mut lru) = self.lru_mut()? else {
+            return Ok(Vec::new());
+        };
+        lru.seed(self.store.as_ref())?;
+        lru.touch(artifact_id);
+        let mut evicted = Vec::new();
+        while let Some(victim) = lru.victim() {
+            self.store.remove(&victim)?;
+            counts.remove(&victim);
+            self.full_hashes_mut()?.remove(&victim);
+            self.tags_mut()?.forget(&victim);
+            evicted.push(victim);
+        }
+        Ok(evicted)
+    }
+
+    fn full_hashes_mut(&self) -> FakResult<RwLockWriteGuard<'_, HashMap<String, String>>> {
+        self.full_hashes.write().map_err(|_| FakError::LockPoisoned {
+            resource: "full_hashes".to_string(),
+        })
+    }
+
+    fn tags_mut(&self) -> FakResult<RwLockWriteGuard<'_, TagIndex>> {
+        self.tags.write().map_err(|_| FakError::LockPoisoned {
+            resource: "tags".to_string(),
+        })
+    }
+
+    /// Summarize how often stores hit an artifact that was already held.
+    ///
+    /// Counts cover artifacts currently held; removing an artifact or
+    /// clearing the store forgets its history.
+    pub fn dedup_stats(&self) -> FakResult<DedupStats> {
+        let counts = self.counts_mut()?;
+
+        let mut stats = DedupStats {
+            unique_artifacts: self.store.ids()?.len(),
+            ..DedupStats::default()
+        };
+        for (artifact_id, &count) in counts.iter() {
+            stats.total_stores += count;
+            if count > 1 {
+                stats.duplicate_stores += count - 1;
+                if let Some(artifact) = self.store.get(artifact_id)? {
+                    stats.bytes_saved += (count - 1) * canonical_json(&artifact).len() as u64;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Capture the current contents as an immutable snapshot.
+    pub fn snapshot(&self) -> FakResult<ArtifactSnapshot> {
+        Ok(self.store.snapshot()?.with_ids(self.ids.clone()))
+    }
 }
 
 impl Default for ArtifactManager {
@@ -136,9 +816,56 @@ impl Default for ArtifactManager {
 
 impl Clone for ArtifactManager {
     fn clone(&self) -> Self {
-        let artifacts = self.artifacts.read().expect("lock not poisoned");
+        let counts = self.store_counts.read().expect("lock not poisoned");
         Self {
-            artifacts: Arc::new(RwLock::new(artifacts.clone())),
+            store: Arc::from(self.store.fork()),
+            event_sink: self.event_sink.clone(),
+            log_reads: self.log_reads,
+            engine: self.engine.clone(),
+            store_counts: Arc::new(RwLock::new(counts.clone())),
+            ids: self.ids.clone(),
+            on_conflict: self.on_conflict,
+            full_hashes: Arc::new(RwLock::new(
+                self.full_hashes.read().expect("lock not poisoned").clone(),
+            )),
+            tags: Arc::new(RwLock::new(self.tags.read().expect("lock not poisoned").clone())),
+            lru: self.lru.as_ref().map(|lru| {
+                Arc::new(RwLock::new(lru.read().expect("lock not poisoned").clone()))
+            }),
         }
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Debug for ArtifactManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArtifactManager")
+            .field("store", &self.store)
+            .field("event_sink", &self.event_sink.is_some())
+            .field("log_reads", &self.log_reads)
+            .field("engine", &self.engine)
+            .field("ids", &self.ids)
+            .field("on_conflict", &self.on_conflict)
+            .field(
+                "capacity",
+                &self.lru.as_ref().and_then(|lru| lru.read().ok().map(|lru| lru.capacity)),
+            )
+            .finish()
+    }
+}
+/// Remove the value at a dotted `path`, if present.
+fn remove_path(value: &mut serde_json::Value, path: &str) {
+    let (parents, last) = match path.rsplit_once('.') {
+        Some((parents, last)) => (Some(parents), last),
+        None => (None, path),
+    };
+    let mut target = value;
+    for segment in parents.into_iter().flat_map(|p| p.split('.')) {
+        match target.get_mut(segment) {
+            Some(next) => target = next,
+            None => return,
+        }
+    }
+    if let Some(object) = target.as_object_mut() {
+        object.remove(last);
+    }
+}
@@ -0,0 +1,151 @@
+//! Artifact manager for async services.
+//!
+//! Built with the `tokio` feature. [`AsyncArtifactManager`] keeps artifacts
+//! in memory behind a `tokio::sync::RwLock`, so store and retrieve calls
+//! await the lock instead of blocking a runtime thread. Tokio locks do not
+//! poison, so no method returns `LockPoisoned`.
+//!
+//! IDs are derived exactly as by [`ArtifactManager`](crate::ArtifactManager):
+//! the same salt, hash algorithm, timestamp keys and volatile keys give the
+//! same ID, and a store that collides with a different artifact fails the
+//! same way. Beyond that it covers the storage core only. It does not record
+//! full-content hashes, so with volatile keys a later copy is always
+//! deduplicated into the first and integrity checks cover the identity
+//! subset. `create_bundle`, tags, capacity and LRU eviction, artifact events
+//! and persistent stores are out of scope; use `ArtifactManager` for them.
+
+#![cfg(feature = "tokio")]
+
+use crate::artifacts::{ArtifactSnapshot, IdScheme};
+use crate::error::{FakError, FakResult};
+use crate::types::HashAlgo;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// In-memory content-addressable store with async access.
+///
+/// The map is copied on write, so snapshots are O(1) and never observe
+/// later changes.
+#[derive(Debug, Default)]
+pub struct AsyncArtifactManager {
+    artifacts: RwLock<Arc<HashMap<String, serde_json::Value>>>,
+    ids: IdScheme,
+}
+
+impl AsyncArtifactManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Namespace artifact IDs with a per-deployment salt, as
+    /// [`ArtifactManager::with_salt`](crate::ArtifactManager::with_salt).
+    pub fn with_salt(mut self, salt: impl Into<Vec<u8>>) -> Self {
+        self.ids.salt = salt.into();
+        self
+    }
+
+    /// Hash IDs with `algo`, as
+    /// [`ArtifactManager::with_hash_algo`](crate::ArtifactManager::with_hash_algo).
+    pub fn with_hash_algo(mut self, algo: HashAlgo) -> Self {
+        self.ids.hash_algo = Some(algo);
+        self
+    }
+
+    /// Normalize these metadata timestamp keys before hashing, as
+    /// [`ArtifactManager::with_timestamp_keys`](crate::ArtifactManager::with_timestamp_keys).
+    pub fn with_timestamp_keys(mut self, keys: Vec<String>) -> Self {
+        self.ids.timestamp_keys = keys;
+        self
+    }
+
+    /// Leave these dotted paths out of IDs, as
+    /// [`ArtifactManager::with_volatile_keys`](crate::ArtifactManager::with_volatile_keys).
+    /// The first copy stored is kept.
+    pub fn with_volatile_keys(mut self, keys: Vec<String>) -> Self {
+        self.ids.volatile_keys = keys;
+        self
+    }
+
+    /// Store ID of `artifact`, as
+    /// [`ArtifactManager::content_hash`](crate::ArtifactManager::content_hash).
+    pub fn content_hash(&self, artifact: &serde_json::Value) -> String {
+        self.ids.content_hash(artifact)
+    }
+
+    /// Store an artifact and return its content-addressable ID.
+    ///
+    /// Storing an artifact that is already held, volatile keys aside, is a
+    /// no-op; a different artifact already held under the same ID is an
+    /// `IntegrityFailure`.
+    pub async fn store_artifact(&self, artifact: &serde_json::Value) -> FakResult<String> {
+        let artifact = &*self.ids.normalize(artifact)?;
+        let artifact_id = self.ids.content_hash(artifact);
+        let mut artifacts = self.artifacts.write().await;
+        match artifacts.get(&artifact_id) {
+            Some(held) => self.ids.check_same(&artifact_id, held, artifact)?,
+            None => {
+                Arc::make_mut(&mut artifacts).insert(artifact_id.clone(), artifact.clone());
+            }
+        }
+        Ok(artifact_id)
+    }
+
+    /// Retrieve an artifact by its ID.
+    pub async fn retrieve_artifact(&self, artifact_id: &str) -> FakResult<serde_json::Value> {
+        self.artifacts
+            .read()
+            .await
+            .get(artifact_id)
+            .cloned()
+            .ok_or_else(|| FakError::ArtifactNotFound {
+                artifact_id: artifact_id.to_string(),
+            })
+    }
+
+    /// Retrieve an artifact and deserialize it as `T`; content that does not
+    /// fit `T` is a `Serialization` error.
+    pub async fn retrieve_artifact_as<T: serde::de::DeserializeOwned>(&self, artifact_id: &str) -> FakResult<T> {
+        Ok(serde_json::from_value(self.retrieve_artifact(artifact_id).await?)?)
+    }
+
+    /// Remove an artifact, returning its content.
+    pub async fn remove_artifact(&self, artifact_id: &str) -> FakResult<serde_json::Value> {
+        let mut artifacts = self.artifacts.write().await;
+        if !artifacts.contains_key(artifact_id) {
+            return Err(FakError::ArtifactNotFound {
+                artifact_id: artifact_id.to_string(),
+            });
+        }
+        Ok(Arc::make_mut(&mut artifacts)
+            .remove(artifact_id)
+            .expect("checked above"))
+    }
+
+    pub async fn contains(&self, artifact_id: &str) -> bool {
+        self.artifacts.read().await.contains_key(artifact_id)
+    }
+
+    pub async fn len(&self) -> usize {
+        self.artifacts.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.artifacts.read().await.is_empty()
+    }
+
+    /// Check that `artifact_id` is the ID of `artifact`, hashing with the
+    /// algorithm a tagged ID names.
+    pub fn validate_artifact_integrity(&self, artifact_id: &str, artifact: &serde_json::Value) -> bool {
+        self.ids.matches(artifact_id, artifact)
+    }
+
+    pub async fn clear(&self) {
+        *self.artifacts.write().await = Arc::default();
+    }
+
+    /// Immutable copy of the current contents.
+    pub async fn snapshot(&self) -> ArtifactSnapshot {
+        ArtifactSnapshot::new(Arc::clone(&*self.artifacts.read().await)).with_ids(self.ids.clone())
+    }
+}
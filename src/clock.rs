@@ -0,0 +1,55 @@
+//! Time source for verification deadlines.
+//!
+//! The engine's per-witness timeout and the verifier's bundle budget read
+//! time through [`Clock`]. Native builds use [`SystemClock`]; builds with the
+//! `wasm` feature for `wasm32` use [`NoopClock`], since `std::time::Instant`
+//! is unavailable there, and so run without deadlines.
+
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+/// Monotonic time source.
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Seconds since an arbitrary fixed origin, or `None` when no time
+    /// source is available, which disables deadlines.
+    fn now_secs(&self) -> Option<f64>;
+}
+
+/// Monotonic clock backed by [`Instant`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_secs(&self) -> Option<f64> {
+        static ORIGIN: OnceLock<Instant> = OnceLock::new();
+        Some(ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64())
+    }
+}
+
+/// Clock that never tells the time, so timeouts never fire.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopClock;
+
+impl Clock for NoopClock {
+    fn now_secs(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Clock used by a default [`EngineConfig`](crate::engine::EngineConfig).
+pub fn default_clock() -> Arc<dyn Clock> {
+    if cfg!(all(feature = "wasm", target_arch = "wasm32")) {
+        Arc::new(NoopClock)
+    } else {
+        Arc::new(SystemClock)
+    }
+}
+
+/// Seconds elapsed on `clock` since `started`, or zero without a time source.
+pub(crate) fn elapsed_secs(clock: &dyn Clock, started: Option<f64>) -> f64 {
+    match (started, clock.now_secs()) {
+        (Some(started), Some(now)) => now - started,
+        _ => 0.0,
+    }
+}
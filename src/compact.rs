@@ -0,0 +1,178 @@
+//! Compact bundle representation with deduplicated artifacts.
+
+use crate::artifacts::ArtifactSnapshot;
+use crate::error::{FakError, FakResult};
+use crate::types::{
+    compute_content_hash, CounterExample, InvariantSpec, ProofBundle, ProofWitness,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Witness whose artifacts are content-hash references into the bundle pool.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompactWitness {
+    pub proof_id: String,
+    pub execution_trace: String,
+    pub capability_manifest: String,
+    pub cost_ledger: String,
+    pub policy_ir: String,
+    pub invariants: Vec<InvariantSpec>,
+    pub counterexamples: Vec<CounterExample>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_evaluated: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vacuous: Vec<String>,
+}
+
+/// Proof bundle with artifacts shared between witnesses stored once.
+///
+/// Expanding a compact bundle yields exactly the bundle it was built from,
+/// so the bundle ID and verification outcome are unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompactBundle {
+    pub id: String,
+    /// Artifact pool keyed by content hash.
+    pub artifacts: BTreeMap<String, serde_json::Value>,
+    pub witnesses: Vec<CompactWitness>,
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ProofBundle {
+    /// Hoist embedded artifacts into a pool shared by all witnesses.
+    pub fn compact(&self) -> FakResult<CompactBundle> {
+        let mut artifacts = BTreeMap::new();
+        let witnesses = self.compact_witnesses(|artifact| {
+            let hash = compute_content_hash(&artifact);
+            artifacts.entry(hash.clone()).or_insert(artifact);
+            Ok(hash)
+        })?;
+        Ok(self.compact_bundle(artifacts, witnesses))
+    }
+
+    /// Compact into a thin bundle that relies on `snapshot`.
+    ///
+    /// An artifact `snapshot` holds exactly as embedded is referenced by the
+    /// snapshot's own ID, which for a snapshot of a salted or otherwise
+    /// configured [`ArtifactManager`](crate::ArtifactManager) is not its
+    /// plain content hash, and is left out of the pool. Any other artifact is
+    /// pooled as by [`Self::compact`]. Expand the result with
+    /// [`CompactBundle::expand_from`] and the same snapshot.
+    pub fn compact_against(&self, snapshot: &ArtifactSnapshot) -> FakResult<CompactBundle> {
+        let mut artifacts = BTreeMap::new();
+        let witnesses = self.compact_witnesses(|artifact| {
+            let id = snapshot.artifact_id(&artifact)?;
+            if snapshot.get(&id) == Some(&artifact) {
+                return Ok(id);
+            }
+            let hash = compute_content_hash(&artifact);
+            artifacts.entry(hash.clone()).or_insert(artifact);
+            Ok(hash)
+        })?;
+        Ok(self.compact_bundle(artifacts, witnesses))
+    }
+
+    /// Witnesses with each artifact replaced by the reference `pool`
+    /// returns for it.
+    fn compact_witnesses(
+        &self,
+        mut pool: impl FnMut(serde_json::Value) -> FakResult<String>,
+    ) -> FakResult<Vec<CompactWitness>> {
+        let mut witnesses = Vec::with_capacity(self.witnesses.len());
+        for w in &self.witnesses {
+            witnesses.push(CompactWitness {
+                proof_id: w.proof_id.clone(),
+                execution_trace: pool(serde_json::to_value(&w.execution_trace)?)?,
+                capability_manifest: pool(serde_json::to_value(&w.capability_manifest)?)?,
+                cost_ledger: pool(serde_json::to_value(&w.cost_ledger)?)?,
+                policy_ir: pool(serde_json::to_value(&w.policy_ir)?)?,
+                invariants: w.invariants.clone(),
+                counterexamples: w.counterexamples.clone(),
+                not_evaluated: w.not_evaluated.clone(),
+                vacuous: w.vacuous.clone(),
+            });
+        }
+        Ok(witnesses)
+    }
+
+    fn compact_bundle(
+        &self,
+        artifacts: BTreeMap<String, serde_json::Value>,
+        witnesses: Vec<CompactWitness>,
+    ) -> CompactBundle {
+        CompactBundle {
+            id: self.id.clone(),
+            artifacts,
+            witnesses,
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+impl CompactBundle {
+    /// Rebuild the full bundle, checking every pooled artifact against its hash.
+    pub fn expand(&self) -> FakResult<ProofBundle> {
+        self.expand_with(None)
+    }
+
+    /// Rebuild the full bundle, resolving references missing from the pool
+    /// in `snapshot`.
+    ///
+    /// A thin bundle can ship with an empty pool and rely on the store the
+    /// verifier reads from (see [`ProofBundle::compact_against`]). Artifacts
+    /// found there are checked against the IDs the snapshot's manager
+    /// assigns, so snapshots of salted managers resolve too.
+    pub fn expand_from(&self, snapshot: &ArtifactSnapshot) -> FakResult<ProofBundle> {
+        self.expand_with(Some(snapshot))
+    }
+
+    fn expand_with(&self, snapshot: Option<&ArtifactSnapshot>) -> FakResult<ProofBundle> {
+        let mut witnesses = Vec::with_capacity(self.witnesses.len());
+        for w in &self.witnesses {
+            witnesses.push(ProofWitness {
+                proof_id: w.proof_id.clone(),
+                execution_trace: self.resolve(&w.execution_trace, snapshot)?,
+                capability_manifest: self.resolve(&w.capability_manifest, snapshot)?,
+                cost_ledger: self.resolve(&w.cost_ledger, snapshot)?,
+                policy_ir: self.resolve(&w.policy_ir, snapshot)?,
+                invariants: w.invariants.clone(),
+                counterexamples: w.counterexamples.clone(),
+                not_evaluated: w.not_evaluated.clone(),
+                vacuous: w.vacuous.clone(),
+            });
+        }
+
+        Ok(ProofBundle::new(self.id.clone(), witnesses, self.metadata.clone()))
+    }
+
+    fn resolve<T: DeserializeOwned>(
+        &self,
+        artifact_id: &str,
+        snapshot: Option<&ArtifactSnapshot>,
+    ) -> FakResult<T> {
+        // Pooled artifacts are keyed by plain content hash; snapshot ones by
+        // the ID scheme of the manager the snapshot came from.
+        let (artifact, valid) = match self.artifacts.get(artifact_id) {
+            Some(artifact) => (artifact, compute_content_hash(artifact) == artifact_id),
+            None => {
+                let snapshot = snapshot.ok_or_else(|| not_found(artifact_id))?;
+                let artifact = snapshot.get(artifact_id).ok_or_else(|| not_found(artifact_id))?;
+                (artifact, snapshot.id_matches(artifact_id, artifact))
+            }
+        };
+        if !valid {
+            return Err(FakError::IntegrityFailure {
+                artifact_id: artifact_id.to_string(),
+                expected: artifact_id.to_string(),
+                actual: compute_content_hash(artifact),
+            });
+        }
+        Ok(serde_json::from_value(artifact.clone())?)
+    }
+}
+
+fn not_found(artifact_id: &str) -> FakError {
+    FakError::ArtifactNotFound {
+        artifact_id: artifact_id.to_string(),
+    }
+}
@@ -10,35 +10,111 @@ use std::sync::OnceLock;
 #[derive(Debug, Clone, PartialEq)]
 pub struct TemporalProperty {
     pub operator: String,
+    /// Operand text: the single operand of a unary operator, or `p until q`
+    /// as written for `until`.
     pub expression: String,
+    /// First operand (the only one for `always`, `eventually` and `next`).
+    pub left: String,
+    /// Second operand of a binary operator (`q` in `p until q`).
+    pub right: Option<String>,
+    /// Step bound of a bounded operator (`N` in `eventually[N] p`).
+    pub bound: Option<usize>,
 }
 
 /// DSL parser for invariant specifications.
 #[derive(Debug, Clone, Default)]
 pub struct InvariantDSL;
 
+/// Parser settings for [`InvariantDSL::parse_invariant_with_config`] and
+/// [`InvariantDSL::parse_invariants_with_config`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DslConfig {
+    /// Marker starting a comment that runs to the end of the line, unless
+    /// it appears inside a quoted string. Empty disables comments.
+    pub comment_prefix: String,
+}
+
+impl Default for DslConfig {
+    fn default() -> Self {
+        Self {
+            comment_prefix: "#".to_string(),
+        }
+    }
+}
+
 static INVARIANT_RE: OnceLock<Regex> = OnceLock::new();
 static TYPE_RE: OnceLock<Regex> = OnceLock::new();
+static VAR_RE: OnceLock<Regex> = OnceLock::new();
 
 fn get_invariant_re() -> &'static Regex {
-    INVARIANT_RE.get_or_init(|| Regex::new(r"invariant\s+(\w+)").expect("valid regex"))
+    INVARIANT_RE.get_or_init(|| Regex::new(r"invariant[ \t]+(\w+)").expect("valid regex"))
 }
 
 fn get_type_re() -> &'static Regex {
     TYPE_RE.get_or_init(|| Regex::new(r"type:\s*(\w+)").expect("valid regex"))
 }
 
+fn get_var_re() -> &'static Regex {
+    VAR_RE.get_or_init(|| Regex::new(r"\$\{(\w+)\}").expect("valid regex"))
+}
+
 impl InvariantDSL {
     /// Parse an invariant specification from DSL text.
+    ///
+    /// A missing `invariant <name>` declaration is reported with the line
+    /// and column where it was expected.
     pub fn parse_invariant(spec_str: &str) -> FakResult<InvariantSpec> {
-        let spec_str_clean = Self::strip_comments(spec_str);
-        let name = Self::extract_name(&spec_str_clean)?;
-        let fields = Self::extract_fields(&spec_str_clean);
+        Self::parse_invariant_inner(spec_str, None, &DslConfig::default())
+    }
+
+    /// Parse an invariant specification using `config`'s comment prefix.
+    pub fn parse_invariant_with_config(spec_str: &str, config: &DslConfig) -> FakResult<InvariantSpec> {
+        Self::parse_invariant_inner(spec_str, None, config)
+    }
+
+    /// Parse an invariant specification, replacing each `${NAME}` in field
+    /// values with `vars["NAME"]`.
+    ///
+    /// A variable missing from `vars` is a `ParseError` naming it, positioned
+    /// at its first use.
+    pub fn parse_invariant_with_vars(
+        spec_str: &str,
+        vars: &HashMap<String, String>,
+    ) -> FakResult<InvariantSpec> {
+        Self::parse_invariant_inner(spec_str, Some(vars), &DslConfig::default())
+    }
+
+    fn parse_invariant_inner(
+        spec_str: &str,
+        vars: Option<&HashMap<String, String>>,
+        config: &DslConfig,
+    ) -> FakResult<InvariantSpec> {
+        let prefix = config.comment_prefix.as_str();
+        let spec_str_clean = Self::strip_comments(spec_str, prefix);
+        let name = Self::extract_name(&spec_str_clean)
+            .map_err(|_| Self::missing_name_error(spec_str, prefix))?;
+        let mut fields = Self::extract_fields(&spec_str_clean);
+        if let Some(vars) = vars {
+            for value in fields.values_mut() {
+                *value = Self::interpolate(spec_str, value, vars)?;
+            }
+        }
         let temporal_properties = Self::parse_temporal_properties_list(
             fields.get("temporal_properties").map(|s| s.as_str()),
         );
         let invariant_type = Self::extract_type(&spec_str_clean)
             .unwrap_or(ProofType::BehavioralSoundness);
+        let tolerance = fields
+            .get("tolerance")
+            .map(|t| {
+                t.parse::<f64>().map_err(|_| FakError::ParseError {
+                    source: "invariant_spec".to_string(),
+                    message: format!("invalid tolerance '{}'", t),
+                    line: None,
+                    column: None,
+                })
+            })
+            .transpose()?;
 
         Ok(InvariantSpec {
             name,
@@ -47,18 +123,75 @@ impl InvariantDSL {
             postcondition: fields.get("postcondition").cloned(),
             temporal_properties,
             invariant_type,
+            remediation: fields.get("remediation").cloned(),
+            gating: fields.get("gating").is_some_and(|v| v == "true"),
+            check_causality: fields.get("causality").is_some_and(|v| v == "true"),
+            tolerance,
+            rate_limit: None,
+            external_discharge: None,
+            compiled_pre: Default::default(),
+            compiled_post: Default::default(),
         })
     }
 
-    fn strip_comments(spec_str: &str) -> String {
+    /// Parse every invariant in a document.
+    ///
+    /// Each `invariant <name>` declaration starts a new block that runs to
+    /// the next declaration. Blocks are parsed and validated independently,
+    /// so one bad block does not hide errors in the others: all failures are
+    /// returned together as one `ParseError`, each prefixed with the line of
+    /// its block's declaration. Comments and blank lines may appear anywhere;
+    /// anything else before the first declaration is an error.
+    pub fn parse_invariants(doc: &str) -> FakResult<Vec<InvariantSpec>> {
+        Self::parse_invariants_with_config(doc, &DslConfig::default())
+    }
+
+    /// Parse every invariant in a document using `config`'s comment prefix.
+    pub fn parse_invariants_with_config(doc: &str, config: &DslConfig) -> FakResult<Vec<InvariantSpec>> {
+        let mut blocks: Vec<(usize, Vec<&str>)> = Vec::new();
+        let mut errors = Vec::new();
+        for (i, line) in doc.lines().enumerate() {
+            let content = strip_line_comment(line, &config.comment_prefix).trim();
+            if get_invariant_re().find(content).is_some_and(|m| m.start() == 0) {
+                blocks.push((i + 1, Vec::new()));
+            }
+            match blocks.last_mut() {
+                Some((_, lines)) => lines.push(line),
+                None if content.is_empty() => {}
+                None => errors.push(format!("line {}: expected an invariant declaration", i + 1)),
+            }
+        }
+
+        let mut invariants = Vec::new();
+        for (line, lines) in blocks {
+            match Self::parse_invariant_with_config(&lines.join("\n"), config).and_then(|spec| spec.validate().map(|()| spec)) {
+                Ok(spec) => invariants.push(spec),
+                Err(e) => {
+                    let name = Self::extract_name(lines[0].trim()).unwrap_or_default();
+                    let message = match e {
+                        FakError::ParseError { message, .. } => message,
+                        other => other.to_string(),
+                    };
+                    errors.push(format!("line {} (invariant '{}'): {}", line, name, message));
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(FakError::ParseError {
+                source: "invariant_document".to_string(),
+                message: errors.join("; "),
+                line: None,
+                column: None,
+            });
+        }
+        Ok(invariants)
+    }
+
+    fn strip_comments(spec_str: &str, prefix: &str) -> String {
         spec_str
             .lines()
             .filter_map(|line| {
-                let trimmed = if let Some(pos) = line.find('#') {
-                    line[..pos].trim()
-                } else {
-                    line.trim()
-                };
+                let trimmed = strip_line_comment(line, prefix).trim();
                 if trimmed.is_empty() { None } else { Some(trimmed) }
             })
             .collect::<Vec<_>>()
@@ -73,9 +206,70 @@ impl InvariantDSL {
             .ok_or_else(|| FakError::ParseError {
                 source: "invariant_spec".to_string(),
                 message: "missing invariant name declaration".to_string(),
+                line: None,
+                column: None,
             })
     }
 
+    /// Error for a spec without a name declaration, positioned after a bare
+    /// `invariant` keyword, or else at the first line with content.
+    fn missing_name_error(spec_str: &str, prefix: &str) -> FakError {
+        let content_lines = spec_str.lines().enumerate().filter_map(|(i, line)| {
+            let content = strip_line_comment(line, prefix);
+            let indent = content.chars().take_while(|c| c.is_whitespace()).count();
+            let content = content.trim();
+            (!content.is_empty()).then_some((i + 1, indent, content))
+        });
+        let mut first = None;
+        for (line, indent, content) in content_lines {
+            let bare_keyword = content
+                .strip_prefix("invariant")
+                .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'));
+            if bare_keyword {
+                return FakError::ParseError {
+                    source: "invariant_spec".to_string(),
+                    message: "expected a name after 'invariant'".to_string(),
+                    line: Some(line),
+                    column: Some(indent + "invariant".len() + 1),
+                };
+            }
+            first.get_or_insert((line, indent + 1));
+        }
+        FakError::ParseError {
+            source: "invariant_spec".to_string(),
+            message: "missing invariant name declaration".to_string(),
+            line: first.map(|(line, _)| line),
+            column: first.map(|(_, column)| column),
+        }
+    }
+
+    /// Replace every `${NAME}` in `value` from `vars`.
+    fn interpolate(spec_str: &str, value: &str, vars: &HashMap<String, String>) -> FakResult<String> {
+        let mut out = String::with_capacity(value.len());
+        let mut last = 0;
+        for caps in get_var_re().captures_iter(value) {
+            let token = caps.get(0).expect("whole match");
+            let var = &caps[1];
+            let replacement = vars.get(var).ok_or_else(|| {
+                let position = spec_str.lines().enumerate().find_map(|(i, line)| {
+                    line.find(token.as_str())
+                        .map(|byte| (i + 1, line[..byte].chars().count() + 1))
+                });
+                FakError::ParseError {
+                    source: "invariant_spec".to_string(),
+                    message: format!("undefined variable '{}'", var),
+                    line: position.map(|(line, _)| line),
+                    column: position.map(|(_, column)| column),
+                }
+            })?;
+            out.push_str(&value[last..token.start()]);
+            out.push_str(replacement);
+            last = token.end();
+        }
+        out.push_str(&value[last..]);
+        Ok(out)
+    }
+
     fn extract_type(spec_str: &str) -> Option<ProofType> {
         get_type_re()
             .captures(spec_str)
@@ -85,7 +279,16 @@ impl InvariantDSL {
 
     fn extract_fields(spec_str: &str) -> HashMap<String, String> {
         let mut fields = HashMap::new();
-        for field_name in &["description", "precondition", "postcondition", "temporal_properties"] {
+        for field_name in &[
+            "description",
+            "precondition",
+            "postcondition",
+            "temporal_properties",
+            "remediation",
+            "gating",
+            "causality",
+            "tolerance",
+        ] {
             if let Some(value) = Self::extract_field_value(spec_str, field_name) {
                 fields.insert(field_name.to_string(), value);
             }
@@ -93,13 +296,42 @@ impl InvariantDSL {
         fields
     }
 
+    /// Value of `field_name`, continued onto following lines up to the next
+    /// line that starts a field, a `type:` or an `invariant` declaration.
+    /// A continued value has its whitespace collapsed to single spaces.
     fn extract_field_value(spec_str: &str, field_name: &str) -> Option<String> {
-        let pattern = format!(r"{}:\s*(.+)", field_name);
-        Regex::new(&pattern)
-            .ok()?
-            .captures(spec_str)?
-            .get(1)
-            .map(|m| m.as_str().trim().to_string())
+        let pattern = format!(r"{}:[ \t]*(.+)", field_name);
+        let first = Regex::new(&pattern).ok()?.captures(spec_str)?.get(1)?;
+        let value = first.as_str().trim();
+        let continuation: Vec<&str> = spec_str[first.end()..]
+            .lines()
+            .skip(1)
+            .take_while(|line| !Self::starts_keyword(line))
+            .collect();
+        if continuation.is_empty() {
+            return Some(value.to_string());
+        }
+        Some(Self::normalize(&format!("{} {}", value, continuation.join(" "))))
+    }
+
+    fn starts_keyword(line: &str) -> bool {
+        const KEYWORDS: [&str; 9] = [
+            "description",
+            "precondition",
+            "postcondition",
+            "temporal_properties",
+            "remediation",
+            "gating",
+            "causality",
+            "tolerance",
+            "type",
+        ];
+        let line = line.trim_start();
+        get_invariant_re().find(line).is_some_and(|m| m.start() == 0)
+            || KEYWORDS.iter().any(|k| {
+                line.strip_prefix(k)
+                    .is_some_and(|rest| rest.trim_start().starts_with(':'))
+            })
     }
 
     fn parse_temporal_properties_list(props_str: Option<&str>) -> Vec<String> {
@@ -115,28 +347,171 @@ impl InvariantDSL {
         }
     }
 
-    /// Parse a temporal property expression into operator and expression.
+    /// Collapse runs of whitespace so equivalent spellings compare equal.
+    fn normalize(expr: &str) -> String {
+        expr.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Find invariants that duplicate an earlier one under another name.
+    ///
+    /// Two invariants are duplicates when every field that can affect a
+    /// verdict matches: type, conditions and temporal properties once
+    /// whitespace is normalized, and tolerance, gating, causality, rate
+    /// limit and external discharge exactly. Only the name, description and
+    /// remediation may differ. Each pair is `(earlier, duplicate)`.
+    /// Logical implication between different conditions is not detected.
+    /// An invariant that cannot be serialized is a `Serialization` error.
+    pub fn find_redundant(invariants: &[InvariantSpec]) -> FakResult<Vec<(String, String)>> {
+        let mut first_by_key: HashMap<String, &str> = HashMap::new();
+        let mut pairs = Vec::new();
+        for inv in invariants {
+            let mut temporal: Vec<String> =
+                inv.temporal_properties.iter().map(|p| Self::normalize(p)).collect();
+            temporal.sort();
+            let normalized = InvariantSpec {
+                name: String::new(),
+                description: String::new(),
+                precondition: inv.precondition.as_deref().map(Self::normalize),
+                postcondition: inv.postcondition.as_deref().map(Self::normalize),
+                temporal_properties: temporal,
+                remediation: None,
+                ..inv.clone()
+            };
+            let key = serde_json::to_string(&normalized)?;
+            match first_by_key.get(key.as_str()) {
+                Some(first) if *first != inv.name => {
+                    pairs.push((first.to_string(), inv.name.clone()));
+                }
+                Some(_) => {}
+                None => {
+                    first_by_key.insert(key, &inv.name);
+                }
+            }
+        }
+        Ok(pairs)
+    }
+
+    /// Parse a temporal property expression into operator and operands.
+    ///
+    /// `always`, `eventually` and `next` are unary prefixes; `until` is
+    /// binary infix (`p until q`) and needs both operands. `eventually[N] p`
+    /// and its synonym `within N p` bound `eventually` to `N` steps.
     pub fn parse_temporal_property(prop_str: &str) -> FakResult<TemporalProperty> {
         let trimmed = prop_str.trim();
-        let operators = ["always", "eventually", "until", "next"];
+        if let Some(rest) = trimmed.strip_prefix("within ") {
+            let rest = rest.trim_start();
+            let (bound, expr) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            return unary_property("eventually", expr.trim(), Some(parse_bound(bound)?));
+        }
+        let operators = ["always", "eventually", "next"];
         for op in &operators {
-            if let Some(rest) = trimmed.strip_prefix(op) {
-                let expr = rest.trim();
-                if expr.is_empty() {
-                    return Err(FakError::ParseError {
+            let Some(rest) = trimmed.strip_prefix(op) else { continue };
+            if rest.chars().next().is_some_and(is_word_char) {
+                continue;
+            }
+            let mut bound = None;
+            let mut rest = rest;
+            if *op == "eventually" {
+                if let Some(bracketed) = rest.strip_prefix('[') {
+                    let (n, after) = bracketed.split_once(']').ok_or_else(|| FakError::ParseError {
                         source: "temporal_property".to_string(),
-                        message: format!("operator '{}' requires an expression", op),
-                    });
+                        message: format!("unclosed bound in: {}", trimmed),
+                        line: None,
+                        column: None,
+                    })?;
+                    bound = Some(parse_bound(n)?);
+                    rest = after;
                 }
-                return Ok(TemporalProperty {
-                    operator: op.to_string(),
-                    expression: expr.to_string(),
+            }
+            return unary_property(op, rest.trim(), bound);
+        }
+        if let Some(idx) = until_position(trimmed) {
+            let left = trimmed[..idx].trim();
+            let right = trimmed[idx + "until".len()..].trim();
+            if left.is_empty() || right.is_empty() {
+                return Err(FakError::ParseError {
+                    source: "temporal_property".to_string(),
+                    message: format!("operator 'until' requires two operands in: {}", trimmed),
+                    line: None,
+                    column: None,
                 });
             }
+            return Ok(TemporalProperty {
+                operator: "until".to_string(),
+                expression: trimmed.to_string(),
+                left: left.to_string(),
+                right: Some(right.to_string()),
+                bound: None,
+            });
         }
         Err(FakError::ParseError {
             source: "temporal_property".to_string(),
             message: format!("unknown temporal operator in: {}", trimmed),
+            line: None,
+            column: None,
         })
     }
-}
\ No newline at end of file
+}
+
+/// `line` up to the first `prefix` outside a quoted string. A quote with no
+/// closing partner on the line (an apostrophe) does not start a string.
+fn strip_line_comment<'a>(line: &'a str, prefix: &str) -> &'a str {
+    if prefix.is_empty() {
+        return line;
+    }
+    let mut i = 0;
+    while let Some(c) = line[i..].chars().next() {
+        if line[i..].starts_with(prefix) {
+            return &line[..i];
+        }
+        if c == '"' || c == '\'' {
+            if let Some(end) = line[i + 1..].find(c) {
+                i += end + 2;
+                continue;
+            }
+        }
+        i += c.len_utf8();
+    }
+    line
+}
+
+fn unary_property(op: &str, expr: &str, bound: Option<usize>) -> FakResult<TemporalProperty> {
+    if expr.is_empty() {
+        return Err(FakError::ParseError {
+            source: "temporal_property".to_string(),
+            message: format!("operator '{}' requires an expression", op),
+            line: None,
+            column: None,
+        });
+    }
+    Ok(TemporalProperty {
+        operator: op.to_string(),
+        expression: expr.to_string(),
+        left: expr.to_string(),
+        right: None,
+        bound,
+    })
+}
+
+/// Step bound of a bounded operator; must be a non-negative integer.
+fn parse_bound(text: &str) -> FakResult<usize> {
+    text.trim().parse().map_err(|_| FakError::ParseError {
+        source: "temporal_property".to_string(),
+        message: format!("temporal bound '{}' is not a non-negative integer", text.trim()),
+        line: None,
+        column: None,
+    })
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Byte offset of the first standalone `until` keyword in `s`.
+fn until_position(s: &str) -> Option<usize> {
+    s.match_indices("until").map(|(i, _)| i).find(|&i| {
+        let before = s[..i].chars().next_back();
+        let after = s[i + "until".len()..].chars().next();
+        !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+    })
+}
@@ -0,0 +1,94 @@
+//! Binary transport encodings for proof bundles.
+//!
+//! Encodings only change how a bundle travels; bundle and proof IDs are
+//! always computed over canonical JSON, so decoding re-derives the bundle ID
+//! and rejects payloads whose contents no longer match it.
+
+#[cfg(any(feature = "binary", feature = "cbor", feature = "compression"))]
+use crate::error::{FakError, FakResult};
+#[cfg(any(feature = "binary", feature = "cbor", feature = "compression"))]
+use crate::types::ProofBundle;
+
+#[cfg(feature = "binary")]
+impl ProofBundle {
+    /// Encode the bundle as MessagePack.
+    pub fn to_bytes(&self) -> FakResult<Vec<u8>> {
+        rmp_serde::to_vec_named(self).map_err(|e| FakError::Serialization {
+            message: e.to_string(),
+        })
+    }
+
+    /// Decode a MessagePack bundle and verify its ID against the contents.
+    pub fn from_bytes(bytes: &[u8]) -> FakResult<ProofBundle> {
+        let bundle: ProofBundle = rmp_serde::from_slice(bytes).map_err(|e| FakError::Serialization {
+            message: e.to_string(),
+        })?;
+        bundle.verify_id()?;
+        Ok(bundle)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl ProofBundle {
+    /// Encode the bundle as CBOR.
+    pub fn to_cbor(&self) -> FakResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes).map_err(|e| FakError::Serialization {
+            message: e.to_string(),
+        })?;
+        Ok(bytes)
+    }
+
+    /// Decode a CBOR bundle and verify its ID against the contents.
+    pub fn from_cbor(bytes: &[u8]) -> FakResult<ProofBundle> {
+        let bundle: ProofBundle = ciborium::from_reader(bytes).map_err(|e| FakError::Serialization {
+            message: e.to_string(),
+        })?;
+        bundle.verify_id()?;
+        Ok(bundle)
+    }
+}
+
+#[cfg(feature = "compression")]
+impl ProofBundle {
+    /// Largest decompressed payload [`Self::from_compressed`] accepts.
+    pub const MAX_DECOMPRESSED_BYTES: usize = 256 * 1024 * 1024;
+
+    /// zstd-compress the bundle's JSON form.
+    pub fn to_compressed(&self) -> FakResult<Vec<u8>> {
+        zstd::encode_all(self.to_json()?.as_bytes(), 0).map_err(|e| FakError::Serialization {
+            message: e.to_string(),
+        })
+    }
+
+    /// Decompress a [`Self::to_compressed`] payload and verify the bundle ID
+    /// against the contents. Payloads that decompress to more than
+    /// [`Self::MAX_DECOMPRESSED_BYTES`] are rejected.
+    pub fn from_compressed(bytes: &[u8]) -> FakResult<ProofBundle> {
+        use std::io::Read;
+
+        let decoder = zstd::Decoder::new(bytes).map_err(|e| FakError::Serialization {
+            message: e.to_string(),
+        })?;
+        let mut json = Vec::new();
+        decoder
+            .take(Self::MAX_DECOMPRESSED_BYTES as u64 + 1)
+            .read_to_end(&mut json)
+            .map_err(|e| FakError::Serialization {
+                message: e.to_string(),
+            })?;
+        if json.len() > Self::MAX_DECOMPRESSED_BYTES {
+            return Err(FakError::ResourceLimit {
+                resource: "decompressed_bytes".to_string(),
+                limit: Self::MAX_DECOMPRESSED_BYTES,
+                actual: json.len(),
+            });
+        }
+        let json = std::str::from_utf8(&json).map_err(|e| FakError::Serialization {
+            message: e.to_string(),
+        })?;
+        let bundle = ProofBundle::from_json(json)?;
+        bundle.verify_id()?;
+        Ok(bundle)
+    }
+}
@@ -1,16 +1,164 @@
 //! Proof engine for FAK.
 use crate::error::{FakError, FakResult};
+use crate::expr::{
+    evaluate_traced, is_namespaced, resolve_path, EvaluationStep, Expr, ExpressionContext,
+};
+use crate::clock::{default_clock, elapsed_secs, Clock};
+use crate::functions::FunctionRegistry;
+use crate::metadata::{normalize_artifact_timestamps, normalize_timestamps};
 use crate::types::{
-    CapabilityManifest, CostLedger, CounterExample, ExecutionTrace, InvariantSpec,
-    PolicyIR, ProofBundle, ProofType, ProofWitness, compute_content_hash,
+    CapabilityManifest, CostLedger, CounterExample, ExecutionTrace, ExternalProof, InvariantSpec,
+    PolicyIR, ProofBundle, ProofType, ProofWitness, RateLimit, VerificationContext,
+    compute_content_hash, compute_content_hash_streaming,
 };
-use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+type Hook<T> = Arc<dyn Fn(&T) -> FakResult<()> + Send + Sync>;
+
+/// Caller-supplied validation run after each artifact's built-in `validate`.
+///
+/// Hooks for an artifact type run in registration order and the first error
+/// is returned unchanged, so deployments can enforce their own constraints
+/// (say, a required trace metadata key) without changing the type definitions.
+#[derive(Clone, Default)]
+pub struct ValidationHooks {
+    trace: Vec<Hook<ExecutionTrace>>,
+    capabilities: Vec<Hook<CapabilityManifest>>,
+    cost_ledger: Vec<Hook<CostLedger>>,
+    policy_ir: Vec<Hook<PolicyIR>>,
+}
+
+impl ValidationHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_trace<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ExecutionTrace) -> FakResult<()> + Send + Sync + 'static,
+    {
+        self.trace.push(Arc::new(hook));
+        self
+    }
+
+    pub fn on_capabilities<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&CapabilityManifest) -> FakResult<()> + Send + Sync + 'static,
+    {
+        self.capabilities.push(Arc::new(hook));
+        self
+    }
+
+    pub fn on_cost_ledger<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&CostLedger) -> FakResult<()> + Send + Sync + 'static,
+    {
+        self.cost_ledger.push(Arc::new(hook));
+        self
+    }
+
+    pub fn on_policy_ir<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&PolicyIR) -> FakResult<()> + Send + Sync + 'static,
+    {
+        self.policy_ir.push(Arc::new(hook));
+        self
+    }
+
+    /// Run every registered hook against the context's artifacts.
+    pub fn check(&self, ctx: &VerificationContext) -> FakResult<()> {
+        run_hooks(&self.trace, ctx.trace)?;
+        run_hooks(&self.capabilities, ctx.capabilities)?;
+        run_hooks(&self.cost_ledger, ctx.cost_ledger)?;
+        run_hooks(&self.policy_ir, ctx.policy_ir)
+    }
+}
+
+fn run_hooks<T>(hooks: &[Hook<T>], artifact: &T) -> FakResult<()> {
+    hooks.iter().try_for_each(|hook| hook(artifact))
+}
+
+/// Content hash of an artifact whose `timestamp_keys` metadata is first
+/// brought to the canonical form.
+fn content_hash_of<T: Serialize>(artifact: &T, timestamp_keys: &[String]) -> FakResult<String> {
+    let mut value = serde_json::to_value(artifact)?;
+    normalize_artifact_timestamps(&mut value, timestamp_keys)?;
+    Ok(compute_content_hash(&value))
+}
+
+impl fmt::Debug for ValidationHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidationHooks")
+            .field("trace", &self.trace.len())
+            .field("capabilities", &self.capabilities.len())
+            .field("cost_ledger", &self.cost_ledger.len())
+            .field("policy_ir", &self.policy_ir.len())
+            .finish()
+    }
+}
 
 /// Configuration for proof engine resource limits.
 #[derive(Debug, Clone)]
 pub struct EngineConfig {
+    /// Invariant count limit per witness. Not material: exceeding it is an
+    /// error rather than a verdict.
     pub max_invariants: usize,
     pub timeout_secs: f64,
+    /// Absolute tolerance for floating-point cost comparisons, including the
+    /// conditions of economic invariants. Other invariants compare exactly
+    /// unless they set their own tolerance.
+    pub cost_tolerance: f64,
+    /// Authority edge limit applied when validating capability manifests.
+    /// Not material: exceeding it is an error rather than a verdict.
+    pub max_authority_edges: usize,
+    /// Trace step limit, enforced before any invariant is checked. Cannot
+    /// exceed [`ExecutionTrace::MAX_STEPS`], which trace validation applies
+    /// regardless. Not material, like `max_authority_edges`.
+    pub max_trace_steps: usize,
+    /// Extra artifact validation; not part of the material summary.
+    pub hooks: ValidationHooks,
+    /// Functions callable from invariant expressions, shared by clones of
+    /// this config. Material by name and version.
+    pub functions: FunctionRegistry,
+    /// Time source for `timeout_secs`; not part of the material summary.
+    pub clock: Arc<dyn Clock>,
+    /// Metadata keys brought to the canonical timestamp form (see
+    /// [`crate::metadata`]) wherever the engine hashes: artifact metadata
+    /// for proof IDs and merged bundle metadata for bundle IDs. Verifiers
+    /// apply the same keys when re-deriving IDs. Material, since it decides
+    /// which IDs verify.
+    pub timestamp_keys: Vec<String>,
+}
+
+impl EngineConfig {
+    /// Bundle metadata key under which generated bundles pin their config.
+    pub const METADATA_KEY: &'static str = "engine_config";
+
+    /// Settings that can change a verdict, as recorded in bundle metadata.
+    ///
+    /// `cost_tolerance` decides economic comparisons, `functions` (see
+    /// [`FunctionRegistry::summary`]) what expressions can call and
+    /// `timestamp_keys` how IDs are derived. Timeouts and resource limits
+    /// such as `max_invariants` are not material: they bound how much
+    /// checking may happen, and exceeding one is reported as an error
+    /// rather than a different conclusion.
+    pub fn material_summary(&self) -> serde_json::Value {
+        serde_json::json!({
+            "cost_tolerance": self.cost_tolerance,
+            "functions": self.functions.summary(),
+            "timestamp_keys": self.timestamp_keys,
+        })
+    }
+
+    /// Content hash of [`material_summary`](Self::material_summary).
+    pub fn fingerprint(&self) -> String {
+        compute_content_hash(&self.material_summary())
+    }
 }
 
 impl Default for EngineConfig {
@@ -18,12 +166,186 @@ impl Default for EngineConfig {
         Self {
             max_invariants: 1000,
             timeout_secs: 30.0,
+            cost_tolerance: 1e-9,
+            max_authority_edges: CapabilityManifest::MAX_AUTHORITY_EDGES,
+            max_trace_steps: ExecutionTrace::MAX_STEPS,
+            hooks: ValidationHooks::default(),
+            functions: FunctionRegistry::default(),
+            clock: default_clock(),
+            timestamp_keys: Vec::new(),
+        }
+    }
+}
+
+/// Expression scope for economic invariants: bare names resolve against the
+/// cost ledger, namespaced names against the whole context.
+struct LedgerScope<'a, 'c>(&'a VerificationContext<'c>);
+
+impl ExpressionContext for LedgerScope<'_, '_> {
+    /// Bare names are `total_cost`, `entry_count`, `entries` and `id`, and
+    /// otherwise the ledger metadata key of that name (such as `budget`).
+    fn resolve(&self, name: &str) -> FakResult<Option<serde_json::Value>> {
+        if name.contains('.') {
+            return self.0.resolve(name);
+        }
+        let ledger = self.0.cost_ledger;
+        Ok(match name {
+            "total_cost" => Some(serde_json::json!(ledger.total_cost)),
+            "entry_count" => Some(serde_json::json!(ledger.entries.len())),
+            "entries" => Some(serde_json::Value::Array(ledger.entries.clone())),
+            "id" => Some(serde_json::Value::String(ledger.id.clone())),
+            _ => ledger.metadata.get(name).cloned(),
+        })
+    }
+}
+
+/// Expression scope for one trace step of a behavioral invariant: names
+/// outside the context namespaces resolve against the step's fields.
+struct StepScope<'a, 'c> {
+    ctx: &'a VerificationContext<'c>,
+    step: &'a serde_json::Value,
+}
+
+impl ExpressionContext for StepScope<'_, '_> {
+    fn resolve(&self, name: &str) -> FakResult<Option<serde_json::Value>> {
+        if is_namespaced(name) {
+            return self.ctx.resolve(name);
+        }
+        Ok(resolve_path(self.step, name))
+    }
+}
+
+/// Which trace steps were read by at least one invariant check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub total_steps: usize,
+    /// Inclusive `(first, last)` ranges of steps that were read.
+    pub inspected: Vec<(usize, usize)>,
+    /// Inclusive `(first, last)` ranges of steps no invariant read.
+    pub uninspected: Vec<(usize, usize)>,
+}
+
+impl CoverageReport {
+    fn from_flags(flags: &[bool]) -> Self {
+        let mut inspected = Vec::new();
+        let mut uninspected = Vec::new();
+        let mut start = 0;
+        for i in 1..=flags.len() {
+            if i == flags.len() || flags[i] != flags[start] {
+                let range = (start, i - 1);
+                if flags[start] {
+                    inspected.push(range);
+                } else {
+                    uninspected.push(range);
+                }
+                start = i;
+            }
+        }
+        Self {
+            total_steps: flags.len(),
+            inspected,
+            uninspected,
+        }
+    }
+
+    /// True when every step was read by some invariant.
+    pub fn is_complete(&self) -> bool {
+        self.uninspected.is_empty()
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges = |ranges: &[(usize, usize)]| {
+            ranges
+                .iter()
+                .map(|(a, b)| if a == b { a.to_string() } else { format!("{}-{}", a, b) })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match (self.inspected.is_empty(), self.uninspected.is_empty()) {
+            (true, true) => write!(f, "trace has no steps"),
+            (false, true) => write!(f, "steps {} inspected", ranges(&self.inspected)),
+            (true, false) => write!(f, "steps {} never read by any invariant", ranges(&self.uninspected)),
+            (false, false) => write!(
+                f,
+                "steps {} inspected, {} never read by any invariant",
+                ranges(&self.inspected),
+                ranges(&self.uninspected)
+            ),
+        }
+    }
+}
+
+/// Step-by-step account of why an invariant failed, from
+/// [`ProofEngine::explain_counterexample`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explanation {
+    pub invariant_name: String,
+    pub error_type: String,
+    /// Evaluation of the precondition and then the postcondition, for those
+    /// the invariant declares. Evaluation stops at a false precondition.
+    pub conditions: Vec<ConditionTrace>,
+    /// Whether re-checking the invariant produced the same counterexample.
+    pub reproduced: bool,
+}
+
+/// Evaluation of one condition expression.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConditionTrace {
+    /// `"precondition"` or `"postcondition"`.
+    pub role: String,
+    pub condition: String,
+    /// Sub-expression values, innermost first.
+    pub steps: Vec<EvaluationStep>,
+    /// Final value, or `None` if evaluation failed.
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.invariant_name, self.error_type)?;
+        if !self.reproduced {
+            write!(f, " [not reproduced]")?;
+        }
+        for condition in &self.conditions {
+            write!(f, "\n  {}: {}", condition.role, condition.condition)?;
+            for step in &condition.steps {
+                write!(f, "\n    {}", step)?;
+            }
+            if let Some(error) = &condition.error {
+                write!(f, "\n    error: {}", error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Records which trace steps had a per-step condition evaluated against
+/// them; inert unless enabled.
+struct StepTracker {
+    inspected: Option<Vec<bool>>,
+}
+
+impl StepTracker {
+    fn disabled() -> Self {
+        Self { inspected: None }
+    }
+
+    fn enabled(step_count: usize) -> Self {
+        Self { inspected: Some(vec![false; step_count]) }
+    }
+
+    fn record(&mut self, step_index: usize) {
+        if let Some(flag) = self.inspected.as_mut().and_then(|f| f.get_mut(step_index)) {
+            *flag = true;
         }
     }
 }
 
 /// Proof engine for verifying governance invariants.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ProofEngine {
     config: EngineConfig,
 }
@@ -39,6 +361,16 @@ impl ProofEngine {
         Self { config }
     }
 
+    /// The configuration this engine runs with.
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    fn validate_context(&self, ctx: &VerificationContext) -> FakResult<()> {
+        ctx.validate_with_edge_limit(self.config.max_authority_edges)?;
+        self.config.hooks.check(ctx)
+    }
+
     /// Verify invariants against governance artifacts.
     pub fn verify_invariants(
         &self,
@@ -48,13 +380,96 @@ impl ProofEngine {
         policy_ir: &PolicyIR,
         invariants: &[InvariantSpec],
     ) -> FakResult<ProofWitness> {
-        // Validate inputs
-        trace.validate()?;
-        capabilities.validate()?;
-        cost_ledger.validate()?;
-        policy_ir.validate()?;
+        self.verify_invariants_with_progress(trace, capabilities, cost_ledger, policy_ir, invariants, |_, _, _| {})
+    }
+
+    /// Verify invariants, calling `progress` with `(index, total, spec)`
+    /// before each one is checked.
+    ///
+    /// Invariants are visited in evaluation order (gating invariants first),
+    /// and every visited invariant is reported, including ones that end up
+    /// skipped or externally discharged; only a timeout stops the calls
+    /// early. The callback observes verification and cannot stop it.
+    pub fn verify_invariants_with_progress(
+        &self,
+        trace: &ExecutionTrace,
+        capabilities: &CapabilityManifest,
+        cost_ledger: &CostLedger,
+        policy_ir: &PolicyIR,
+        invariants: &[InvariantSpec],
+        mut progress: impl FnMut(usize, usize, &InvariantSpec),
+    ) -> FakResult<ProofWitness> {
+        let ctx = VerificationContext::new(trace, capabilities, cost_ledger, policy_ir);
+        self.verify_tracked(&ctx, invariants, &mut StepTracker::disabled(), &mut progress, None)
+    }
 
-        let start_time = self.current_time_secs();
+    /// Verify invariants, stopping early once `cancel` is set.
+    ///
+    /// The flag is checked before each invariant; when it is set the witness
+    /// ends with a `cancelled` counterexample naming the first invariant not
+    /// checked, like a timeout.
+    pub fn verify_invariants_cancellable(
+        &self,
+        trace: &ExecutionTrace,
+        capabilities: &CapabilityManifest,
+        cost_ledger: &CostLedger,
+        policy_ir: &PolicyIR,
+        invariants: &[InvariantSpec],
+        cancel: &AtomicBool,
+    ) -> FakResult<ProofWitness> {
+        let ctx = VerificationContext::new(trace, capabilities, cost_ledger, policy_ir);
+        self.verify_tracked(&ctx, invariants, &mut StepTracker::disabled(), &mut |_, _, _| {}, Some(cancel))
+    }
+
+    /// Verify invariants against a borrowed or owned context.
+    ///
+    /// Accepts a [`VerificationContext`], a reference to one, or a reference
+    /// to an [`OwnedVerificationContext`](crate::types::OwnedVerificationContext).
+    pub fn verify_invariants_ctx<'c>(
+        &self,
+        ctx: impl Into<VerificationContext<'c>>,
+        invariants: &[InvariantSpec],
+    ) -> FakResult<ProofWitness> {
+        self.verify_tracked(&ctx.into(), invariants, &mut StepTracker::disabled(), &mut |_, _, _| {}, None)
+    }
+
+    /// Verify invariants while recording which trace steps the checks read.
+    ///
+    /// A step counts as read once a behavioral invariant's per-step
+    /// condition is evaluated against it; structural scans such as the
+    /// causality check do not count. Coverage bookkeeping only happens
+    /// through this entry point, so plain `verify_invariants` calls pay
+    /// nothing for it. Steps reported as never read are parts of the trace
+    /// that no invariant constrains.
+    pub fn verify_invariants_with_coverage(
+        &self,
+        trace: &ExecutionTrace,
+        capabilities: &CapabilityManifest,
+        cost_ledger: &CostLedger,
+        policy_ir: &PolicyIR,
+        invariants: &[InvariantSpec],
+    ) -> FakResult<(ProofWitness, CoverageReport)> {
+        let ctx = VerificationContext::new(trace, capabilities, cost_ledger, policy_ir);
+        let mut tracker = StepTracker::enabled(trace.steps.len());
+        let witness = self.verify_tracked(&ctx, invariants, &mut tracker, &mut |_, _, _| {}, None)?;
+        let flags = tracker.inspected.unwrap_or_default();
+        Ok((witness, CoverageReport::from_flags(&flags)))
+    }
+
+    fn verify_tracked(
+        &self,
+        ctx: &VerificationContext,
+        invariants: &[InvariantSpec],
+        tracker: &mut StepTracker,
+        progress: &mut dyn FnMut(usize, usize, &InvariantSpec),
+        cancel: Option<&AtomicBool>,
+    ) -> FakResult<ProofWitness> {
+        self.validate_context(ctx)?;
+        let VerificationContext { trace, capabilities, cost_ledger, policy_ir } = *ctx;
+
+        // Monotonic, so clock adjustments cannot disable the timeout.
+        let clock = self.config.clock.as_ref();
+        let started = clock.now_secs();
 
         if invariants.len() > self.config.max_invariants {
             return Err(FakError::ResourceLimit {
@@ -63,11 +478,44 @@ impl ProofEngine {
                 actual: invariants.len(),
             });
         }
+        if trace.steps.len() > self.config.max_trace_steps {
+            return Err(FakError::ResourceLimit {
+                resource: "trace_steps".to_string(),
+                limit: self.config.max_trace_steps,
+                actual: trace.steps.len(),
+            });
+        }
 
+        // Gating invariants run first; once one fails the rest are skipped.
+        let (gating, diagnostic): (Vec<_>, Vec<_>) = invariants.iter().partition(|i| i.gating);
         let mut counterexamples = Vec::new();
+        let mut not_evaluated = Vec::new();
+        let mut vacuous = Vec::new();
+        let mut gate_failed = false;
 
-        for invariant in invariants {
-            let elapsed = self.current_time_secs() - start_time;
+        for (index, invariant) in gating.into_iter().chain(diagnostic).enumerate() {
+            if cancel.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+                counterexamples.push(CounterExample {
+                    invariant_name: invariant.name.clone(),
+                    error_type: "cancelled".to_string(),
+                    details: serde_json::json!({
+                        "reason": "Verification cancelled",
+                        "checked": index
+                    }),
+                    step_index: None,
+                });
+                break;
+            }
+            progress(index, invariants.len(), invariant);
+            if invariant.external_discharge.as_ref().is_some_and(ExternalProof::discharges) {
+                continue;
+            }
+            if gate_failed && !invariant.gating {
+                not_evaluated.push(invariant.name.clone());
+                continue;
+            }
+
+            let elapsed = elapsed_secs(clock, started);
             if elapsed > self.config.timeout_secs {
                 counterexamples.push(CounterExample {
                     invariant_name: invariant.name.clone(),
@@ -82,32 +530,42 @@ impl ProofEngine {
                 break;
             }
 
-            match self.check_invariant(trace, capabilities, cost_ledger, policy_ir, invariant) {
-                Ok(true) => continue,
-                Ok(false) => counterexamples.push(CounterExample {
-                    invariant_name: invariant.name.clone(),
-                    error_type: "violation".to_string(),
-                    details: serde_json::json!({
-                        "reason": "Invariant violated",
-                        "invariant_type": invariant.invariant_type.as_str()
-                    }),
-                    step_index: None,
-                }),
-                Err(e) => counterexamples.push(CounterExample {
+            let mut triggered = false;
+            let counterexample = match self.check_invariant(ctx, invariant, tracker, &mut triggered) {
+                Ok(None) => {
+                    if invariant.precondition.is_some() && !triggered {
+                        vacuous.push(invariant.name.clone());
+                    }
+                    continue;
+                }
+                Ok(Some(counterexample)) => counterexample,
+                Err(e) => CounterExample {
                     invariant_name: invariant.name.clone(),
                     error_type: "check_error".to_string(),
                     details: serde_json::json!({"error": e.to_string()}),
                     step_index: None,
-                }),
-            }
+                },
+            };
+            counterexamples.push(counterexample);
+            gate_failed |= invariant.gating;
         }
 
+        // Content hashes bind the proof ID to what the artifacts say, not
+        // only to their IDs, so an in-place edit changes the ID.
+        let timestamp_keys = &self.config.timestamp_keys;
         let proof_content = serde_json::json!({
             "trace_id": trace.id,
             "capabilities_id": capabilities.id,
             "cost_ledger_id": cost_ledger.id,
             "policy_ir_id": policy_ir.id,
             "invariant_names": invariants.iter().map(|i| &i.name).collect::<Vec<_>>(),
+            "content_hashes": [
+                content_hash_of(trace, timestamp_keys)?,
+                content_hash_of(capabilities, timestamp_keys)?,
+                content_hash_of(cost_ledger, timestamp_keys)?,
+                content_hash_of(policy_ir, timestamp_keys)?,
+                content_hash_of(&invariants, timestamp_keys)?,
+            ],
         });
 
         let proof_id = compute_content_hash(&proof_content);
@@ -120,73 +578,618 @@ impl ProofEngine {
             policy_ir: policy_ir.clone(),
             invariants: invariants.to_vec(),
             counterexamples,
+            not_evaluated,
+            vacuous,
+        })
+    }
+
+    /// Check a single invariant against a context without building a witness.
+    ///
+    /// Returns `None` when the invariant holds and the counterexample otherwise.
+    pub fn check_one(
+        &self,
+        ctx: &VerificationContext,
+        inv: &InvariantSpec,
+    ) -> FakResult<Option<CounterExample>> {
+        self.validate_context(ctx)?;
+        self.check_invariant(ctx, inv, &mut StepTracker::disabled(), &mut false)
+    }
+
+    /// Replay `counterexample` for `inv` and show how its conditions
+    /// evaluated.
+    ///
+    /// The invariant is re-checked against `ctx` alone and each condition is
+    /// evaluated with every sub-expression value recorded. Nothing is
+    /// stored, so explaining is deterministic and side-effect free.
+    pub fn explain_counterexample(
+        &self,
+        ctx: &VerificationContext,
+        inv: &InvariantSpec,
+        counterexample: &CounterExample,
+    ) -> FakResult<Explanation> {
+        if counterexample.invariant_name != inv.name {
+            return Err(FakError::Validation {
+                field: "invariant_name".to_string(),
+                message: format!(
+                    "counterexample is for '{}', not '{}'",
+                    counterexample.invariant_name, inv.name
+                ),
+            });
+        }
+        let reproduced = self.check_one(ctx, inv)?.as_ref() == Some(counterexample);
+
+        let ledger_scope = LedgerScope(ctx);
+        let step_scope = counterexample
+            .step_index
+            .and_then(|i| ctx.trace.steps.get(i))
+            .filter(|_| inv.invariant_type == ProofType::BehavioralSoundness)
+            .map(|step| StepScope { ctx, step });
+        let scope: &dyn ExpressionContext = match (&step_scope, inv.invariant_type) {
+            (Some(step_scope), _) => step_scope,
+            (None, ProofType::EconomicInvariance) => &ledger_scope,
+            (None, _) => ctx,
+        };
+        let tolerance = self.condition_tolerance(inv);
+        let mut conditions = Vec::new();
+        for (role, condition) in [("precondition", &inv.precondition), ("postcondition", &inv.postcondition)] {
+            let Some(condition) = condition else { continue };
+            let trace = match evaluate_traced(condition, scope, &self.config.functions, tolerance) {
+                Ok((value, steps)) => ConditionTrace {
+                    role: role.to_string(),
+                    condition: condition.clone(),
+                    steps,
+                    result: Some(value),
+                    error: None,
+                },
+                Err(e) => ConditionTrace {
+                    role: role.to_string(),
+                    condition: condition.clone(),
+                    steps: Vec::new(),
+                    result: None,
+                    error: Some(e.to_string()),
+                },
+            };
+            let stop = trace.result == Some(serde_json::Value::Bool(false));
+            conditions.push(trace);
+            if stop && role == "precondition" {
+                break;
+            }
+        }
+
+        Ok(Explanation {
+            invariant_name: inv.name.clone(),
+            error_type: counterexample.error_type.clone(),
+            conditions,
+            reproduced,
         })
     }
 
+    /// Sets `triggered` once a precondition evaluates true, or once the
+    /// conditions are checked at all when there is no precondition.
     fn check_invariant(
         &self,
-        trace: &ExecutionTrace,
-        capabilities: &CapabilityManifest,
-        cost_ledger: &CostLedger,
-        policy_ir: &PolicyIR,
+        ctx: &VerificationContext,
         invariant: &InvariantSpec,
-    ) -> FakResult<bool> {
+        tracker: &mut StepTracker,
+        triggered: &mut bool,
+    ) -> FakResult<Option<CounterExample>> {
         invariant.validate()?;
 
-        match invariant.invariant_type {
-            ProofType::BehavioralSoundness => self.check_behavioral_soundness(trace, invariant),
+        let structural = match invariant.invariant_type {
+            ProofType::BehavioralSoundness => {
+                self.check_behavioral_soundness(ctx.trace, invariant)
+            }
             ProofType::AuthorityNonEscalation => {
-                self.check_authority_non_escalation(capabilities, invariant)
+                self.check_authority_non_escalation(ctx.capabilities, ctx.policy_ir, invariant)
+            }
+            ProofType::EconomicInvariance => {
+                self.check_economic_invariance(ctx.cost_ledger, invariant)
             }
-            ProofType::EconomicInvariance => self.check_economic_invariance(cost_ledger, invariant),
             ProofType::SemanticPreservation => {
-                self.check_semantic_preservation(policy_ir, invariant)
+                self.check_semantic_preservation(ctx.policy_ir, invariant)
+            }
+        }?;
+        if structural.is_some() {
+            return Ok(structural);
+        }
+
+        // Economic conditions may also use bare ledger names (`total_cost`,
+        // `budget`) and behavioral ones bare step fields; every type resolves
+        // the namespaced names of the context.
+        match invariant.invariant_type {
+            ProofType::EconomicInvariance => {
+                self.check_conditions(&LedgerScope(ctx), invariant, triggered)
+            }
+            ProofType::BehavioralSoundness if Self::uses_step_fields(invariant)? => {
+                self.check_step_conditions(ctx, invariant, tracker, triggered)
             }
+            _ => self.check_conditions(ctx, invariant, triggered),
         }
     }
 
+    /// Whether a condition names a field outside the context namespaces,
+    /// which behavioral invariants read from each trace step.
+    fn uses_step_fields(inv: &InvariantSpec) -> FakResult<bool> {
+        for condition in inv.precondition.iter().chain(&inv.postcondition) {
+            if crate::expr::referenced_paths(condition)?
+                .iter()
+                .any(|name| !is_namespaced(name))
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Check the conditions against every trace step in order.
+    ///
+    /// A step whose precondition is false is skipped. The first step whose
+    /// postcondition fails, or that is not a JSON object, is reported with
+    /// its index and contents.
+    fn check_step_conditions(
+        &self,
+        ctx: &VerificationContext,
+        inv: &InvariantSpec,
+        tracker: &mut StepTracker,
+        triggered: &mut bool,
+    ) -> FakResult<Option<CounterExample>> {
+        for (i, step) in ctx.trace.steps.iter().enumerate() {
+            tracker.record(i);
+            if !step.is_object() {
+                return Ok(Some(CounterExample {
+                    invariant_name: inv.name.clone(),
+                    error_type: "invalid_step".to_string(),
+                    details: serde_json::json!({
+                        "reason": "Trace step is not a JSON object",
+                        "step": step
+                    }),
+                    step_index: Some(i),
+                }));
+            }
+            let scope = StepScope { ctx, step };
+            if let Some(mut counterexample) = self.check_conditions(&scope, inv, triggered)? {
+                counterexample.step_index = Some(i);
+                if let Some(details) = counterexample.details.as_object_mut() {
+                    details.insert("step".to_string(), step.clone());
+                }
+                return Ok(Some(counterexample));
+            }
+        }
+        Ok(None)
+    }
+
     fn check_behavioral_soundness(
         &self,
         trace: &ExecutionTrace,
         inv: &InvariantSpec,
-    ) -> FakResult<bool> {
+    ) -> FakResult<Option<CounterExample>> {
         // Trace must be non-empty if precondition exists
-        Ok(!trace.steps.is_empty() || inv.precondition.is_none())
+        if trace.steps.is_empty() && inv.precondition.is_some() {
+            return Ok(Some(Self::violation(inv)));
+        }
+        if inv.check_causality {
+            if let Some(counterexample) = Self::check_step_causality(trace, inv) {
+                return Ok(Some(counterexample));
+            }
+        }
+        match &inv.rate_limit {
+            Some(rate_limit) => Self::check_rate_limit(trace, inv, rate_limit),
+            None => Ok(None),
+        }
+    }
+
+    /// Slide a window over the timestamps of steps matching the limited
+    /// action and report the first window holding more than `limit` of them.
+    ///
+    /// A matching step without a numeric timestamp is a validation error.
+    fn check_rate_limit(
+        trace: &ExecutionTrace,
+        inv: &InvariantSpec,
+        rate_limit: &RateLimit,
+    ) -> FakResult<Option<CounterExample>> {
+        let mut events = Vec::new();
+        for (i, step) in trace.steps.iter().enumerate() {
+            if step.get(&rate_limit.action_field) != Some(&rate_limit.action) {
+                continue;
+            }
+            let time = step
+                .get(&rate_limit.window_field)
+                .and_then(|t| t.as_f64())
+                .ok_or_else(|| FakError::Validation {
+                    field: "steps".to_string(),
+                    message: format!("step {} has no numeric {}", i, rate_limit.window_field),
+                })?;
+            events.push((time, i));
+        }
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut start = 0;
+        for end in 0..events.len() {
+            while events[end].0 - events[start].0 >= rate_limit.window {
+                start += 1;
+            }
+            let count = end - start + 1;
+            if count > rate_limit.limit {
+                return Ok(Some(CounterExample {
+                    invariant_name: inv.name.clone(),
+                    error_type: "rate_limit_exceeded".to_string(),
+                    details: serde_json::json!({
+                        "reason": "Action exceeded its rate limit",
+                        "action": rate_limit.action,
+                        "window_start": events[start].0,
+                        "window_end": events[start].0 + rate_limit.window,
+                        "count": count,
+                        "limit": rate_limit.limit,
+                        "steps": events[start..=end].iter().map(|e| e.1).collect::<Vec<_>>()
+                    }),
+                    step_index: Some(events[end].1),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Check that every `depends_on` index points to an earlier step of the trace.
+    ///
+    /// Requiring strictly backward references also makes the dependency graph
+    /// acyclic, so no separate cycle detection is needed.
+    fn check_step_causality(trace: &ExecutionTrace, inv: &InvariantSpec) -> Option<CounterExample> {
+        let step_count = trace.steps.len();
+        for (i, step) in trace.steps.iter().enumerate() {
+            let deps = match step.get("depends_on") {
+                Some(deps) => deps,
+                None => continue,
+            };
+            let deps = match deps.as_array() {
+                Some(deps) => deps,
+                None => {
+                    return Some(Self::causality_violation(
+                        inv,
+                        i,
+                        "depends_on must be an array of step indices",
+                        deps,
+                        step_count,
+                    ))
+                }
+            };
+            for dep in deps {
+                let reason = match dep.as_u64() {
+                    Some(d) if d >= step_count as u64 => "dependency index out of range",
+                    Some(d) if d >= i as u64 => "forward dependency reference",
+                    Some(_) => continue,
+                    None => "dependency index must be a non-negative integer",
+                };
+                return Some(Self::causality_violation(inv, i, reason, dep, step_count));
+            }
+        }
+        None
+    }
+
+    fn causality_violation(
+        inv: &InvariantSpec,
+        step_index: usize,
+        reason: &str,
+        dependency: &serde_json::Value,
+        step_count: usize,
+    ) -> CounterExample {
+        CounterExample {
+            invariant_name: inv.name.clone(),
+            error_type: "causality_violation".to_string(),
+            details: serde_json::json!({
+                "reason": reason,
+                "dependency": dependency,
+                "step_count": step_count
+            }),
+            step_index: Some(step_index),
+        }
     }
 
     fn check_authority_non_escalation(
         &self,
         caps: &CapabilityManifest,
+        policy: &PolicyIR,
         inv: &InvariantSpec,
-    ) -> FakResult<bool> {
+    ) -> FakResult<Option<CounterExample>> {
         // Authority graph must be non-empty if precondition exists
-        Ok(!caps.authority_graph.is_empty() || inv.precondition.is_none())
+        if caps.authority_graph.is_empty() && inv.precondition.is_some() {
+            return Ok(Some(Self::violation(inv)));
+        }
+        if let Some(path) = caps.escalation_path() {
+            let capability = path.last().cloned().unwrap_or_default();
+            return Ok(Some(CounterExample {
+                invariant_name: inv.name.clone(),
+                error_type: "authority_escalation".to_string(),
+                details: serde_json::json!({
+                    "reason": format!("agent '{}' can reach undeclared capability '{}'", caps.agent_id, capability),
+                    "agent_id": caps.agent_id,
+                    "capability": capability,
+                    "path": path
+                }),
+                step_index: None,
+            }));
+        }
+        Self::check_permitted_grants(caps, policy, inv)
+    }
+
+    /// Every granted capability must be permitted to the agent by the
+    /// policy's `permitted_grants`, when the policy declares them.
+    fn check_permitted_grants(
+        caps: &CapabilityManifest,
+        policy: &PolicyIR,
+        inv: &InvariantSpec,
+    ) -> FakResult<Option<CounterExample>> {
+        let Some(permitted) = policy.permitted_grants(&caps.agent_id)? else {
+            return Ok(None);
+        };
+        let disallowed: Vec<&String> = caps
+            .capabilities
+            .iter()
+            .filter(|cap| !permitted.contains(&cap.as_str()))
+            .collect();
+        let Some(first) = disallowed.first() else {
+            return Ok(None);
+        };
+        Ok(Some(CounterExample {
+            invariant_name: inv.name.clone(),
+            error_type: "unauthorized_grant".to_string(),
+            details: serde_json::json!({
+                "reason": format!("capability '{}' is not permitted to agent '{}' by the policy", first, caps.agent_id),
+                "agent_id": caps.agent_id,
+                "capability": first,
+                "disallowed": disallowed,
+                "policy_id": policy.id
+            }),
+            step_index: None,
+        }))
     }
 
     fn check_economic_invariance(
         &self,
         ledger: &CostLedger,
-        _inv: &InvariantSpec,
-    ) -> FakResult<bool> {
-        Ok(ledger.total_cost >= 0.0)
+        inv: &InvariantSpec,
+    ) -> FakResult<Option<CounterExample>> {
+        if ledger.total_cost < 0.0 {
+            return Ok(Some(Self::violation(inv)));
+        }
+        if let Some(counterexample) = self.check_entry_sum(ledger, inv)? {
+            return Ok(Some(counterexample));
+        }
+        self.check_cumulative_cost(ledger, inv)
+    }
+
+    /// Evaluate the invariant's precondition and postcondition in `scope`.
+    ///
+    /// A false precondition means the invariant does not apply, so it holds;
+    /// a false postcondition is a `condition_failed` counterexample. A name
+    /// with no value is reported as `unresolved_identifier`. `triggered` is
+    /// set when the precondition holds or there is none.
+    fn check_conditions(
+        &self,
+        scope: &dyn ExpressionContext,
+        inv: &InvariantSpec,
+        triggered: &mut bool,
+    ) -> FakResult<Option<CounterExample>> {
+        if let (Some(precondition), Some(expr)) = (&inv.precondition, inv.compiled_precondition()?) {
+            match self.evaluate_condition(precondition, &expr, scope, inv)? {
+                Ok(true) => {}
+                Ok(false) => return Ok(None),
+                Err(counterexample) => return Ok(Some(counterexample)),
+            }
+        }
+        *triggered = true;
+        let (Some(postcondition), Some(expr)) = (&inv.postcondition, inv.compiled_postcondition()?) else {
+            return Ok(None);
+        };
+        Ok(match self.evaluate_condition(postcondition, &expr, scope, inv)? {
+            Ok(true) => None,
+            Ok(false) => Some(CounterExample {
+                invariant_name: inv.name.clone(),
+                error_type: "condition_failed".to_string(),
+                details: serde_json::json!({
+                    "reason": "Condition evaluated to false",
+                    "condition": postcondition,
+                    "values": Self::condition_values(postcondition, scope)?
+                }),
+                step_index: None,
+            }),
+            Err(counterexample) => Some(counterexample),
+        })
+    }
+
+    /// Evaluate `condition` to a boolean, or the counterexample for an
+    /// identifier it names that has no value.
+    ///
+    /// Identifiers are checked before evaluation so an unresolvable one is
+    /// reported even where short-circuiting would skip it.
+    fn evaluate_condition(
+        &self,
+        condition: &str,
+        expr: &Expr,
+        scope: &dyn ExpressionContext,
+        inv: &InvariantSpec,
+    ) -> FakResult<Result<bool, CounterExample>> {
+        for name in crate::expr::referenced_paths(condition)? {
+            if scope.resolve(&name)?.is_none() {
+                return Ok(Err(CounterExample {
+                    invariant_name: inv.name.clone(),
+                    error_type: "unresolved_identifier".to_string(),
+                    details: serde_json::json!({
+                        "reason": "Condition names an identifier with no value",
+                        "identifier": name,
+                        "condition": condition
+                    }),
+                    step_index: None,
+                }));
+            }
+        }
+
+        let tolerance = self.condition_tolerance(inv);
+        let result = crate::expr::evaluate_expr(expr, scope, &self.config.functions, tolerance)?;
+        result.as_bool().map(Ok).ok_or_else(|| FakError::Validation {
+            field: "expression".to_string(),
+            message: format!("condition '{}' evaluated to {}, not a boolean", condition, result),
+        })
+    }
+
+    /// Comparison slack for `inv`'s conditions: its own tolerance, else
+    /// `cost_tolerance` for economic invariants and none for the rest.
+    fn condition_tolerance(&self, inv: &InvariantSpec) -> f64 {
+        inv.tolerance.unwrap_or(match inv.invariant_type {
+            ProofType::EconomicInvariance => self.config.cost_tolerance,
+            _ => 0.0,
+        })
+    }
+
+    /// Values of the identifiers in `condition`, for counterexample details.
+    fn condition_values(
+        condition: &str,
+        scope: &dyn ExpressionContext,
+    ) -> FakResult<serde_json::Map<String, serde_json::Value>> {
+        let mut values = serde_json::Map::new();
+        for name in crate::expr::referenced_paths(condition)? {
+            if let Some(value) = scope.resolve(&name)? {
+                values.insert(name, value);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Check that entry costs sum to `total_cost` without overflow or drift.
+    ///
+    /// Ledgers whose entries carry no `cost` fields have nothing to reconcile
+    /// and pass. Rounding error is judged relative to the size of the sum,
+    /// so large ledgers are not flagged for drift below their precision.
+    fn check_entry_sum(
+        &self,
+        ledger: &CostLedger,
+        inv: &InvariantSpec,
+    ) -> FakResult<Option<CounterExample>> {
+        let sum = ledger.sum_entry_costs()?;
+        if sum.counted == 0 {
+            return Ok(None);
+        }
+
+        let tolerance = inv.tolerance.unwrap_or(self.config.cost_tolerance);
+        let failure = if !sum.total.is_finite() {
+            Some(("overflow", serde_json::json!({
+                "reason": "Entry cost sum overflowed",
+                "entry_count": sum.counted
+            })))
+        } else if sum.rounding_error > tolerance * sum.total.abs().max(1.0) {
+            Some(("precision_loss", serde_json::json!({
+                "reason": "Naive summation error exceeds tolerance",
+                "compensated_sum": sum.total,
+                "rounding_error": sum.rounding_error,
+                "tolerance": tolerance
+            })))
+        } else if let Err(e) = sum.reconcile(ledger.total_cost, tolerance) {
+            Some(("cost_mismatch", serde_json::json!({
+                "reason": "Entry costs do not sum to total_cost",
+                "error": e.to_string(),
+                "entry_sum": sum.total,
+                "total_cost": ledger.total_cost,
+                "tolerance": tolerance
+            })))
+        } else {
+            None
+        };
+
+        Ok(failure.map(|(error_type, details)| CounterExample {
+            invariant_name: inv.name.clone(),
+            error_type: error_type.to_string(),
+            details,
+            step_index: None,
+        }))
+    }
+
+    /// Check that entries' running `cumulative_cost` never decreases and ends
+    /// at `total_cost`.
+    ///
+    /// Entries without a `cumulative_cost` field are skipped, and a ledger
+    /// where no entry carries one is not checked; a non-numeric value is a
+    /// validation error.
+    fn check_cumulative_cost(
+        &self,
+        ledger: &CostLedger,
+        inv: &InvariantSpec,
+    ) -> FakResult<Option<CounterExample>> {
+        let tolerance = inv.tolerance.unwrap_or(self.config.cost_tolerance);
+        let mut last: Option<(usize, f64)> = None;
+
+        for (i, entry) in ledger.entries.iter().enumerate() {
+            let cumulative = match entry.get("cumulative_cost") {
+                Some(value) => value.as_f64().ok_or_else(|| FakError::Validation {
+                    field: "entries".to_string(),
+                    message: format!("entry {} has a non-numeric cumulative_cost", i),
+                })?,
+                None => continue,
+            };
+            if let Some((previous_index, previous)) = last {
+                if cumulative < previous - tolerance {
+                    return Ok(Some(CounterExample {
+                        invariant_name: inv.name.clone(),
+                        error_type: "cost_decreased".to_string(),
+                        details: serde_json::json!({
+                            "reason": "cumulative_cost decreased between entries",
+                            "entry_index": i,
+                            "previous_entry_index": previous_index,
+                            "previous": previous,
+                            "current": cumulative
+                        }),
+                        step_index: None,
+                    }));
+                }
+            }
+            last = Some((i, cumulative));
+        }
+
+        Ok(last.and_then(|(i, final_cost)| {
+            ((final_cost - ledger.total_cost).abs() > tolerance).then(|| CounterExample {
+                invariant_name: inv.name.clone(),
+                error_type: "cumulative_mismatch".to_string(),
+                details: serde_json::json!({
+                    "reason": "Final cumulative_cost does not equal total_cost",
+                    "entry_index": i,
+                    "cumulative_cost": final_cost,
+                    "total_cost": ledger.total_cost,
+                    "tolerance": tolerance
+                }),
+                step_index: None,
+            })
+        }))
     }
 
     fn check_semantic_preservation(
         &self,
         policy: &PolicyIR,
-        _inv: &InvariantSpec,
-    ) -> FakResult<bool> {
-        Ok(!policy.id.is_empty())
+        inv: &InvariantSpec,
+    ) -> FakResult<Option<CounterExample>> {
+        if policy.id.is_empty() {
+            return Ok(Some(Self::violation(inv)));
+        }
+        Ok(None)
     }
 
-    fn current_time_secs(&self) -> f64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs_f64())
-            .unwrap_or(0.0)
+    /// Generic counterexample for an invariant whose check does not report specifics.
+    fn violation(inv: &InvariantSpec) -> CounterExample {
+        CounterExample {
+            invariant_name: inv.name.clone(),
+            error_type: "violation".to_string(),
+            details: serde_json::json!({
+                "reason": "Invariant violated",
+                "invariant_type": inv.invariant_type.as_str()
+            }),
+            step_index: None,
+        }
     }
 
     /// Generate a proof bundle from witnesses.
+    ///
+    /// Generation is idempotent: the same witnesses, in any order, always
+    /// produce an identical bundle, which callers may rely on for
+    /// deduplication. Witnesses are stored sorted by `proof_id`. The
+    /// engine's material config is pinned in the bundle metadata so verifiers
+    /// can detect when they run under different settings.
     pub fn generate_bundle(&self, witnesses: &[ProofWitness]) -> FakResult<ProofBundle> {
         if witnesses.is_empty() {
             return Err(FakError::Validation {
@@ -196,28 +1199,70 @@ impl ProofEngine {
         }
 
         for w in witnesses {
-            w.validate()?;
+            w.validate_with_edge_limit(self.config.max_authority_edges)?;
         }
 
-        let bundle_content = serde_json::json!({
-            "witnesses": witnesses.iter().map(|w| w.proof_id.clone()).collect::<Vec<_>>(),
-            "metadata": {},
-        });
-
-        let bundle_id = compute_content_hash(&bundle_content);
+        let mut pinned = self.config.material_summary();
+        pinned["fingerprint"] = serde_json::Value::String(self.config.fingerprint());
+        let mut metadata = serde_json::Map::new();
+        metadata.insert(EngineConfig::METADATA_KEY.to_string(), pinned);
+        let mut witnesses = witnesses.to_vec();
+        witnesses.sort_by(|a, b| a.proof_id.cmp(&b.proof_id));
+        let bundle_id = ProofBundle::compute_id(&witnesses, &metadata);
 
-        Ok(ProofBundle {
-            id: bundle_id,
-            witnesses: witnesses.to_vec(),
-            metadata: serde_json::Map::new(),
-        })
+        Ok(ProofBundle::new(bundle_id, witnesses, metadata))
     }
-}
 
-impl Default for ProofEngine {
-    fn default() -> Self {
-        Self {
-            config: EngineConfig::default(),
+    /// Combine bundles into one, e.g. to verify work from several workers
+    /// in a single pass.
+    ///
+    /// Witnesses are sorted by `proof_id`, as in [`Self::generate_bundle`],
+    /// and exact repeats are dropped; two witnesses sharing a `proof_id`
+    /// but differing in content are an integrity failure. Metadata maps are
+    /// merged, later bundles winning on key conflicts. Every input must
+    /// carry a valid ID, so a tampered bundle cannot be laundered into a
+    /// freshly hashed one.
+    pub fn merge_bundles(&self, bundles: &[ProofBundle]) -> FakResult<ProofBundle> {
+        if bundles.is_empty() {
+            return Err(FakError::Validation {
+                field: "bundles".to_string(),
+                message: "cannot merge zero bundles".to_string(),
+            });
         }
+
+        let mut by_proof_id = BTreeMap::new();
+        let mut metadata = serde_json::Map::new();
+        for bundle in bundles {
+            bundle.verify_id()?;
+            for witness in &bundle.witnesses {
+                let hash = compute_content_hash_streaming(&serde_json::to_value(witness)?);
+                match by_proof_id.entry(witness.proof_id.as_str()) {
+                    Entry::Vacant(entry) => {
+                        entry.insert((hash, witness));
+                    }
+                    Entry::Occupied(entry) if entry.get().0 != hash => {
+                        return Err(FakError::IntegrityFailure {
+                            artifact_id: witness.proof_id.clone(),
+                            expected: entry.get().0.clone(),
+                            actual: hash,
+                        });
+                    }
+                    Entry::Occupied(_) => {}
+                }
+            }
+            metadata.extend(bundle.metadata.clone());
+        }
+        let witnesses: Vec<ProofWitness> = by_proof_id.into_values().map(|(_, w)| w.clone()).collect();
+        normalize_timestamps(&mut metadata, &self.config.timestamp_keys)?;
+        if witnesses.len() > ProofBundle::MAX_WITNESSES {
+            return Err(FakError::ResourceLimit {
+                resource: "bundle_witnesses".to_string(),
+                limit: ProofBundle::MAX_WITNESSES,
+                actual: witnesses.len(),
+            });
+        }
+
+        let bundle_id = ProofBundle::compute_id(&witnesses, &metadata);
+        Ok(ProofBundle::new(bundle_id, witnesses, metadata))
     }
-}
\ No newline at end of file
+}
@@ -11,8 +11,14 @@ pub enum FakError {
     ArtifactNotFound { artifact_id: String },
     /// Artifact integrity check failed
     IntegrityFailure { artifact_id: String, expected: String, actual: String },
-    /// Invariant parsing error
-    ParseError { source: String, message: String },
+    /// Invariant parsing error, with the 1-based position of the problem
+    /// when it is known
+    ParseError {
+        source: String,
+        message: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
     /// Invariant verification failed
     VerificationFailure { invariant: String, reason: String },
     /// Resource limit exceeded
@@ -27,6 +33,58 @@ pub enum FakError {
     BundleVerificationFailed { bundle_id: String, reason: String },
     /// Lock acquisition failed (thread safety)
     LockPoisoned { resource: String },
+    /// I/O error while reading or writing output
+    Io { message: String },
+}
+
+/// Broad class of a [`FakError`], for callers that only need to know who
+/// is at fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The caller supplied malformed, unknown or unparseable input.
+    Input,
+    /// A size, count or time limit was exceeded.
+    Resource,
+    /// Content failed a hash, proof or verification check.
+    Integrity,
+    /// The failure lies in the environment (I/O, poisoned locks).
+    Internal,
+}
+
+impl FakError {
+    /// Stable machine-readable code for the variant. Codes are never
+    /// reassigned; new variants get new codes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Validation { .. } => "FAK001",
+            Self::ArtifactNotFound { .. } => "FAK002",
+            Self::IntegrityFailure { .. } => "FAK003",
+            Self::ParseError { .. } => "FAK004",
+            Self::VerificationFailure { .. } => "FAK005",
+            Self::ResourceLimit { .. } => "FAK006",
+            Self::Timeout { .. } => "FAK007",
+            Self::Serialization { .. } => "FAK008",
+            Self::UnknownProofType { .. } => "FAK009",
+            Self::BundleVerificationFailed { .. } => "FAK010",
+            Self::LockPoisoned { .. } => "FAK011",
+            Self::Io { .. } => "FAK012",
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::Validation { .. }
+            | Self::ArtifactNotFound { .. }
+            | Self::ParseError { .. }
+            | Self::Serialization { .. }
+            | Self::UnknownProofType { .. } => ErrorCategory::Input,
+            Self::ResourceLimit { .. } | Self::Timeout { .. } => ErrorCategory::Resource,
+            Self::IntegrityFailure { .. }
+            | Self::VerificationFailure { .. }
+            | Self::BundleVerificationFailed { .. } => ErrorCategory::Integrity,
+            Self::LockPoisoned { .. } | Self::Io { .. } => ErrorCategory::Internal,
+        }
+    }
 }
 
 impl fmt::Display for FakError {
@@ -42,8 +100,15 @@ impl fmt::Display for FakError {
                 write!(f, "integrity check failed for '{}': expected '{}', got '{}'", 
                        artifact_id, expected, actual)
             }
-            Self::ParseError { source, message } => {
-                write!(f, "parse error in '{}': {}", source, message)
+            Self::ParseError { source, message, line, column } => {
+                write!(f, "parse error in '{}': {}", source, message)?;
+                if let Some(line) = line {
+                    write!(f, " at line {}", line)?;
+                    if let Some(column) = column {
+                        write!(f, ", column {}", column)?;
+                    }
+                }
+                Ok(())
             }
             Self::VerificationFailure { invariant, reason } => {
                 write!(f, "verification failed for '{}': {}", invariant, reason)
@@ -66,6 +131,9 @@ impl fmt::Display for FakError {
             Self::LockPoisoned { resource } => {
                 write!(f, "lock poisoned for resource: {}", resource)
             }
+            Self::Io { message } => {
+                write!(f, "i/o error: {}", message)
+            }
         }
     }
 }
@@ -81,6 +149,12 @@ impl From<serde_json::Error> for FakError {
     }
 }
 
+impl From<std::io::Error> for FakError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io { message: e.to_string() }
+    }
+}
+
 impl<T> From<std::sync::PoisonError<T>> for FakError {
     fn from(_: std::sync::PoisonError<T>) -> Self {
         Self::LockPoisoned { resource: "mutex".to_string() }
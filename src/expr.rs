@@ -0,0 +1,628 @@
+//! Parsing and evaluation of invariant condition expressions.
+//!
+//! Expressions combine numbers, quoted strings, `true`/`false`, field
+//! identifiers and function calls with, from lowest to highest precedence,
+//! `||`, `&&`, the comparisons `>= <= == != > <`, `+ -`, `* /`, and the
+//! prefix operators `!` and `-`. Comparisons do not chain.
+
+use crate::error::{FakError, FakResult};
+use crate::functions::FunctionRegistry;
+use crate::types::VerificationContext;
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Token of a precondition or postcondition expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Number(f64),
+    /// Quoted string literal, without the quotes.
+    Str(String),
+    /// Identifier or field path such as `total_cost` or
+    /// `trace.steps[*].action`.
+    Ident(String),
+    /// Operator: one of `>= <= == != > < + - * / && || !`.
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+const OPERATORS: [&str; 13] = [
+    ">=", "<=", "==", "!=", "&&", "||", ">", "<", "+", "-", "*", "/", "!",
+];
+
+/// Split an expression into tokens.
+///
+/// Paths may use dots and bracketed segments (`steps[0]`, `steps[*]`);
+/// brackets are kept as part of the identifier.
+pub fn tokenize(input: &str) -> FakResult<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse().map_err(|_| parse_error(format!("invalid number '{}'", text)))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            let mut depth = 0;
+            while i < chars.len() {
+                let ch = chars[i];
+                match ch {
+                    '[' => depth += 1,
+                    ']' if depth > 0 => depth -= 1,
+                    _ if depth > 0 || ch.is_alphanumeric() || ch == '_' || ch == '.' => {}
+                    _ => break,
+                }
+                i += 1;
+            }
+            if depth > 0 {
+                return Err(parse_error("unclosed '[' in path".to_string()));
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&ch| ch == c)
+                .ok_or_else(|| parse_error("unterminated string literal".to_string()))?;
+            tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else if c == '(' || c == ')' || c == ',' {
+            tokens.push(match c {
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                _ => Token::Comma,
+            });
+            i += 1;
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| parse_error(format!("unexpected character '{}'", c)))?;
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Identifiers with a fixed meaning that never name a field.
+const KEYWORDS: [&str; 2] = ["true", "false"];
+
+/// Field paths an expression reads, excluding function names and keywords.
+pub fn referenced_paths(input: &str) -> FakResult<Vec<String>> {
+    let tokens = tokenize(input)?;
+    Ok(tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, token)| match token {
+            Token::Ident(name)
+                if tokens.get(i + 1) != Some(&Token::LParen)
+                    && !KEYWORDS.contains(&name.as_str()) =>
+            {
+                Some(name.clone())
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Parsed expression. `Display` renders it back to source form, with
+/// only the parentheses precedence requires.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// Number, string or boolean constant.
+    Literal(Value),
+    /// Field path, e.g. `trace.steps` or `budget`.
+    Ident(String),
+    /// Call of a registered function.
+    Call { name: String, args: Vec<Expr> },
+    /// Unary minus.
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    /// Arithmetic: `+ - * /`.
+    BinaryOp { op: &'static str, left: Box<Expr>, right: Box<Expr> },
+    /// Comparison: `>= <= == != > <`.
+    Comparison { op: &'static str, left: Box<Expr>, right: Box<Expr> },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+/// Parse `input` without evaluating it.
+pub fn parse_expr(input: &str) -> FakResult<Expr> {
+    Parser::parse(input)
+}
+
+/// Lazily parsed condition, cached with the text it was parsed from.
+///
+/// A lookup with different text (the condition was changed after the cache
+/// filled) parses afresh, so the cache can never serve a stale tree. Caches
+/// always compare equal, so they never affect a spec's equality.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExprCache(OnceLock<(String, Expr)>);
+
+impl ExprCache {
+    pub(crate) fn get(&self, source: &str) -> FakResult<Cow<'_, Expr>> {
+        if self.0.get().is_none() {
+            let expr = parse_expr(source)?;
+            let _ = self.0.set((source.to_string(), expr));
+        }
+        match self.0.get() {
+            Some((cached, expr)) if cached == source => Ok(Cow::Borrowed(expr)),
+            _ => parse_expr(source).map(Cow::Owned),
+        }
+    }
+}
+
+impl PartialEq for ExprCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Expr {
+    /// Binding strength, used to parenthesize when rendering.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(..) => 1,
+            Expr::And(..) => 2,
+            Expr::Comparison { .. } => 3,
+            Expr::BinaryOp { op: "+" | "-", .. } => 4,
+            Expr::BinaryOp { .. } => 5,
+            Expr::Neg(_) | Expr::Not(_) => 6,
+            _ => 7,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prec = self.precedence();
+        // Operators are left-associative, so a right operand of equal
+        // precedence needs parentheses too.
+        let operand = |expr: &Expr, min: u8| {
+            if expr.precedence() < min {
+                format!("({})", expr)
+            } else {
+                expr.to_string()
+            }
+        };
+        match self {
+            Expr::Literal(value) => write!(f, "{}", value),
+            Expr::Ident(name) => write!(f, "{}", name),
+            Expr::Call { name, args } => {
+                let args: Vec<String> = args.iter().map(Expr::to_string).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+            Expr::Neg(inner) => write!(f, "-{}", operand(inner, prec)),
+            Expr::Not(inner) => write!(f, "!{}", operand(inner, prec)),
+            Expr::BinaryOp { op, left, right } | Expr::Comparison { op, left, right } => {
+                write!(f, "{} {} {}", operand(left, prec), op, operand(right, prec + 1))
+            }
+            Expr::And(left, right) => {
+                write!(f, "{} && {}", operand(left, prec), operand(right, prec + 1))
+            }
+            Expr::Or(left, right) => {
+                write!(f, "{} || {}", operand(left, prec), operand(right, prec + 1))
+            }
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn parse(input: &str) -> FakResult<Expr> {
+        let mut parser = Parser { tokens: tokenize(input)?, pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(parse_error(format!("unexpected {:?}", token))),
+        }
+    }
+
+    fn peek_op(&self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[&'static str],
+        operand: fn(&mut Self) -> FakResult<Expr>,
+        build: fn(&'static str, Expr, Expr) -> Expr,
+    ) -> FakResult<Expr> {
+        let mut left = operand(self)?;
+        while let Some(op) = self.peek_op(ops) {
+            self.pos += 1;
+            left = build(op, left, operand(self)?);
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> FakResult<Expr> {
+        self.binary(&["||"], Self::and, |_, l, r| Expr::Or(Box::new(l), Box::new(r)))
+    }
+
+    fn and(&mut self) -> FakResult<Expr> {
+        self.binary(&["&&"], Self::comparison, |_, l, r| Expr::And(Box::new(l), Box::new(r)))
+    }
+
+    fn comparison(&mut self) -> FakResult<Expr> {
+        let left = self.sum()?;
+        let Some(op) = self.peek_op(&[">=", "<=", "==", "!=", ">", "<"]) else {
+            return Ok(left);
+        };
+        self.pos += 1;
+        let right = self.sum()?;
+        if self.peek_op(&[">=", "<=", "==", "!=", ">", "<"]).is_some() {
+            return Err(parse_error("comparisons cannot be chained".to_string()));
+        }
+        Ok(Expr::Comparison { op, left: Box::new(left), right: Box::new(right) })
+    }
+
+    fn sum(&mut self) -> FakResult<Expr> {
+        self.binary(&["+", "-"], Self::term, arithmetic)
+    }
+
+    fn term(&mut self) -> FakResult<Expr> {
+        self.binary(&["*", "/"], Self::unary, arithmetic)
+    }
+
+    fn unary(&mut self) -> FakResult<Expr> {
+        match self.peek_op(&["!", "-"]) {
+            Some("!") => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(_) => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> FakResult<Expr> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| parse_error("unexpected end of expression".to_string()))?;
+        self.pos += 1;
+        match token {
+            Token::Number(n) => Ok(Expr::Literal(serde_json::json!(n))),
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::Ident(name) if name == "true" || name == "false" => {
+                Ok(Expr::Literal(Value::Bool(name == "true")))
+            }
+            Token::Ident(name) if self.tokens.get(self.pos) == Some(&Token::LParen) => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.tokens.get(self.pos) != Some(&Token::RParen) {
+                    loop {
+                        args.push(self.or()?);
+                        if self.tokens.get(self.pos) != Some(&Token::Comma) {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                self.expect(Token::RParen)?;
+                Ok(Expr::Call { name, args })
+            }
+            Token::Ident(name) => Ok(Expr::Ident(name)),
+            Token::LParen => {
+                let inner = self.or()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            other => Err(parse_error(format!("unexpected {:?}", other))),
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> FakResult<()> {
+        if self.tokens.get(self.pos) == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(parse_error(format!("expected {:?}", token)))
+        }
+    }
+}
+
+fn arithmetic(op: &'static str, left: Expr, right: Expr) -> Expr {
+    Expr::BinaryOp { op, left: Box::new(left), right: Box::new(right) }
+}
+
+/// Source of identifier values for expression evaluation.
+pub trait ExpressionContext {
+    /// Value of `name`, `Ok(None)` when the name is well-formed but has no
+    /// value, or an error when it cannot name anything in this context.
+    fn resolve(&self, name: &str) -> FakResult<Option<Value>>;
+}
+
+impl<F> ExpressionContext for F
+where
+    F: Fn(&str) -> Option<Value>,
+{
+    fn resolve(&self, name: &str) -> FakResult<Option<Value>> {
+        Ok(self(name))
+    }
+}
+
+/// Names resolve across all four artifacts with the grammar
+///
+/// ```text
+/// name      := namespace "." segment ("." segment)*
+/// namespace := "trace" | "caps" | "cost" | "policy"
+/// segment   := field | field "[" index "]" | field "[*]" | "len"
+/// ```
+///
+/// where `trace` is the execution trace, `caps` the capability manifest,
+/// `cost` the cost ledger and `policy` the policy IR, and fields are those
+/// of the artifact's JSON form (so `cost.metadata.budget` reads ledger
+/// metadata). `[*]` applies the rest of the path to every array element
+/// and yields an array. A trailing `len` is the length of an array, object
+/// or string unless an object has a `len` field of its own. For example
+/// `trace.steps.len`, `cost.total_cost` and `caps.capabilities.len`.
+///
+/// A name outside the four namespaces is a parse error; a path that does
+/// not exist within one resolves to `None`.
+impl ExpressionContext for VerificationContext<'_> {
+    fn resolve(&self, name: &str) -> FakResult<Option<Value>> {
+        let (namespace, path) = name.split_once('.').unwrap_or((name, ""));
+        let artifact = match namespace {
+            "trace" => to_value(self.trace)?,
+            "caps" => to_value(self.capabilities)?,
+            "cost" => to_value(self.cost_ledger)?,
+            "policy" => to_value(self.policy_ir)?,
+            _ => {
+                return Err(parse_error(format!(
+                    "'{}' is not in a known namespace (trace, caps, cost, policy)",
+                    name
+                )))
+            }
+        };
+        if path.is_empty() {
+            return Err(parse_error(format!("'{}' names no field", name)));
+        }
+        let segments: Vec<&str> = path.split('.').collect();
+        Ok(walk(&artifact, &segments))
+    }
+}
+
+/// Namespaces of [`VerificationContext`] identifiers.
+pub const NAMESPACES: [&str; 4] = ["trace", "caps", "cost", "policy"];
+
+/// Whether `name` is a path within one of the [`NAMESPACES`]. A bare
+/// namespace name such as `cost` is not.
+pub fn is_namespaced(name: &str) -> bool {
+    name.split_once('.')
+        .is_some_and(|(namespace, _)| NAMESPACES.contains(&namespace))
+}
+
+/// Resolve a dotted `path` within `value`, using the segment syntax of
+/// [`VerificationContext`] names.
+pub fn resolve_path(value: &Value, path: &str) -> Option<Value> {
+    let segments: Vec<&str> = path.split('.').collect();
+    walk(value, &segments)
+}
+
+fn to_value<T: Serialize>(artifact: &T) -> FakResult<Value> {
+    Ok(serde_json::to_value(artifact)?)
+}
+
+/// Follow dotted `segments` from `value`; see the grammar on
+/// [`VerificationContext`]'s [`ExpressionContext`] impl.
+fn walk(value: &Value, segments: &[&str]) -> Option<Value> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return Some(value.clone());
+    };
+    let (field, index) = match segment.split_once('[') {
+        Some((field, index)) => (field, Some(index.strip_suffix(']')?)),
+        None => (*segment, None),
+    };
+    let child = match value.get(field) {
+        Some(child) => child,
+        None if field == "len" && index.is_none() && rest.is_empty() => {
+            let len = match value {
+                Value::Array(items) => items.len(),
+                Value::Object(map) => map.len(),
+                Value::String(s) => s.chars().count(),
+                _ => return None,
+            };
+            return Some(serde_json::json!(len));
+        }
+        None => return None,
+    };
+    match index {
+        None => walk(child, rest),
+        Some("*") => child
+            .as_array()?
+            .iter()
+            .map(|item| walk(item, rest))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array),
+        Some(i) => walk(child.get(i.parse::<usize>().ok()?)?, rest),
+    }
+}
+
+/// Evaluate `input`, looking identifiers up in `context` and calls in
+/// `functions`.
+///
+/// Numeric comparisons allow `tolerance` of slack as documented on
+/// [`InvariantSpec::tolerance`](crate::types::InvariantSpec::tolerance);
+/// `==` and `!=` also compare strings and booleans exactly. An identifier
+/// that `context` does not know, a type mismatch or a division by zero is
+/// a validation error.
+pub fn evaluate(
+    input: &str,
+    context: &dyn ExpressionContext,
+    functions: &FunctionRegistry,
+    tolerance: f64,
+) -> FakResult<Value> {
+    evaluate_expr(&parse_expr(input)?, context, functions, tolerance)
+}
+
+/// [`evaluate`] for an already parsed expression.
+pub fn evaluate_expr(
+    expr: &Expr,
+    context: &dyn ExpressionContext,
+    functions: &FunctionRegistry,
+    tolerance: f64,
+) -> FakResult<Value> {
+    Evaluator { context, functions, tolerance, steps: None }.eval(expr)
+}
+
+/// One sub-expression and the value it evaluated to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvaluationStep {
+    pub expression: String,
+    pub value: Value,
+}
+
+impl fmt::Display for EvaluationStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}", self.expression, self.value)
+    }
+}
+
+/// Like [`evaluate`], also returning every identifier, call and operator
+/// evaluated, innermost first. Operands skipped by `&&`/`||` short-circuiting
+/// do not appear; literals are left out.
+pub fn evaluate_traced(
+    input: &str,
+    context: &dyn ExpressionContext,
+    functions: &FunctionRegistry,
+    tolerance: f64,
+) -> FakResult<(Value, Vec<EvaluationStep>)> {
+    let expr = parse_expr(input)?;
+    let evaluator = Evaluator {
+        context,
+        functions,
+        tolerance,
+        steps: Some(RefCell::new(Vec::new())),
+    };
+    let value = evaluator.eval(&expr)?;
+    Ok((value, evaluator.steps.map(RefCell::into_inner).unwrap_or_default()))
+}
+
+struct Evaluator<'a> {
+    context: &'a dyn ExpressionContext,
+    functions: &'a FunctionRegistry,
+    tolerance: f64,
+    /// Recorded sub-expression values, when tracing.
+    steps: Option<RefCell<Vec<EvaluationStep>>>,
+}
+
+impl Evaluator<'_> {
+    fn eval(&self, expr: &Expr) -> FakResult<Value> {
+        let value = self.eval_node(expr)?;
+        if let Some(steps) = &self.steps {
+            if !matches!(expr, Expr::Literal(_)) {
+                steps.borrow_mut().push(EvaluationStep {
+                    expression: expr.to_string(),
+                    value: value.clone(),
+                });
+            }
+        }
+        Ok(value)
+    }
+
+    fn eval_node(&self, expr: &Expr) -> FakResult<Value> {
+        match expr {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Ident(name) => self
+                .context
+                .resolve(name)?
+                .ok_or_else(|| eval_error(format!("unresolved identifier '{}'", name))),
+            Expr::Call { name, args } => {
+                let args = args.iter().map(|a| self.eval(a)).collect::<FakResult<Vec<_>>>()?;
+                self.functions.call(name, &args)
+            }
+            Expr::Neg(inner) => Ok(serde_json::json!(-self.number(inner)?)),
+            Expr::Not(inner) => Ok(Value::Bool(!self.boolean(inner)?)),
+            Expr::BinaryOp { op, left, right } => {
+                let (a, b) = (self.number(left)?, self.number(right)?);
+                let value = match *op {
+                    "+" => a + b,
+                    "-" => a - b,
+                    "*" => a * b,
+                    _ if b == 0.0 => return Err(eval_error("division by zero".to_string())),
+                    _ => a / b,
+                };
+                Ok(serde_json::json!(value))
+            }
+            Expr::Comparison { op, left, right } => {
+                self.compare(op, &self.eval(left)?, &self.eval(right)?).map(Value::Bool)
+            }
+            Expr::And(left, right) => Ok(Value::Bool(self.boolean(left)? && self.boolean(right)?)),
+            Expr::Or(left, right) => Ok(Value::Bool(self.boolean(left)? || self.boolean(right)?)),
+        }
+    }
+
+    fn compare(&self, op: &str, left: &Value, right: &Value) -> FakResult<bool> {
+        let t = self.tolerance;
+        match (left.as_f64(), right.as_f64()) {
+            (Some(a), Some(b)) => Ok(match op {
+                ">=" => a >= b - t,
+                "<=" => a <= b + t,
+                ">" => a > b,
+                "<" => a < b,
+                "==" => (a - b).abs() <= t,
+                _ => (a - b).abs() > t,
+            }),
+            _ => match op {
+                "==" => Ok(left == right),
+                "!=" => Ok(left != right),
+                _ => Err(eval_error(format!("cannot order {} and {}", left, right))),
+            },
+        }
+    }
+
+    fn number(&self, expr: &Expr) -> FakResult<f64> {
+        let value = self.eval(expr)?;
+        value.as_f64().ok_or_else(|| eval_error(format!("expected a number, got {}", value)))
+    }
+
+    fn boolean(&self, expr: &Expr) -> FakResult<bool> {
+        let value = self.eval(expr)?;
+        value.as_bool().ok_or_else(|| eval_error(format!("expected a boolean, got {}", value)))
+    }
+}
+
+fn eval_error(message: String) -> FakError {
+    FakError::Validation {
+        field: "expression".to_string(),
+        message,
+    }
+}
+
+fn parse_error(message: String) -> FakError {
+    FakError::ParseError {
+        source: "expression".to_string(),
+        message,
+        line: None,
+        column: None,
+    }
+}
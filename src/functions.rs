@@ -0,0 +1,147 @@
+//! Named functions callable from invariant expressions.
+
+use crate::error::{FakError, FakResult};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Signature of a function callable from invariant expressions.
+pub type ExprFunction = Arc<dyn Fn(&[serde_json::Value]) -> FakResult<serde_json::Value> + Send + Sync>;
+
+/// Registry of expression functions, keyed by name.
+///
+/// The default registry holds the built-ins `abs(x)`, `min(a, ...)`,
+/// `max(a, ...)` and `len(x)`, where `len` accepts an array, string or
+/// object. Registering a function under an existing name replaces it.
+/// Clones share the registered closures.
+#[derive(Clone)]
+pub struct FunctionRegistry {
+    functions: BTreeMap<String, ExprFunction>,
+    versions: BTreeMap<String, String>,
+}
+
+impl FunctionRegistry {
+    /// Registry with the built-in functions.
+    pub fn new() -> Self {
+        Self::empty()
+            .with_function("abs", |args| {
+                let [x] = numeric_args::<1>("abs", args)?;
+                Ok(serde_json::json!(x.abs()))
+            })
+            .with_function("min", |args| fold_numeric("min", args, f64::min))
+            .with_function("max", |args| fold_numeric("max", args, f64::max))
+            .with_function("len", |args| {
+                let len = match args {
+                    [serde_json::Value::Array(items)] => items.len(),
+                    [serde_json::Value::String(s)] => s.chars().count(),
+                    [serde_json::Value::Object(map)] => map.len(),
+                    _ => return Err(call_error("len", "expects one array, string or object")),
+                };
+                Ok(serde_json::json!(len))
+            })
+    }
+
+    /// Registry with no functions at all.
+    pub fn empty() -> Self {
+        Self { functions: BTreeMap::new(), versions: BTreeMap::new() }
+    }
+
+    /// Register `function` under `name`.
+    pub fn with_function<F>(mut self, name: &str, function: F) -> Self
+    where
+        F: Fn(&[serde_json::Value]) -> FakResult<serde_json::Value> + Send + Sync + 'static,
+    {
+        self.functions.insert(name.to_string(), Arc::new(function));
+        self.versions.remove(name);
+        self
+    }
+
+    /// Register `function` under `name`, tagged with `version` so that a
+    /// changed implementation changes [`Self::summary`].
+    pub fn with_versioned_function<F>(self, name: &str, version: &str, function: F) -> Self
+    where
+        F: Fn(&[serde_json::Value]) -> FakResult<serde_json::Value> + Send + Sync + 'static,
+    {
+        let mut registry = self.with_function(name, function);
+        registry.versions.insert(name.to_string(), version.to_string());
+        registry
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Registered function names in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(String::as_str)
+    }
+
+    /// Each registered name mapped to its version, or null if unversioned.
+    pub fn summary(&self) -> serde_json::Value {
+        self.functions
+            .keys()
+            .map(|name| (name.clone(), serde_json::json!(self.versions.get(name))))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    /// Call `name` with `args`, failing on unknown functions.
+    pub fn call(&self, name: &str, args: &[serde_json::Value]) -> FakResult<serde_json::Value> {
+        let function = self
+            .functions
+            .get(name)
+            .ok_or_else(|| call_error(name, "unknown function"))?;
+        function(args)
+    }
+}
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for FunctionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.functions.keys()).finish()
+    }
+}
+
+fn call_error(name: &str, message: &str) -> FakError {
+    FakError::Validation {
+        field: "expression".to_string(),
+        message: format!("{}(): {}", name, message),
+    }
+}
+
+fn numeric_args<const N: usize>(name: &str, args: &[serde_json::Value]) -> FakResult<[f64; N]> {
+    if args.len() != N {
+        return Err(call_error(
+            name,
+            &format!("expects {} argument(s), got {}", N, args.len()),
+        ));
+    }
+    let mut values = [0.0; N];
+    for (slot, arg) in values.iter_mut().zip(args) {
+        *slot = arg
+            .as_f64()
+            .ok_or_else(|| call_error(name, &format!("expects numbers, got {}", arg)))?;
+    }
+    Ok(values)
+}
+
+fn fold_numeric(
+    name: &str,
+    args: &[serde_json::Value],
+    op: fn(f64, f64) -> f64,
+) -> FakResult<serde_json::Value> {
+    let mut values = args.iter().map(|arg| {
+        arg.as_f64()
+            .ok_or_else(|| call_error(name, &format!("expects numbers, got {}", arg)))
+    });
+    let first = values
+        .next()
+        .ok_or_else(|| call_error(name, "expects at least one argument"))??;
+    let result = values.try_fold(first, |acc, v| v.map(|v| op(acc, v)))?;
+    Ok(serde_json::json!(result))
+}
@@ -3,19 +3,37 @@
 //! Core modules for formal verification of governance stack components.
 
 pub mod error;
+#[cfg(feature = "archive")]
+pub mod archive;
 pub mod artifacts;
+pub mod async_artifacts;
+pub mod clock;
+pub mod compact;
 pub mod dsl;
+pub mod encoding;
 pub mod engine;
+pub mod expr;
+pub mod functions;
+pub mod metadata;
+pub mod proto;
+pub mod report;
+pub mod sandbox;
+pub mod schema;
+pub mod store;
+pub mod timestamp;
 pub mod types;
 pub mod verifier;
+pub mod wasm;
 
-pub use error::{FakError, FakResult};
+pub use error::{ErrorCategory, FakError, FakResult};
 pub use artifacts::ArtifactManager;
-pub use dsl::InvariantDSL;
+pub use compact::{CompactBundle, CompactWitness};
+pub use dsl::{DslConfig, InvariantDSL};
 pub use engine::ProofEngine;
 pub use types::{
-    CapabilityManifest, CostLedger, CounterExample, ExecutionTrace, 
-    InvariantSpec, PolicyIR, ProofBundle, ProofType, ProofWitness, 
-    compute_content_hash, VerificationContext,
+    AuthorityEdge, BundleDiff, CapabilityDiff, CapabilityManifest, CostLedger, CounterExample, EntryCostSum, ExecutionTrace, ExternalProof, HashAlgo, IncrementalHasher,
+    InvariantSpec, OwnedVerificationContext, PolicyIR, ProofBundle, ProofType, ProofWitness, RateLimit, WitnessChange,
+    canonical_json, compute_content_hash, compute_content_hash_streaming, compute_content_hash_with, compute_salted_content_hash, short_id, short_id_is_unique_among, VerificationContext,
+    SHORT_ID_LEN,
 };
 pub use verifier::Verifier;
\ No newline at end of file
@@ -0,0 +1,177 @@
+//! Normalization of artifact metadata values.
+//!
+//! Producers disagree on how to write timestamps, and two spellings of the
+//! same instant hash differently. Normalizing the configured keys before
+//! hashing gives every producer the same canonical form: an RFC 3339 UTC
+//! string with exactly millisecond precision, e.g.
+//! `2024-01-01T12:00:00.000Z`.
+//!
+//! Recognized inputs are JSON integers holding milliseconds since the Unix
+//! epoch, and RFC 3339 strings with a `Z` or `±HH:MM` offset and optional
+//! fractional seconds. Fractions finer than a millisecond are truncated.
+//! Only years 0000 through 9999 can be represented.
+
+use crate::error::{FakError, FakResult};
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// Rewrite each of `keys` present in `metadata` to the canonical timestamp
+/// form. Keys that are absent are skipped; a present value that is not a
+/// recognized timestamp is a validation error naming the key.
+pub fn normalize_timestamps(
+    metadata: &mut serde_json::Map<String, serde_json::Value>,
+    keys: &[String],
+) -> FakResult<()> {
+    for key in keys {
+        if let Some(value) = metadata.get_mut(key) {
+            let canonical = canonical_timestamp(value).map_err(|message| FakError::Validation {
+                field: key.clone(),
+                message,
+            })?;
+            *value = serde_json::Value::String(canonical);
+        }
+    }
+    Ok(())
+}
+
+/// [`normalize_timestamps`] applied to the `metadata` object of a JSON
+/// artifact; artifacts without one are left alone.
+pub fn normalize_artifact_timestamps(artifact: &mut serde_json::Value, keys: &[String]) -> FakResult<()> {
+    match artifact.get_mut("metadata").and_then(|m| m.as_object_mut()) {
+        Some(metadata) => normalize_timestamps(metadata, keys),
+        None => Ok(()),
+    }
+}
+
+/// Canonical RFC 3339 form of an epoch-millis number or RFC 3339 string.
+pub fn canonical_timestamp(value: &serde_json::Value) -> Result<String, String> {
+    let millis = match value {
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .ok_or_else(|| format!("epoch milliseconds must be an integer, got {}", n))?,
+        serde_json::Value::String(s) => {
+            parse_rfc3339(s).ok_or_else(|| format!("'{}' is not an RFC 3339 timestamp", s))?
+        }
+        other => return Err(format!("expected a timestamp, got {}", other)),
+    };
+    format_rfc3339(millis).ok_or_else(|| format!("timestamp {} ms is out of range", millis))
+}
+
+/// Milliseconds since the Unix epoch for an RFC 3339 date-time.
+fn parse_rfc3339(s: &str) -> Option<i64> {
+    let b = s.as_bytes();
+    let digits = |range: std::ops::Range<usize>| -> Option<i64> {
+        let part = b.get(range)?;
+        part.iter().all(u8::is_ascii_digit).then(|| {
+            part.iter().fold(0, |acc, d| acc * 10 + i64::from(d - b'0'))
+        })
+    };
+    let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+    if b.len() < 20
+        || separators.iter().any(|&(i, c)| b[i] != c)
+        || !matches!(b[10], b'T' | b't' | b' ')
+    {
+        return None;
+    }
+    let (year, month, day) = (digits(0..4)?, digits(5..7)?, digits(8..10)?);
+    let (hour, minute, second) = (digits(11..13)?, digits(14..16)?, digits(17..19)?);
+    if !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+
+    let mut rest = &b[19..];
+    let mut fraction_millis = 0;
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let len = fraction.iter().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        fraction_millis = fraction[..len.min(3)]
+            .iter()
+            .chain(std::iter::repeat(&b'0'))
+            .take(3)
+            .fold(0, |acc, d| acc * 10 + i64::from(d - b'0'));
+        rest = &fraction[len..];
+    }
+    let offset_minutes = match rest {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let two = |a: u8, b: u8| {
+                (a.is_ascii_digit() && b.is_ascii_digit())
+                    .then(|| i64::from(a - b'0') * 10 + i64::from(b - b'0'))
+            };
+            let (h, m) = (two(*h1, *h2)?, two(*m1, *m2)?);
+            if h > 23 || m > 59 {
+                return None;
+            }
+            if *sign == b'-' {
+                -(h * 60 + m)
+            } else {
+                h * 60 + m
+            }
+        }
+        _ => return None,
+    };
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second
+        - offset_minutes * 60;
+    Some(seconds * 1000 + fraction_millis)
+}
+
+fn format_rfc3339(millis: i64) -> Option<String> {
+    let (year, month, day) = civil_from_days(millis.div_euclid(MILLIS_PER_DAY));
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+    let ms = millis.rem_euclid(MILLIS_PER_DAY);
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    ))
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,156 @@
+//! Protobuf messages for verification results.
+//!
+//! The messages mirror `proto/fak.proto` (package `fak.v1`) and encode
+//! wire-compatibly with code generated from it, so RPC services can send
+//! results without a hand-written adapter.
+
+#![cfg(feature = "proto")]
+
+use crate::types::canonical_json;
+use std::collections::BTreeMap;
+
+/// Protobuf form of [`crate::verifier::BundleResult`].
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BundleResult {
+    #[prost(string, tag = "1")]
+    pub bundle_id: String,
+    #[prost(bool, tag = "2")]
+    pub success: bool,
+    #[prost(message, repeated, tag = "3")]
+    pub witness_results: Vec<WitnessResult>,
+    #[prost(string, optional, tag = "4")]
+    pub error: Option<String>,
+    #[prost(string, repeated, tag = "5")]
+    pub warnings: Vec<String>,
+}
+
+/// Protobuf form of [`crate::verifier::WitnessResult`].
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct WitnessResult {
+    #[prost(string, tag = "1")]
+    pub proof_id: String,
+    #[prost(bool, tag = "2")]
+    pub success: bool,
+    #[prost(uint64, tag = "3")]
+    pub invariant_count: u64,
+    #[prost(uint64, tag = "4")]
+    pub counterexample_count: u64,
+    #[prost(uint64, tag = "5")]
+    pub violation_count: u64,
+    #[prost(uint64, tag = "6")]
+    pub error_count: u64,
+    #[prost(string, optional, tag = "7")]
+    pub error: Option<String>,
+    #[prost(btree_map = "string, string", tag = "8")]
+    pub remediations: BTreeMap<String, String>,
+    #[prost(string, repeated, tag = "9")]
+    pub not_evaluated: Vec<String>,
+    #[prost(btree_map = "string, message", tag = "10")]
+    pub externally_verified: BTreeMap<String, ExternalProof>,
+    #[prost(string, tag = "11")]
+    pub witness_hash: String,
+    #[prost(message, repeated, tag = "12")]
+    pub counterexamples: Vec<CounterExample>,
+    #[prost(string, repeated, tag = "13")]
+    pub vacuous: Vec<String>,
+}
+
+/// Protobuf form of [`crate::types::ExternalProof`].
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExternalProof {
+    #[prost(string, tag = "1")]
+    pub tool: String,
+    #[prost(string, tag = "2")]
+    pub result: String,
+}
+
+/// Protobuf form of [`crate::types::CounterExample`]; `details` travel as
+/// canonical JSON text.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CounterExample {
+    #[prost(string, tag = "1")]
+    pub invariant_name: String,
+    #[prost(string, tag = "2")]
+    pub error_type: String,
+    #[prost(string, tag = "3")]
+    pub details_json: String,
+    #[prost(uint64, optional, tag = "4")]
+    pub step_index: Option<u64>,
+}
+
+impl From<&crate::verifier::BundleResult> for BundleResult {
+    fn from(result: &crate::verifier::BundleResult) -> Self {
+        Self {
+            bundle_id: result.bundle_id.clone(),
+            success: result.success,
+            witness_results: result.witness_results.iter().map(WitnessResult::from).collect(),
+            error: result.error.clone(),
+            warnings: result.warnings.clone(),
+        }
+    }
+}
+
+impl From<&crate::verifier::WitnessResult> for WitnessResult {
+    fn from(result: &crate::verifier::WitnessResult) -> Self {
+        Self {
+            proof_id: result.proof_id.clone(),
+            success: result.success,
+            invariant_count: result.invariant_count as u64,
+            counterexample_count: result.counterexample_count as u64,
+            violation_count: result.violation_count as u64,
+            error_count: result.error_count as u64,
+            error: result.error.clone(),
+            remediations: result.remediations.clone(),
+            not_evaluated: result.not_evaluated.clone(),
+            externally_verified: result
+                .externally_verified
+                .iter()
+                .map(|(name, proof)| (name.clone(), ExternalProof::from(proof)))
+                .collect(),
+            witness_hash: result.witness_hash.clone(),
+            counterexamples: result.counterexamples.iter().map(CounterExample::from).collect(),
+            vacuous: result.vacuous.clone(),
+        }
+    }
+}
+
+impl From<&crate::types::ExternalProof> for ExternalProof {
+    fn from(proof: &crate::types::ExternalProof) -> Self {
+        Self {
+            tool: proof.tool.clone(),
+            result: proof.result.clone(),
+        }
+    }
+}
+
+impl From<&crate::types::CounterExample> for CounterExample {
+    fn from(counterexample: &crate::types::CounterExample) -> Self {
+        Self {
+            invariant_name: counterexample.invariant_name.clone(),
+            error_type: counterexample.error_type.clone(),
+            details_json: canonical_json(&counterexample.details),
+            step_index: counterexample.step_index.map(|i| i as u64),
+        }
+    }
+}
+
+impl crate::verifier::BundleResult {
+    /// Convert to the `fak.v1.BundleResult` protobuf message.
+    pub fn to_proto(&self) -> BundleResult {
+        self.into()
+    }
+}
+
+impl crate::verifier::WitnessResult {
+    /// Convert to the `fak.v1.WitnessResult` protobuf message.
+    pub fn to_proto(&self) -> WitnessResult {
+        self.into()
+    }
+}
+
+impl crate::types::CounterExample {
+    /// Convert to the `fak.v1.CounterExample` protobuf message.
+    pub fn to_proto(&self) -> CounterExample {
+        self.into()
+    }
+}
@@ -0,0 +1,74 @@
+//! Human-readable rendering of verification results for logs.
+
+use crate::verifier::BundleResult;
+use std::fmt::Write;
+
+/// Multi-line report: the bundle's status, then one line per witness with
+/// its invariant and counterexample counts, followed by any error and
+/// remediation hints.
+pub fn format_bundle_result(result: &BundleResult) -> String {
+    let mut out = String::new();
+    write_bundle_result(result, &mut out).expect("writing to a String cannot fail");
+    out
+}
+
+/// One-line report, e.g. `bundle 3f2a…: FAIL (1/2 witnesses passed, 3 counterexamples)`.
+pub fn format_bundle_result_summary(result: &BundleResult) -> String {
+    let passed = result.witness_results.iter().filter(|wr| wr.success).count();
+    let counterexamples: usize = result.witness_results.iter().map(|wr| wr.counterexample_count).sum();
+    let mut line = format!(
+        "bundle {}: {} ({}/{} witnesses passed, {} counterexamples)",
+        result.bundle_id,
+        status_label(result.success),
+        passed,
+        result.witness_results.len(),
+        counterexamples
+    );
+    if let Some(error) = &result.error {
+        let _ = write!(line, ": {}", error);
+    }
+    line
+}
+
+fn write_bundle_result(result: &BundleResult, w: &mut String) -> std::fmt::Result {
+    writeln!(w, "bundle {}: {}", result.bundle_id, status_label(result.success))?;
+    if let Some(error) = &result.error {
+        writeln!(w, "  error: {}", error)?;
+    }
+    for wr in &result.witness_results {
+        writeln!(
+            w,
+            "  witness {}: {} ({} invariants, {} counterexamples: {} violations, {} check errors)",
+            wr.proof_id,
+            status_label(wr.success),
+            wr.invariant_count,
+            wr.counterexample_count,
+            wr.violation_count,
+            wr.error_count
+        )?;
+        if let Some(error) = &wr.error {
+            writeln!(w, "    error: {}", error)?;
+        }
+        for (name, hint) in &wr.remediations {
+            writeln!(w, "    {} failed: {}", name, hint)?;
+        }
+        if !wr.not_evaluated.is_empty() {
+            writeln!(w, "    not evaluated: {}", wr.not_evaluated.join(", "))?;
+        }
+        if !wr.vacuous.is_empty() {
+            writeln!(w, "    vacuously true: {}", wr.vacuous.join(", "))?;
+        }
+        for (name, proof) in &wr.externally_verified {
+            writeln!(w, "    {} externally verified by {}: {}", name, proof.tool, proof.result)?;
+        }
+    }
+    Ok(())
+}
+
+fn status_label(success: bool) -> &'static str {
+    if success {
+        "PASS"
+    } else {
+        "FAIL"
+    }
+}
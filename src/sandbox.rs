@@ -0,0 +1,122 @@
+//! Memory accounting for verifying untrusted bundles.
+//!
+//! [`TrackingAllocator`] wraps the system allocator and counts live heap
+//! bytes. Install it as the global allocator of the verifying binary:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOC: fak::sandbox::TrackingAllocator = fak::sandbox::TrackingAllocator;
+//! ```
+//!
+//! [`Verifier::verify_bundle_with_memory_limit`] then bounds how far the
+//! peak heap may rise above its level when verification started.
+//!
+//! The limit is enforced by polling, not inside the allocator: the peak is
+//! checked before the witnesses are verified and after each one, so a single
+//! witness can overshoot the limit before verification is aborted. Counts are
+//! process-wide, so allocations by other threads during verification count
+//! against the limit too, and concurrent memory-limited verifications share
+//! one peak. Only heap allocations through the global allocator are seen;
+//! stack usage and allocator overhead are not.
+//!
+//! [`Verifier::verify_bundle_with_memory_limit`]: crate::Verifier::verify_bundle_with_memory_limit
+
+use crate::error::{FakError, FakResult};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Global allocator that forwards to [`System`] and counts live bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    fn grow(size: usize) {
+        let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+    }
+
+    fn shrink(size: usize) {
+        CURRENT.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            INSTALLED.store(true, Ordering::Relaxed);
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            INSTALLED.store(true, Ordering::Relaxed);
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                Self::grow(new_size - layout.size());
+            } else {
+                Self::shrink(layout.size() - new_size);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// Live heap bytes, or `None` if [`TrackingAllocator`] is not the global
+/// allocator.
+pub fn allocated_bytes() -> Option<usize> {
+    INSTALLED
+        .load(Ordering::Relaxed)
+        .then(|| CURRENT.load(Ordering::Relaxed))
+}
+
+/// Peak-above-baseline check used by memory-limited verification.
+#[derive(Debug)]
+pub(crate) struct MemoryGuard {
+    baseline: usize,
+    limit: usize,
+}
+
+impl MemoryGuard {
+    /// Start measuring from the current heap size.
+    pub(crate) fn start(limit: usize) -> FakResult<Self> {
+        let baseline = allocated_bytes().ok_or_else(|| FakError::Validation {
+            field: "memory_limit".to_string(),
+            message: "memory limits require fak::sandbox::TrackingAllocator as the global allocator"
+                .to_string(),
+        })?;
+        PEAK.store(baseline, Ordering::Relaxed);
+        Ok(Self { baseline, limit })
+    }
+
+    pub(crate) fn check(&self) -> FakResult<()> {
+        let used = PEAK.load(Ordering::Relaxed).saturating_sub(self.baseline);
+        if used > self.limit {
+            return Err(FakError::ResourceLimit {
+                resource: "memory".to_string(),
+                limit: self.limit,
+                actual: used,
+            });
+        }
+        Ok(())
+    }
+}
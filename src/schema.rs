@@ -0,0 +1,31 @@
+//! JSON Schema for the artifact and bundle types.
+//!
+//! Built with the `schema` feature. The schemas describe the serialized
+//! form, so they are a contract for producers written in other languages.
+
+#![cfg(feature = "schema")]
+
+use crate::types::{
+    CapabilityManifest, CostLedger, CounterExample, ExecutionTrace, InvariantSpec, PolicyIR,
+    ProofBundle, ProofWitness,
+};
+use schemars::schema_for;
+use std::collections::HashMap;
+
+/// JSON Schema of each public artifact and bundle type, keyed by type name.
+pub fn artifact_schemas() -> HashMap<&'static str, serde_json::Value> {
+    fn to_value(schema: schemars::schema::RootSchema) -> serde_json::Value {
+        serde_json::to_value(schema).expect("schemas serialize to JSON")
+    }
+
+    HashMap::from([
+        ("ExecutionTrace", to_value(schema_for!(ExecutionTrace))),
+        ("CapabilityManifest", to_value(schema_for!(CapabilityManifest))),
+        ("CostLedger", to_value(schema_for!(CostLedger))),
+        ("PolicyIR", to_value(schema_for!(PolicyIR))),
+        ("InvariantSpec", to_value(schema_for!(InvariantSpec))),
+        ("CounterExample", to_value(schema_for!(CounterExample))),
+        ("ProofWitness", to_value(schema_for!(ProofWitness))),
+        ("ProofBundle", to_value(schema_for!(ProofBundle))),
+    ])
+}
@@ -0,0 +1,239 @@
+//! Storage backends for [`ArtifactManager`](crate::ArtifactManager).
+//!
+//! A store maps artifact IDs to artifact JSON. IDs are computed by the
+//! manager; stores only persist what they are given, so integrity checks
+//! work the same over every backend.
+
+use crate::artifacts::ArtifactSnapshot;
+use crate::error::{FakError, FakResult};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Backend holding an artifact manager's artifacts.
+pub trait ArtifactStore: Send + Sync + fmt::Debug {
+    /// Artifact stored under `artifact_id`, if any.
+    fn get(&self, artifact_id: &str) -> FakResult<Option<serde_json::Value>>;
+
+    /// Store `artifact` under `artifact_id` unless that ID is already held.
+    /// Returns whether the artifact was newly stored.
+    fn put(&self, artifact_id: &str, artifact: &serde_json::Value) -> FakResult<bool>;
+
+    fn contains(&self, artifact_id: &str) -> FakResult<bool>;
+
+    /// Remove and return the artifact stored under `artifact_id`.
+    fn remove(&self, artifact_id: &str) -> FakResult<Option<serde_json::Value>>;
+
+    fn clear(&self) -> FakResult<()>;
+
+    /// IDs of every stored artifact, in no particular order.
+    fn ids(&self) -> FakResult<Vec<String>>;
+
+    /// Immutable copy of the current contents.
+    fn snapshot(&self) -> FakResult<ArtifactSnapshot> {
+        let mut artifacts = HashMap::new();
+        for artifact_id in self.ids()? {
+            if let Some(artifact) = self.get(&artifact_id)? {
+                artifacts.insert(artifact_id, artifact);
+            }
+        }
+        Ok(ArtifactSnapshot::new(Arc::new(artifacts)))
+    }
+
+    /// Store for a cloned manager. In-memory stores copy their contents so
+    /// the clone evolves independently; persistent stores hand out another
+    /// handle to the same storage.
+    fn fork(&self) -> Box<dyn ArtifactStore>;
+}
+
+/// Default in-memory store.
+///
+/// The map is copied on write, so snapshots are O(1) and never observe
+/// later changes.
+#[derive(Debug, Default)]
+pub struct MemoryArtifactStore {
+    artifacts: RwLock<Arc<HashMap<String, serde_json::Value>>>,
+}
+
+impl MemoryArtifactStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> FakResult<Arc<HashMap<String, serde_json::Value>>> {
+        self.artifacts
+            .read()
+            .map(|artifacts| Arc::clone(&artifacts))
+            .map_err(|_| FakError::LockPoisoned {
+                resource: "artifacts".to_string(),
+            })
+    }
+
+    fn write<T>(&self, f: impl FnOnce(&mut HashMap<String, serde_json::Value>) -> T) -> FakResult<T> {
+        let mut artifacts = self.artifacts.write().map_err(|_| FakError::LockPoisoned {
+            resource: "artifacts".to_string(),
+        })?;
+        Ok(f(Arc::make_mut(&mut artifacts)))
+    }
+}
+
+impl ArtifactStore for MemoryArtifactStore {
+    fn get(&self, artifact_id: &str) -> FakResult<Option<serde_json::Value>> {
+        Ok(self.read()?.get(artifact_id).cloned())
+    }
+
+    fn put(&self, artifact_id: &str, artifact: &serde_json::Value) -> FakResult<bool> {
+        if self.contains(artifact_id)? {
+            return Ok(false);
+        }
+        self.write(|artifacts| {
+            artifacts
+                .insert(artifact_id.to_string(), artifact.clone())
+                .is_none()
+        })
+    }
+
+    fn contains(&self, artifact_id: &str) -> FakResult<bool> {
+        Ok(self.read()?.contains_key(artifact_id))
+    }
+
+    fn remove(&self, artifact_id: &str) -> FakResult<Option<serde_json::Value>> {
+        if !self.contains(artifact_id)? {
+            return Ok(None);
+        }
+        self.write(|artifacts| artifacts.remove(artifact_id))
+    }
+
+    fn clear(&self) -> FakResult<()> {
+        let mut artifacts = self.artifacts.write().map_err(|_| FakError::LockPoisoned {
+            resource: "artifacts".to_string(),
+        })?;
+        *artifacts = Arc::default();
+        Ok(())
+    }
+
+    fn ids(&self) -> FakResult<Vec<String>> {
+        Ok(self.read()?.keys().cloned().collect())
+    }
+
+    fn snapshot(&self) -> FakResult<ArtifactSnapshot> {
+        Ok(ArtifactSnapshot::new(self.read()?))
+    }
+
+    fn fork(&self) -> Box<dyn ArtifactStore> {
+        let artifacts = self.read().unwrap_or_default();
+        Box::new(Self {
+            artifacts: RwLock::new(artifacts),
+        })
+    }
+}
+
+/// Store that keeps each artifact as `<id>.json` under a root directory.
+///
+/// The `:` of algorithm-tagged IDs (`blake3:…`) is written as `.` in file
+/// names, which plain IDs never contain.
+///
+/// Artifacts are read from disk on every lookup, so a manager created over
+/// an existing directory sees everything a previous process stored there.
+/// Files are written to a temporary name and renamed into place, so a
+/// crash never leaves a partial artifact under its final name.
+#[derive(Debug, Clone)]
+pub struct FileArtifactStore {
+    root: PathBuf,
+}
+
+impl FileArtifactStore {
+    /// Open the store rooted at `root`, creating the directory if needed.
+    pub fn new(root: impl Into<PathBuf>) -> FakResult<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, artifact_id: &str) -> FakResult<PathBuf> {
+        let valid = !artifact_id.is_empty()
+            && artifact_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':');
+        if !valid {
+            return Err(FakError::Validation {
+                field: "artifact_id".to_string(),
+                message: format!("'{}' is not usable as a file name", artifact_id),
+            });
+        }
+        Ok(self.root.join(format!("{}.json", artifact_id.replace(':', "."))))
+    }
+}
+
+impl ArtifactStore for FileArtifactStore {
+    fn get(&self, artifact_id: &str) -> FakResult<Option<serde_json::Value>> {
+        let path = self.path(artifact_id)?;
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| FakError::ParseError {
+                source: path.display().to_string(),
+                message: e.to_string(),
+                line: None,
+                column: None,
+            })
+    }
+
+    fn put(&self, artifact_id: &str, artifact: &serde_json::Value) -> FakResult<bool> {
+        let path = self.path(artifact_id)?;
+        if path.exists() {
+            return Ok(false);
+        }
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&serde_json::to_vec(artifact)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        Ok(true)
+    }
+
+    fn contains(&self, artifact_id: &str) -> FakResult<bool> {
+        Ok(self.path(artifact_id)?.exists())
+    }
+
+    fn remove(&self, artifact_id: &str) -> FakResult<Option<serde_json::Value>> {
+        let artifact = self.get(artifact_id)?;
+        if artifact.is_some() {
+            fs::remove_file(self.path(artifact_id)?)?;
+        }
+        Ok(artifact)
+    }
+
+    fn clear(&self) -> FakResult<()> {
+        for artifact_id in self.ids()? {
+            fs::remove_file(self.path(&artifact_id)?)?;
+        }
+        Ok(())
+    }
+
+    fn ids(&self) -> FakResult<Vec<String>> {
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let name = entry?.file_name();
+            if let Some(id) = name.to_str().and_then(|n| n.strip_suffix(".json")) {
+                ids.push(id.replace('.', ":"));
+            }
+        }
+        Ok(ids)
+    }
+
+    fn fork(&self) -> Box<dyn ArtifactStore> {
+        Box::new(self.clone())
+    }
+}
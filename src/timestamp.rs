@@ -0,0 +1,326 @@
+//! RFC 3161 trusted timestamps over bundle IDs.
+//!
+//! A timestamp authority (TSA) signs a token whose message imprint is the
+//! SHA-256 digest of the data it vouches for. For a bundle that digest is
+//! the bundle ID itself, so a token covers a bundle exactly when its imprint
+//! equals the bundle's recomputed content ID.
+//!
+//! The TSA certificate is trusted as supplied: its own issuer chain and
+//! revocation status are the caller's concern.
+
+#![cfg(feature = "timestamping")]
+
+use crate::error::{FakError, FakResult};
+use crate::types::ProofBundle;
+use crate::verifier::{BundleResult, Verifier};
+use cms::content_info::ContentInfo;
+use cms::signed_data::{SignedData, SignerIdentifier, SignerInfo};
+use der::asn1::{AnyRef, Int, ObjectIdentifier, OctetString};
+use der::{Decode, Encode, Reader, SliceReader, Tag, Tagged};
+use p256::ecdsa::signature::Verifier as _;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use sha2::{Digest, Sha256};
+use x509_cert::ext::pkix::SubjectKeyIdentifier;
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use x509_cert::Certificate;
+
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const ID_SUBJECT_KEY_ID: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.14");
+const ID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const ID_EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const ID_PRIME256V1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const ID_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+
+/// What a timestamp token attests about a bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampInfo {
+    /// Time asserted by the TSA, in seconds since the Unix epoch.
+    pub gen_time_secs: u64,
+    /// TSA policy under which the token was issued.
+    pub policy: String,
+    /// Token serial number as lowercase hex.
+    pub serial: String,
+}
+
+/// Check that an RFC 3161 timestamp covers `bundle`.
+///
+/// `tsr` is a DER `TimeStampResp` or a bare `TimeStampToken`, and `tsa_cert`
+/// the DER certificate of the issuing TSA. The token's SHA-256 message
+/// imprint must equal the bundle's recomputed ID, its time must fall within
+/// the certificate's validity period, and a signer entry identifying that
+/// certificate must carry a valid signature.
+///
+/// The signer must use SHA-256 with signed attributes whose message digest
+/// matches the TSTInfo; the signature over those attributes is verified
+/// with the certificate's ECDSA P-256 or RSA (PKCS#1 v1.5) key.
+pub fn check_timestamp(
+    bundle: &ProofBundle,
+    tsr: &[u8],
+    tsa_cert: &[u8],
+) -> FakResult<TimestampInfo> {
+    let token = parse_token(tsr)?;
+    if token.content_type != ID_SIGNED_DATA {
+        return Err(invalid("token is not CMS SignedData"));
+    }
+    let signed_data: SignedData = token.content.decode_as().map_err(parse_error)?;
+    let encap = &signed_data.encap_content_info;
+    if encap.econtent_type != ID_CT_TST_INFO {
+        return Err(invalid("token does not encapsulate a TSTInfo"));
+    }
+    let tst_der: OctetString = encap
+        .econtent
+        .as_ref()
+        .ok_or_else(|| invalid("token has no TSTInfo content"))?
+        .decode_as()
+        .map_err(parse_error)?;
+    let tst = parse_tst_info(tst_der.as_bytes()).map_err(parse_error)?;
+
+    if tst.imprint_algorithm != ID_SHA256 {
+        return Err(invalid(&format!(
+            "message imprint uses {}, expected SHA-256",
+            tst.imprint_algorithm
+        )));
+    }
+    let expected = bundle.content_id();
+    let imprint = hex(&tst.hashed_message);
+    if imprint != expected {
+        return Err(invalid(&format!(
+            "message imprint {} does not cover bundle content {}",
+            imprint, expected
+        )));
+    }
+
+    let cert = Certificate::from_der(tsa_cert).map_err(parse_error)?;
+    let validity = &cert.tbs_certificate.validity;
+    let not_before = validity.not_before.to_unix_duration().as_secs();
+    let not_after = validity.not_after.to_unix_duration().as_secs();
+    if tst.gen_time_secs < not_before || tst.gen_time_secs > not_after {
+        return Err(invalid(&format!(
+            "token time {} is outside the TSA certificate validity {}..{}",
+            tst.gen_time_secs, not_before, not_after
+        )));
+    }
+    let signer = signed_data
+        .signer_infos
+        .0
+        .iter()
+        .find(|si| identifies(&si.sid, &cert))
+        .ok_or_else(|| invalid("token is not signed by the supplied TSA certificate"))?;
+    verify_signer(signer, tst_der.as_bytes(), &cert)?;
+
+    Ok(TimestampInfo {
+        gen_time_secs: tst.gen_time_secs,
+        policy: tst.policy.to_string(),
+        serial: hex(tst.serial.as_bytes()),
+    })
+}
+
+impl Verifier {
+    /// Verify a bundle and check that an RFC 3161 timestamp covers it.
+    ///
+    /// Timestamp problems are reported as a bundle-level error alongside any
+    /// other verification failure; see [`check_timestamp`] for the checks.
+    pub fn verify_timestamped_bundle(
+        &self,
+        bundle: &ProofBundle,
+        tsr: &[u8],
+        tsa_cert: &[u8],
+    ) -> BundleResult {
+        let mut result = self.verify_bundle(bundle);
+        if let Err(e) = check_timestamp(bundle, tsr, tsa_cert) {
+            let message = format!("timestamp: {}", e);
+            result.success = false;
+            result.error = Some(match result.error.take() {
+                Some(existing) => format!("{}; {}", existing, message),
+                None => message,
+            });
+        }
+        result
+    }
+}
+
+struct TstInfo {
+    policy: ObjectIdentifier,
+    imprint_algorithm: ObjectIdentifier,
+    hashed_message: Vec<u8>,
+    serial: Int,
+    gen_time_secs: u64,
+}
+
+/// Accept either a `TimeStampResp` or the `ContentInfo` token it wraps.
+fn parse_token(der: &[u8]) -> FakResult<ContentInfo> {
+    if let Ok(token) = ContentInfo::from_der(der) {
+        return Ok(token);
+    }
+    let mut reader = SliceReader::new(der).map_err(parse_error)?;
+    let (status, token) = reader
+        .sequence(|r| {
+            let status: u32 = r.sequence(|s| {
+                let status = s.decode()?;
+                s.read_slice(s.remaining_len())?;
+                Ok(status)
+            })?;
+            let token: Option<ContentInfo> = if r.is_finished() { None } else { Some(r.decode()?) };
+            Ok((status, token))
+        })
+        .map_err(parse_error)?;
+    reader.finish(()).map_err(parse_error)?;
+    match (status, token) {
+        (0 | 1, Some(token)) => Ok(token),
+        (0 | 1, None) => Err(invalid("response carries no timestamp token")),
+        (status, _) => Err(invalid(&format!("TSA refused the request with status {}", status))),
+    }
+}
+
+fn parse_tst_info(der: &[u8]) -> der::Result<TstInfo> {
+    let mut reader = SliceReader::new(der)?;
+    let info = reader.sequence(|r| {
+        let _version: u8 = r.decode()?;
+        let policy = r.decode()?;
+        let (imprint_algorithm, hashed_message) = r.sequence(|m| {
+            let algorithm: AlgorithmIdentifierOwned = m.decode()?;
+            let hashed: OctetString = m.decode()?;
+            Ok((algorithm.oid, hashed.into_bytes()))
+        })?;
+        let serial = r.decode()?;
+        let gen_time: AnyRef<'_> = r.decode()?;
+        // Accuracy, ordering, nonce, tsa name and extensions are not needed.
+        r.read_slice(r.remaining_len())?;
+        Ok(TstInfo {
+            policy,
+            imprint_algorithm,
+            hashed_message,
+            serial,
+            gen_time_secs: generalized_time_secs(gen_time)?,
+        })
+    })?;
+    reader.finish(info)
+}
+
+/// Parse a `GeneralizedTime`, allowing the fractional seconds RFC 3161 permits.
+fn generalized_time_secs(any: AnyRef<'_>) -> der::Result<u64> {
+    any.tag().assert_eq(Tag::GeneralizedTime)?;
+    let value = std::str::from_utf8(any.value()).map_err(|_| Tag::GeneralizedTime.value_error())?;
+    let digits = value
+        .strip_suffix('Z')
+        .map(|v| v.split('.').next().unwrap_or(v))
+        .filter(|v| v.len() == 14 && v.bytes().all(|b| b.is_ascii_digit()))
+        .ok_or_else(|| Tag::GeneralizedTime.value_error())?;
+    let field = |range: std::ops::Range<usize>| digits[range].parse::<u16>().unwrap_or(0);
+    let datetime = der::DateTime::new(
+        field(0..4),
+        field(4..6) as u8,
+        field(6..8) as u8,
+        field(8..10) as u8,
+        field(10..12) as u8,
+        field(12..14) as u8,
+    )?;
+    Ok(datetime.unix_duration().as_secs())
+}
+
+/// Check the signed attributes against `econtent` and the signature over
+/// them against the certificate's public key.
+fn verify_signer(si: &SignerInfo, econtent: &[u8], cert: &Certificate) -> FakResult<()> {
+    if si.digest_alg.oid != ID_SHA256 {
+        return Err(invalid(&format!(
+            "signer digest uses {}, expected SHA-256",
+            si.digest_alg.oid
+        )));
+    }
+    let attrs = si
+        .signed_attrs
+        .as_ref()
+        .ok_or_else(|| invalid("signer has no signed attributes"))?;
+    let attribute = |oid: ObjectIdentifier| {
+        let mut found = attrs.iter().filter(|attr| attr.oid == oid);
+        match (found.next(), found.next()) {
+            (Some(attr), None) if attr.values.len() == 1 => Ok(&attr.values.as_slice()[0]),
+            _ => Err(invalid(&format!("signed attribute {} must appear once with one value", oid))),
+        }
+    };
+    let content_type: ObjectIdentifier =
+        attribute(ID_CONTENT_TYPE)?.decode_as().map_err(parse_error)?;
+    if content_type != ID_CT_TST_INFO {
+        return Err(invalid("signed content type is not TSTInfo"));
+    }
+    let digest: OctetString = attribute(ID_MESSAGE_DIGEST)?.decode_as().map_err(parse_error)?;
+    if digest.as_bytes() != Sha256::digest(econtent).as_slice() {
+        return Err(invalid("signed message digest does not match the TSTInfo"));
+    }
+
+    // The signature covers the attributes DER-encoded as a SET OF rather
+    // than with their [0] IMPLICIT tag.
+    let signed = attrs.to_der().map_err(parse_error)?;
+    let signature = si.signature.as_bytes();
+    let spki = &cert.tbs_certificate.subject_public_key_info;
+    let key = spki.subject_public_key.raw_bytes();
+    let verified = match spki.algorithm.oid {
+        ID_EC_PUBLIC_KEY => {
+            let curve = spki
+                .algorithm
+                .parameters
+                .as_ref()
+                .and_then(|p| p.decode_as::<ObjectIdentifier>().ok());
+            if curve != Some(ID_PRIME256V1) {
+                return Err(invalid("TSA certificate uses an unsupported elliptic curve"));
+            }
+            let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(key)
+                .map_err(|_| invalid("TSA certificate has a malformed P-256 key"))?;
+            p256::ecdsa::Signature::from_der(signature)
+                .map(|sig| key.verify(&signed, &sig).is_ok())
+                .unwrap_or(false)
+        }
+        ID_RSA_ENCRYPTION => {
+            let key = rsa::RsaPublicKey::from_pkcs1_der(key)
+                .map_err(|_| invalid("TSA certificate has a malformed RSA key"))?;
+            key.verify(rsa::Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(&signed), signature)
+                .is_ok()
+        }
+        other => {
+            return Err(invalid(&format!("TSA certificate key algorithm {} is not supported", other)))
+        }
+    };
+    if !verified {
+        return Err(invalid("token signature does not verify against the TSA certificate"));
+    }
+    Ok(())
+}
+
+fn identifies(sid: &SignerIdentifier, cert: &Certificate) -> bool {
+    let tbs = &cert.tbs_certificate;
+    match sid {
+        SignerIdentifier::IssuerAndSerialNumber(ias) => {
+            ias.issuer == tbs.issuer && ias.serial_number == tbs.serial_number
+        }
+        SignerIdentifier::SubjectKeyIdentifier(ski) => tbs
+            .extensions
+            .iter()
+            .flatten()
+            .filter(|ext| ext.extn_id == ID_SUBJECT_KEY_ID)
+            .filter_map(|ext| SubjectKeyIdentifier::from_der(ext.extn_value.as_bytes()).ok())
+            .any(|cert_ski| cert_ski == *ski),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_error(e: der::Error) -> FakError {
+    FakError::ParseError {
+        source: "timestamp".to_string(),
+        message: e.to_string(),
+        line: None,
+        column: None,
+    }
+}
+
+fn invalid(message: &str) -> FakError {
+    FakError::Validation {
+        field: "timestamp".to_string(),
+        message: message.to_string(),
+    }
+}
@@ -1,12 +1,15 @@
 //! Core data types for FAK.
 
 use crate::error::{FakError, FakResult};
+use crate::expr::{Expr, ExprCache};
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256, Sha512};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 /// Execution trace capturing a sequence of governance operations.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExecutionTrace {
     pub id: String,
     pub steps: Vec<serde_json::Value>,
@@ -41,6 +44,34 @@ impl ExecutionTrace {
         }
         Ok(())
     }
+
+    /// [`Self::validate`], plus a check of each step's shape.
+    ///
+    /// Every step must be a JSON object. A step with a `step` key must give
+    /// a non-negative integer index greater than that of the last step that
+    /// had one; steps without the key are allowed anywhere.
+    pub fn validate_steps(&self) -> FakResult<()> {
+        self.validate()?;
+        let mut last_index: Option<u64> = None;
+        for (i, step) in self.steps.iter().enumerate() {
+            let invalid = |message: String| FakError::Validation {
+                field: "steps".to_string(),
+                message: format!("step {}: {}", i, message),
+            };
+            let Some(fields) = step.as_object() else {
+                return Err(invalid(format!("expected a JSON object, got {}", step)));
+            };
+            let Some(index) = fields.get("step") else { continue };
+            let index = index
+                .as_u64()
+                .ok_or_else(|| invalid(format!("'step' must be a non-negative integer, got {}", index)))?;
+            if let Some(last) = last_index.filter(|&last| index <= last) {
+                return Err(invalid(format!("'step' index {} does not follow {}", index, last)));
+            }
+            last_index = Some(index);
+        }
+        Ok(())
+    }
 }
 
 impl Default for ExecutionTrace {
@@ -55,15 +86,34 @@ impl Default for ExecutionTrace {
 
 /// Capability manifest defining agent permissions and authority relationships.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CapabilityManifest {
     pub id: String,
     pub agent_id: String,
     pub capabilities: Vec<String>,
+    /// Capability-to-capability grants: holding a key capability also
+    /// confers each capability it maps to. Walks of this graph start at
+    /// `capabilities`; `agent_id` is never a node.
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub authority_graph: HashMap<String, Vec<String>>,
     pub metadata: serde_json::Map<String, serde_json::Value>,
 }
 
+/// Serialize a `HashMap` with sorted keys so serialized output is deterministic.
+fn serialize_sorted_map<S, V>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    V: Serialize,
+{
+    let sorted: std::collections::BTreeMap<_, _> = map.iter().collect();
+    sorted.serialize(serializer)
+}
+
 impl CapabilityManifest {
+    /// Maximum allowed authority edges, summed over all sources, to keep
+    /// graph traversal bounded.
+    pub const MAX_AUTHORITY_EDGES: usize = 100_000;
+
     pub fn new(
         id: String,
         agent_id: String,
@@ -81,6 +131,11 @@ impl CapabilityManifest {
     }
 
     pub fn validate(&self) -> FakResult<()> {
+        self.validate_with_edge_limit(Self::MAX_AUTHORITY_EDGES)
+    }
+
+    /// [`validate`](Self::validate) with a caller-chosen authority edge limit.
+    pub fn validate_with_edge_limit(&self, max_edges: usize) -> FakResult<()> {
         if self.id.is_empty() {
             return Err(FakError::Validation {
                 field: "id".to_string(),
@@ -93,8 +148,156 @@ impl CapabilityManifest {
                 message: "CapabilityManifest must have a non-empty agent_id".to_string(),
             });
         }
+        let edge_count: usize = self.authority_graph.values().map(Vec::len).sum();
+        if edge_count > max_edges {
+            return Err(FakError::ResourceLimit {
+                resource: "authority_edges".to_string(),
+                limit: max_edges,
+                actual: edge_count,
+            });
+        }
         Ok(())
     }
+
+    /// Compare against a newer manifest, reporting what `other` adds or removes.
+    pub fn diff(&self, other: &CapabilityManifest) -> CapabilityDiff {
+        let old_caps: BTreeSet<&String> = self.capabilities.iter().collect();
+        let new_caps: BTreeSet<&String> = other.capabilities.iter().collect();
+        let old_edges = self.authority_edges();
+        let new_edges = other.authority_edges();
+
+        CapabilityDiff {
+            added_capabilities: new_caps.difference(&old_caps).map(|c| c.to_string()).collect(),
+            removed_capabilities: old_caps.difference(&new_caps).map(|c| c.to_string()).collect(),
+            added_edges: new_edges.difference(&old_edges).cloned().collect(),
+            removed_edges: old_edges.difference(&new_edges).cloned().collect(),
+        }
+    }
+
+    /// Graph nodes that no chain of grants from a declared capability
+    /// reaches, sorted.
+    ///
+    /// Nodes are every source and target in `authority_graph`. This is a
+    /// diagnostic for dead manifest entries, not a validation rule.
+    pub fn unreachable_nodes(&self) -> Vec<String> {
+        let reachable = self.walk_grants(|_| false).seen;
+        let nodes: BTreeSet<&str> = self
+            .authority_graph
+            .iter()
+            .flat_map(|(from, targets)| std::iter::once(from).chain(targets))
+            .map(String::as_str)
+            .collect();
+        nodes
+            .into_iter()
+            .filter(|node| !reachable.contains(node))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Shortest chain of grants from a declared capability to one that is
+    /// not declared, if any.
+    ///
+    /// The chain starts at a member of `capabilities` and ends at the first
+    /// undeclared capability reached; ties are broken by capability name so
+    /// the result is deterministic.
+    pub fn escalation_path(&self) -> Option<Vec<String>> {
+        let declared: BTreeSet<&str> = self.capabilities.iter().map(String::as_str).collect();
+        let walk = self.walk_grants(|capability| !declared.contains(capability));
+        let target = walk.stopped_at?;
+        let mut path = vec![target.to_string()];
+        let mut current = target;
+        while let Some(&previous) = walk.parent.get(current) {
+            path.push(previous.to_string());
+            current = previous;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Every capability reachable from `capabilities` through
+    /// `authority_graph`, the declared ones included. Cycles are walked once.
+    pub fn transitive_capabilities(&self) -> HashSet<String> {
+        self.walk_grants(|_| false).seen.into_iter().map(str::to_string).collect()
+    }
+
+    /// Breadth-first walk of `authority_graph` from the declared
+    /// capabilities, visiting targets in name order and stopping at the
+    /// first newly reached capability for which `stop` returns true.
+    fn walk_grants<'a>(&'a self, mut stop: impl FnMut(&str) -> bool) -> GrantWalk<'a> {
+        let mut walk = GrantWalk {
+            seen: self.capabilities.iter().map(String::as_str).collect(),
+            parent: HashMap::new(),
+            stopped_at: None,
+        };
+        let mut queue: VecDeque<&str> = walk.seen.iter().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let mut targets: Vec<&str> = self
+                .authority_graph
+                .get(node)
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect();
+            targets.sort_unstable();
+            for target in targets {
+                if !walk.seen.insert(target) {
+                    continue;
+                }
+                walk.parent.insert(target, node);
+                if stop(target) {
+                    walk.stopped_at = Some(target);
+                    return walk;
+                }
+                queue.push_back(target);
+            }
+        }
+        walk
+    }
+
+    fn authority_edges(&self) -> BTreeSet<AuthorityEdge> {
+        self.authority_graph
+            .iter()
+            .flat_map(|(from, targets)| {
+                targets.iter().map(move |to| AuthorityEdge {
+                    from: from.clone(),
+                    to: to.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Result of [`CapabilityManifest::walk_grants`].
+struct GrantWalk<'a> {
+    seen: BTreeSet<&'a str>,
+    parent: HashMap<&'a str, &'a str>,
+    stopped_at: Option<&'a str>,
+}
+
+/// Directed grant in an authority graph.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AuthorityEdge {
+    pub from: String,
+    pub to: String,
+}
+
+/// Changes between two capability manifests, with every list sorted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct CapabilityDiff {
+    pub added_capabilities: Vec<String>,
+    pub removed_capabilities: Vec<String>,
+    pub added_edges: Vec<AuthorityEdge>,
+    pub removed_edges: Vec<AuthorityEdge>,
+}
+
+impl CapabilityDiff {
+    /// True when the manifests grant identical authority.
+    pub fn is_empty(&self) -> bool {
+        self.added_capabilities.is_empty()
+            && self.removed_capabilities.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
 }
 
 impl Default for CapabilityManifest {
@@ -111,6 +314,7 @@ impl Default for CapabilityManifest {
 
 /// Cost ledger tracking economic attribution for inference operations.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CostLedger {
     pub id: String,
     pub entries: Vec<serde_json::Value>,
@@ -154,6 +358,87 @@ impl CostLedger {
         }
         Ok(())
     }
+
+    /// Sum the `cost` field of every entry using compensated summation.
+    ///
+    /// Entries without a `cost` field are skipped; a non-numeric `cost` is a
+    /// validation error.
+    pub fn sum_entry_costs(&self) -> FakResult<EntryCostSum> {
+        let mut sum = 0.0_f64;
+        let mut compensation = 0.0_f64;
+        let mut counted = 0;
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let cost = match entry.get("cost") {
+                Some(cost) => cost.as_f64().ok_or_else(|| FakError::Validation {
+                    field: "entries".to_string(),
+                    message: format!("entry {} has a non-numeric cost", i),
+                })?,
+                None => continue,
+            };
+            // Neumaier's variant of Kahan summation: the compensation term
+            // collects exactly the low-order bits that naive addition drops.
+            let t = sum + cost;
+            if sum.abs() >= cost.abs() {
+                compensation += (sum - t) + cost;
+            } else {
+                compensation += (cost - t) + sum;
+            }
+            sum = t;
+            counted += 1;
+        }
+
+        Ok(EntryCostSum {
+            total: sum + compensation,
+            rounding_error: compensation.abs(),
+            counted,
+        })
+    }
+
+    /// Default epsilon for [`CostLedger::reconcile`].
+    pub const RECONCILE_EPSILON: f64 = 1e-9;
+
+    /// Check that entry costs sum to `total_cost` within
+    /// [`Self::RECONCILE_EPSILON`].
+    pub fn reconcile(&self) -> FakResult<()> {
+        self.reconcile_within(Self::RECONCILE_EPSILON)
+    }
+
+    /// Check that entry costs sum to `total_cost` within `epsilon`.
+    ///
+    /// A ledger whose entries carry no `cost` fields has nothing to
+    /// reconcile and passes.
+    pub fn reconcile_within(&self, epsilon: f64) -> FakResult<()> {
+        self.sum_entry_costs()?.reconcile(self.total_cost, epsilon)
+    }
+}
+
+/// Compensated sum of a ledger's entry costs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntryCostSum {
+    /// Compensated sum of all entry costs; non-finite on overflow.
+    pub total: f64,
+    /// Rounding error a naive left-to-right sum would have accumulated.
+    pub rounding_error: f64,
+    /// Number of entries that carried a `cost` field.
+    pub counted: usize,
+}
+
+impl EntryCostSum {
+    /// Compare against a ledger's `total_cost`; a non-finite sum never
+    /// reconciles.
+    pub(crate) fn reconcile(&self, total_cost: f64, epsilon: f64) -> FakResult<()> {
+        if self.counted == 0 || (self.total - total_cost).abs() <= epsilon {
+            return Ok(());
+        }
+        Err(FakError::Validation {
+            field: "total_cost".to_string(),
+            message: format!(
+                "total_cost {} does not match entry cost sum {} within {}",
+                total_cost, self.total, epsilon
+            ),
+        })
+    }
 }
 
 impl Default for CostLedger {
@@ -169,6 +454,7 @@ impl Default for CostLedger {
 
 /// Policy intermediate representation for compiled governance rules.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PolicyIR {
     pub id: String,
     pub ast: serde_json::Map<String, serde_json::Value>,
@@ -177,6 +463,14 @@ pub struct PolicyIR {
 }
 
 impl PolicyIR {
+    /// `ast` key naming the capabilities each agent may be granted.
+    ///
+    /// Its value maps agent IDs to arrays of capability names, e.g.
+    /// `{"permitted_grants": {"agent-001": ["read", "write"]}}`. A policy
+    /// without the key does not restrict grants; an agent missing from the
+    /// map may be granted nothing.
+    pub const PERMITTED_GRANTS_KEY: &'static str = "permitted_grants";
+
     pub fn new(
         id: String,
         ast: serde_json::Map<String, serde_json::Value>,
@@ -198,8 +492,46 @@ impl PolicyIR {
                 message: "PolicyIR must have a non-empty ID".to_string(),
             });
         }
+        self.grants_table()?;
         Ok(())
     }
+
+    /// Capabilities the policy permits `agent_id` to hold, or `None` if the
+    /// policy does not restrict grants (see [`Self::PERMITTED_GRANTS_KEY`]).
+    pub fn permitted_grants(&self, agent_id: &str) -> FakResult<Option<Vec<&str>>> {
+        let Some(table) = self.grants_table()? else {
+            return Ok(None);
+        };
+        Ok(Some(
+            table
+                .get(agent_id)
+                .and_then(|caps| caps.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|cap| cap.as_str())
+                .collect(),
+        ))
+    }
+
+    fn grants_table(&self) -> FakResult<Option<&serde_json::Map<String, serde_json::Value>>> {
+        let Some(grants) = self.ast.get(Self::PERMITTED_GRANTS_KEY) else {
+            return Ok(None);
+        };
+        let malformed = || FakError::Validation {
+            field: Self::PERMITTED_GRANTS_KEY.to_string(),
+            message: "expected an object mapping agent IDs to arrays of capability names"
+                .to_string(),
+        };
+        let table = grants.as_object().ok_or_else(malformed)?;
+        let well_formed = table.values().all(|caps| {
+            caps.as_array()
+                .is_some_and(|caps| caps.iter().all(serde_json::Value::is_string))
+        });
+        if !well_formed {
+            return Err(malformed());
+        }
+        Ok(Some(table))
+    }
 }
 
 impl Default for PolicyIR {
@@ -215,13 +547,124 @@ impl Default for PolicyIR {
 
 /// Specification for an invariant to be verified.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct InvariantSpec {
     pub name: String,
     pub description: String,
+    /// Condition under which the invariant applies. Names are resolved as
+    /// described on [`crate::expr`]; behavioral invariants naming fields
+    /// outside the context namespaces are checked against each trace step.
     pub precondition: Option<String>,
     pub postcondition: Option<String>,
     pub temporal_properties: Vec<String>,
     pub invariant_type: ProofType,
+    /// Operator guidance surfaced only when the invariant fails.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+    /// Gating invariants are checked first; if any fails, the witness's
+    /// non-gating invariants are not evaluated.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub gating: bool,
+    /// Also require every `depends_on` index in a behavioral invariant's
+    /// trace to point at an earlier step.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub check_causality: bool,
+    /// Absolute slack for this invariant's numeric comparisons. Without it,
+    /// economic invariants use the engine's `cost_tolerance` and others
+    /// compare exactly. Tolerance loosens the non-strict checks: `a <= b`
+    /// holds when `a` is within `tolerance` above `b`, `a >= b` when `a` is
+    /// within `tolerance` below `b`, `a == b` when `|a - b| <= tolerance`,
+    /// and `a != b` only when `|a - b| > tolerance`. `a < b` and `a > b`
+    /// stay strict.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerance: Option<f64>,
+    /// Cap on how often an action may occur in the trace, checked for
+    /// behavioral soundness invariants.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    /// Proof obtained outside FAK. When its result is a proving one (see
+    /// [`ExternalProof::discharges`]) the engine treats the invariant as
+    /// satisfied without checking it; the claim is kept in the witness.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_discharge: Option<ExternalProof>,
+    /// Parsed precondition, filled on first evaluation.
+    #[serde(skip)]
+    pub(crate) compiled_pre: ExprCache,
+    /// Parsed postcondition, filled on first evaluation.
+    #[serde(skip)]
+    pub(crate) compiled_post: ExprCache,
+}
+
+/// Claim that an invariant was proven by an external tool, such as an SMT
+/// solver. FAK records the claim but cannot check it.
+///
+/// Only a claim whose `result` is one of [`Self::DISCHARGING_RESULTS`]
+/// stands in for the invariant's own check; any other result, such as
+/// `"sat"` or `"unknown"`, leaves the invariant to be checked as usual.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ExternalProof {
+    /// Tool that discharged the obligation, e.g. `"z3 4.12"`.
+    pub tool: String,
+    /// Result the tool reported, e.g. `"unsat"`.
+    pub result: String,
+}
+
+impl ExternalProof {
+    /// Results, compared case-insensitively, that count as a proof.
+    pub const DISCHARGING_RESULTS: &'static [&'static str] = &["unsat", "verified", "proved"];
+
+    pub fn new(tool: &str, result: &str) -> Self {
+        Self {
+            tool: tool.to_string(),
+            result: result.to_string(),
+        }
+    }
+
+    /// Whether the tool reported that it proved the invariant.
+    pub fn discharges(&self) -> bool {
+        let result = self.result.trim();
+        Self::DISCHARGING_RESULTS.iter().any(|r| r.eq_ignore_ascii_case(result))
+    }
+}
+
+/// "No more than `limit` steps whose `action_field` equals `action` within
+/// any `window` span of `window_field`."
+///
+/// Windows are half-open: events exactly `window` apart fall in different
+/// windows. Steps that do not match the action are ignored, and matching
+/// steps need not be in timestamp order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RateLimit {
+    /// Step field holding the numeric timestamp.
+    pub window_field: String,
+    /// Step field holding the action name.
+    pub action_field: String,
+    /// Action value being limited.
+    pub action: serde_json::Value,
+    /// Maximum number of matching steps per window.
+    pub limit: usize,
+    /// Window length, in the units of `window_field`.
+    pub window: f64,
+}
+
+impl RateLimit {
+    pub fn new(
+        window_field: &str,
+        action_field: &str,
+        action: serde_json::Value,
+        limit: usize,
+        window: f64,
+    ) -> Self {
+        Self {
+            window_field: window_field.to_string(),
+            action_field: action_field.to_string(),
+            action,
+            limit,
+            window,
+        }
+    }
 }
 
 impl InvariantSpec {
@@ -240,9 +683,84 @@ impl InvariantSpec {
             postcondition,
             temporal_properties,
             invariant_type,
+            remediation: None,
+            gating: false,
+            check_causality: false,
+            tolerance: None,
+            rate_limit: None,
+            external_discharge: None,
+            compiled_pre: ExprCache::default(),
+            compiled_post: ExprCache::default(),
         }
     }
 
+    /// Attach a remediation hint shown when the invariant fails.
+    pub fn with_remediation(mut self, remediation: String) -> Self {
+        self.remediation = Some(remediation);
+        self
+    }
+
+    /// Mark the invariant as gating the rest of its witness.
+    pub fn with_gating(mut self, gating: bool) -> Self {
+        self.gating = gating;
+        self
+    }
+
+    /// Opt in to the trace step causality check.
+    pub fn with_causality(mut self, check_causality: bool) -> Self {
+        self.check_causality = check_causality;
+        self
+    }
+
+    /// Parsed precondition, cached after the first call.
+    pub fn compiled_precondition(&self) -> FakResult<Option<Cow<'_, Expr>>> {
+        self.precondition
+            .as_deref()
+            .map(|source| self.compiled_pre.get(source))
+            .transpose()
+    }
+
+    /// Parsed postcondition, cached after the first call.
+    pub fn compiled_postcondition(&self) -> FakResult<Option<Cow<'_, Expr>>> {
+        self.postcondition
+            .as_deref()
+            .map(|source| self.compiled_post.get(source))
+            .transpose()
+    }
+
+    /// Field paths read by the precondition and postcondition, sorted and
+    /// deduplicated.
+    ///
+    /// This is static analysis of the spec: nothing is evaluated. An
+    /// expression that does not tokenize contributes no paths.
+    pub fn referenced_paths(&self) -> Vec<String> {
+        let paths: BTreeSet<String> = [&self.precondition, &self.postcondition]
+            .into_iter()
+            .flatten()
+            .filter_map(|e| crate::expr::referenced_paths(e).ok())
+            .flatten()
+            .collect();
+        paths.into_iter().collect()
+    }
+
+    /// Override the engine's comparison tolerance for this invariant.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// Limit how often an action may occur in the trace.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Record that an external tool discharged this invariant.
+    pub fn with_external_discharge(mut self, proof: ExternalProof) -> Self {
+        self.external_discharge = Some(proof);
+        self
+    }
+
     pub fn validate(&self) -> FakResult<()> {
         if self.name.is_empty() {
             return Err(FakError::Validation {
@@ -250,8 +768,68 @@ impl InvariantSpec {
                 message: "InvariantSpec must have a non-empty name".to_string(),
             });
         }
+        for condition in self.precondition.iter().chain(&self.postcondition) {
+            crate::expr::parse_expr(condition)?;
+        }
+        if self.external_discharge.as_ref().is_some_and(|p| p.tool.is_empty()) {
+            return Err(FakError::Validation {
+                field: "external_discharge".to_string(),
+                message: "external proof must name the tool that produced it".to_string(),
+            });
+        }
+        if let Some(tolerance) = self.tolerance {
+            if !tolerance.is_finite() || tolerance < 0.0 {
+                return Err(FakError::Validation {
+                    field: "tolerance".to_string(),
+                    message: format!("tolerance must be a non-negative number, got {}", tolerance),
+                });
+            }
+        }
+        if let Some(rate_limit) = &self.rate_limit {
+            if !rate_limit.window.is_finite() || rate_limit.window <= 0.0 {
+                return Err(FakError::Validation {
+                    field: "rate_limit".to_string(),
+                    message: format!("window must be a positive number, got {}", rate_limit.window),
+                });
+            }
+        }
         Ok(())
     }
+
+    /// [`Self::validate`], plus the fields this invariant's type requires:
+    ///
+    /// | `invariant_type`           | Requires                                            |
+    /// |----------------------------|-----------------------------------------------------|
+    /// | `behavioral_soundness`     | nothing more                                        |
+    /// | `authority_non_escalation` | a precondition or postcondition naming a `caps.` path |
+    /// | `economic_invariance`      | a postcondition                                     |
+    /// | `semantic_preservation`    | nothing more                                        |
+    ///
+    /// The engine does not apply these rules, since every type also has a
+    /// built-in structural check; they are for authoring tools that want
+    /// each invariant to state its own condition.
+    pub fn validate_for_type(&self) -> FakResult<()> {
+        self.validate()?;
+        let missing = match self.invariant_type {
+            ProofType::BehavioralSoundness | ProofType::SemanticPreservation => None,
+            ProofType::AuthorityNonEscalation => {
+                let mut paths = Vec::new();
+                for condition in self.precondition.iter().chain(&self.postcondition) {
+                    paths.extend(crate::expr::referenced_paths(condition)?);
+                }
+                (!paths.iter().any(|p| p.starts_with("caps.")))
+                    .then_some("a precondition or postcondition naming a caps. path")
+            }
+            ProofType::EconomicInvariance => self.postcondition.is_none().then_some("a postcondition"),
+        };
+        match missing {
+            Some(requirement) => Err(FakError::Validation {
+                field: "postcondition".to_string(),
+                message: format!("{} invariants require {}", self.invariant_type, requirement),
+            }),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Default for InvariantSpec {
@@ -263,12 +841,21 @@ impl Default for InvariantSpec {
             postcondition: None,
             temporal_properties: Vec::new(),
             invariant_type: ProofType::BehavioralSoundness,
+            remediation: None,
+            gating: false,
+            check_causality: false,
+            tolerance: None,
+            rate_limit: None,
+            external_discharge: None,
+            compiled_pre: ExprCache::default(),
+            compiled_post: ExprCache::default(),
         }
     }
 }
 
 /// Counter-example generated when an invariant is violated.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CounterExample {
     pub invariant_name: String,
     pub error_type: String,
@@ -276,8 +863,22 @@ pub struct CounterExample {
     pub step_index: Option<usize>,
 }
 
+impl CounterExample {
+    /// Error types recorded when a check could not reach a verdict.
+    pub const CHECK_ERROR_TYPES: [&'static str; 4] =
+        ["check_error", "timeout", "cancelled", "unresolved_identifier"];
+
+    /// True when the checker failed, ran out of time, was cancelled or met
+    /// a condition naming an identifier with no value, rather than finding
+    /// the invariant violated.
+    pub fn is_check_error(&self) -> bool {
+        Self::CHECK_ERROR_TYPES.contains(&self.error_type.as_str())
+    }
+}
+
 /// Witness containing proof artifacts and verification results.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ProofWitness {
     pub proof_id: String,
     pub execution_trace: ExecutionTrace,
@@ -286,6 +887,14 @@ pub struct ProofWitness {
     pub policy_ir: PolicyIR,
     pub invariants: Vec<InvariantSpec>,
     pub counterexamples: Vec<CounterExample>,
+    /// Invariants skipped because a gating invariant failed; these are
+    /// neither passed nor failed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_evaluated: Vec<String>,
+    /// Invariants that passed only because their precondition was never
+    /// true, so they constrained nothing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vacuous: Vec<String>,
 }
 
 impl ProofWitness {
@@ -306,10 +915,18 @@ impl ProofWitness {
             policy_ir,
             invariants,
             counterexamples,
+            not_evaluated: Vec::new(),
+            vacuous: Vec::new(),
         }
     }
 
     pub fn validate(&self) -> FakResult<()> {
+        self.validate_with_edge_limit(CapabilityManifest::MAX_AUTHORITY_EDGES)
+    }
+
+    /// [`validate`](Self::validate) with a caller-chosen authority edge limit
+    /// for the capability manifest.
+    pub fn validate_with_edge_limit(&self, max_authority_edges: usize) -> FakResult<()> {
         if self.proof_id.is_empty() {
             return Err(FakError::Validation {
                 field: "proof_id".to_string(),
@@ -317,48 +934,378 @@ impl ProofWitness {
             });
         }
         self.execution_trace.validate()?;
-        self.capability_manifest.validate()?;
+        self.capability_manifest.validate_with_edge_limit(max_authority_edges)?;
         self.cost_ledger.validate()?;
         self.policy_ir.validate()?;
-        Ok(())
-    }
-}
-
-impl Default for ProofWitness {
-    fn default() -> Self {
-        Self {
-            proof_id: String::new(),
-            execution_trace: ExecutionTrace::default(),
-            capability_manifest: CapabilityManifest::default(),
-            cost_ledger: CostLedger::default(),
-            policy_ir: PolicyIR::default(),
-            invariants: Vec::new(),
-            counterexamples: Vec::new(),
+        let step_count = self.execution_trace.steps.len();
+        for ce in &self.counterexamples {
+            if !self.invariants.iter().any(|inv| inv.name == ce.invariant_name) {
+                return Err(FakError::Validation {
+                    field: "counterexamples".to_string(),
+                    message: format!(
+                        "counterexample references undeclared invariant '{}'",
+                        ce.invariant_name
+                    ),
+                });
+            }
+            if let Some(step_index) = ce.step_index {
+                if step_index >= step_count {
+                    return Err(FakError::Validation {
+                        field: "counterexamples".to_string(),
+                        message: format!(
+                            "counterexample for '{}' references step {} but trace has {} steps",
+                            ce.invariant_name, step_index, step_count
+                        ),
+                    });
+                }
+            }
         }
+        Ok(())
     }
 }
 
 /// Bundle containing multiple proof witnesses for batch verification.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ProofBundle {
     pub id: String,
     pub witnesses: Vec<ProofWitness>,
     pub metadata: serde_json::Map<String, serde_json::Value>,
+    /// Version of the serialized layout (see [`Self::SCHEMA_VERSION`]).
+    /// Files written before the field existed read as version 1. Not part
+    /// of the bundle ID.
+    #[serde(default = "ProofBundle::initial_schema_version")]
+    pub schema_version: u32,
+}
+
+/// Witness-level changes between two bundles, with every list sorted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct BundleDiff {
+    /// Proof IDs only in the newer bundle.
+    pub added: Vec<String>,
+    /// Proof IDs only in the older bundle.
+    pub removed: Vec<String>,
+    /// Witnesses re-issued under a new proof ID for the same invariants.
+    pub changed: Vec<WitnessChange>,
+}
+
+impl BundleDiff {
+    /// True when both bundles hold the same witnesses.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A witness whose proof ID changed while its invariant names did not.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WitnessChange {
+    pub old_proof_id: String,
+    pub new_proof_id: String,
 }
 
 impl ProofBundle {
     /// Maximum allowed witnesses per bundle to prevent resource exhaustion.
     pub const MAX_WITNESSES: usize = 100;
 
+    /// Newest serialized layout this build reads and writes. Bumped when a
+    /// field is added that older readers cannot ignore.
+    pub const SCHEMA_VERSION: u32 = 1;
+
+    fn initial_schema_version() -> u32 {
+        1
+    }
+
     pub fn new(
         id: String,
         witnesses: Vec<ProofWitness>,
         metadata: serde_json::Map<String, serde_json::Value>,
     ) -> Self {
-        Self { id, witnesses, metadata }
+        Self {
+            id,
+            witnesses,
+            metadata,
+            schema_version: Self::SCHEMA_VERSION,
+        }
+    }
+
+    /// Serialize to JSON.
+    pub fn to_json(&self) -> FakResult<String> {
+        Self::check_schema_version(self.schema_version)?;
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Deserialize from JSON, refusing schema versions this build does not
+    /// know before reading any other field.
+    pub fn from_json(json: &str) -> FakResult<ProofBundle> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let version = match value.get("schema_version") {
+            None => Self::initial_schema_version(),
+            Some(v) => v
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| FakError::Serialization {
+                    message: format!("bundle schema_version must be a non-negative integer, got {}", v),
+                })?,
+        };
+        Self::check_schema_version(version)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    fn check_schema_version(version: u32) -> FakResult<()> {
+        if version == 0 || version > Self::SCHEMA_VERSION {
+            return Err(FakError::Serialization {
+                message: format!(
+                    "unsupported bundle schema version {} (this build supports 1 through {})",
+                    version,
+                    Self::SCHEMA_VERSION
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Compute the content-addressable bundle ID for witnesses and metadata.
+    ///
+    /// The ID depends only on the set of witness proof IDs, sorted so their
+    /// order does not matter, and the metadata. Any arrangement of the same
+    /// witnesses therefore yields the same ID.
+    pub fn compute_id(
+        witnesses: &[ProofWitness],
+        metadata: &serde_json::Map<String, serde_json::Value>,
+    ) -> String {
+        compute_content_hash(&Self::preimage(witnesses, metadata))
+    }
+
+    fn preimage(
+        witnesses: &[ProofWitness],
+        metadata: &serde_json::Map<String, serde_json::Value>,
+    ) -> serde_json::Value {
+        let mut proof_ids: Vec<&str> = witnesses.iter().map(|w| w.proof_id.as_str()).collect();
+        proof_ids.sort_unstable();
+        serde_json::json!({
+            "witnesses": proof_ids,
+            "metadata": metadata,
+        })
+    }
+
+    /// Format 1 IDs hashed the proof IDs in bundle order.
+    fn compute_ordered_id(
+        witnesses: &[ProofWitness],
+        metadata: &serde_json::Map<String, serde_json::Value>,
+    ) -> String {
+        compute_content_hash(&serde_json::json!({
+            "witnesses": witnesses.iter().map(|w| w.proof_id.clone()).collect::<Vec<_>>(),
+            "metadata": metadata,
+        }))
+    }
+
+    /// Recompute this bundle's ID from its current contents.
+    pub fn content_id(&self) -> String {
+        Self::compute_id(&self.witnesses, &self.metadata)
+    }
+
+    /// Compare against a newer bundle by witness `proof_id`.
+    ///
+    /// A witness present only in `self` and one present only in `other` are
+    /// reported as a change rather than a removal and an addition when they
+    /// declare the same set of invariant names. If several witnesses share a
+    /// name set, they are paired in `proof_id` order.
+    pub fn diff(&self, other: &ProofBundle) -> BundleDiff {
+        fn unmatched<'a>(
+            witnesses: &'a [ProofWitness],
+            other: &[ProofWitness],
+        ) -> BTreeMap<BTreeSet<&'a str>, Vec<&'a str>> {
+            let other_ids: HashSet<&str> = other.iter().map(|w| w.proof_id.as_str()).collect();
+            let mut by_names: BTreeMap<BTreeSet<&str>, Vec<&str>> = BTreeMap::new();
+            for witness in witnesses.iter().filter(|w| !other_ids.contains(w.proof_id.as_str())) {
+                let names = witness.invariants.iter().map(|i| i.name.as_str()).collect();
+                by_names.entry(names).or_default().push(&witness.proof_id);
+            }
+            for ids in by_names.values_mut() {
+                ids.sort_unstable();
+            }
+            by_names
+        }
+
+        let mut old = unmatched(&self.witnesses, &other.witnesses);
+        let new = unmatched(&other.witnesses, &self.witnesses);
+        let mut diff = BundleDiff::default();
+        for (names, new_ids) in new {
+            let old_ids = old.remove(&names).unwrap_or_default();
+            for (i, new_id) in new_ids.iter().enumerate() {
+                match old_ids.get(i) {
+                    Some(old_id) => diff.changed.push(WitnessChange {
+                        old_proof_id: old_id.to_string(),
+                        new_proof_id: new_id.to_string(),
+                    }),
+                    None => diff.added.push(new_id.to_string()),
+                }
+            }
+            diff.removed.extend(old_ids.iter().skip(new_ids.len()).map(|id| id.to_string()));
+        }
+        diff.removed.extend(old.into_values().flatten().map(str::to_string));
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort_by(|a, b| a.old_proof_id.cmp(&b.old_proof_id));
+        diff
+    }
+
+    /// The exact value hashed into [`content_id`](Self::content_id).
+    ///
+    /// Holds the sorted witness proof IDs and the metadata. When an
+    /// ID check fails, diffing this against the preimage of the bundle the ID
+    /// was issued for shows whether the witness list or the metadata changed.
+    pub fn id_preimage(&self) -> serde_json::Value {
+        Self::preimage(&self.witnesses, &self.metadata)
+    }
+
+    /// Check whether this bundle proves at least everything `other` proves.
+    ///
+    /// Witnesses are matched by the content hashes of their four artifacts, so
+    /// two witnesses about byte-identical inputs are compared regardless of
+    /// their IDs. An invariant counts as proven by a witness when it is
+    /// declared there and no counterexample names it; its identity is its
+    /// name, proof type, pre/postcondition and temporal properties
+    /// (descriptions and remediation hints are ignored). Returns true iff, for
+    /// every artifact set in `other`, each invariant proven over it in `other`
+    /// is also proven over the same artifact set in `self`.
+    pub fn covers(&self, other: &ProofBundle) -> bool {
+        let ours = Self::proven_by_artifacts(&self.witnesses);
+        Self::proven_by_artifacts(&other.witnesses)
+            .into_iter()
+            .all(|(artifacts, theirs)| {
+                ours.get(&artifacts)
+                    .map(|proven| theirs.is_subset(proven))
+                    .unwrap_or_else(|| theirs.is_empty())
+            })
+    }
+
+    fn proven_by_artifacts(
+        witnesses: &[ProofWitness],
+    ) -> BTreeMap<[String; 4], BTreeSet<String>> {
+        let hash = |v: serde_json::Result<serde_json::Value>| {
+            compute_content_hash(&v.unwrap_or_default())
+        };
+        let mut proven: BTreeMap<[String; 4], BTreeSet<String>> = BTreeMap::new();
+        for w in witnesses {
+            let artifacts = [
+                hash(serde_json::to_value(&w.execution_trace)),
+                hash(serde_json::to_value(&w.capability_manifest)),
+                hash(serde_json::to_value(&w.cost_ledger)),
+                hash(serde_json::to_value(&w.policy_ir)),
+            ];
+            let entry = proven.entry(artifacts).or_default();
+            for inv in &w.invariants {
+                if w.counterexamples.iter().any(|ce| ce.invariant_name == inv.name)
+                    || w.not_evaluated.contains(&inv.name)
+                {
+                    continue;
+                }
+                let identity = serde_json::json!([
+                    inv.name,
+                    inv.invariant_type,
+                    inv.precondition,
+                    inv.postcondition,
+                    inv.temporal_properties,
+                ]);
+                entry.insert(identity.to_string());
+            }
+        }
+        proven
+    }
+
+    /// Check that the stored ID matches the bundle's contents.
+    pub fn verify_id(&self) -> FakResult<()> {
+        let actual = self.content_id();
+        if actual != self.id {
+            return Err(FakError::IntegrityFailure {
+                artifact_id: self.id.clone(),
+                expected: self.id.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Current bundle format version, bumped whenever the ID rules change.
+    ///
+    /// Version 2 sorts the witness proof IDs before hashing; version 1 hashed
+    /// them in bundle order. The two agree on bundles whose witnesses are
+    /// already sorted.
+    pub const FORMAT_VERSION: u32 = 2;
+
+    fn compute_id_for_version(
+        version: u32,
+        witnesses: &[ProofWitness],
+        metadata: &serde_json::Map<String, serde_json::Value>,
+    ) -> Option<String> {
+        match version {
+            1 => Some(Self::compute_ordered_id(witnesses, metadata)),
+            2 => Some(Self::compute_id(witnesses, metadata)),
+            _ => None,
+        }
+    }
+
+    /// Newest format version whose ID rules reproduce this bundle's ID.
+    ///
+    /// `None` means the ID matches no known format, i.e. the bundle was
+    /// altered after it was generated.
+    pub fn format_version(&self) -> Option<u32> {
+        (1..=Self::FORMAT_VERSION).rev().find(|&version| {
+            Self::compute_id_for_version(version, &self.witnesses, &self.metadata).as_deref()
+                == Some(self.id.as_str())
+        })
+    }
+
+    /// Upgrade a bundle written under an older format to `to_version`.
+    ///
+    /// The source version is detected with [`format_version`](Self::format_version),
+    /// so a bundle whose ID holds under no format is refused rather than
+    /// re-issued a valid ID. Each step re-derives the bundle ID under the next
+    /// format's rules; witness proof IDs are carried over as they are.
+    pub fn migrate(self, to_version: u32) -> FakResult<ProofBundle> {
+        if to_version == 0 || to_version > Self::FORMAT_VERSION {
+            return Err(FakError::Validation {
+                field: "format_version".to_string(),
+                message: format!(
+                    "unknown target format version {} (current is {})",
+                    to_version,
+                    Self::FORMAT_VERSION
+                ),
+            });
+        }
+        let from = self.format_version().ok_or_else(|| FakError::Validation {
+            field: "id".to_string(),
+            message: format!("bundle ID '{}' matches no known format version", self.id),
+        })?;
+        if from > to_version {
+            return Err(FakError::Validation {
+                field: "format_version".to_string(),
+                message: format!(
+                    "cannot downgrade bundle from format version {} to {}",
+                    from, to_version
+                ),
+            });
+        }
+
+        let mut bundle = self;
+        for version in from + 1..=to_version {
+            if let Some(id) =
+                Self::compute_id_for_version(version, &bundle.witnesses, &bundle.metadata)
+            {
+                bundle.id = id;
+            }
+        }
+        Ok(bundle)
     }
 
     pub fn validate(&self) -> FakResult<()> {
+        self.validate_with_edge_limit(CapabilityManifest::MAX_AUTHORITY_EDGES)
+    }
+
+    /// [`validate`](Self::validate) with a caller-chosen authority edge limit
+    /// for each witness's capability manifest.
+    pub fn validate_with_edge_limit(&self, max_authority_edges: usize) -> FakResult<()> {
         if self.id.is_empty() {
             return Err(FakError::Validation {
                 field: "id".to_string(),
@@ -373,7 +1320,7 @@ impl ProofBundle {
             });
         }
         for witness in &self.witnesses {
-            witness.validate()?;
+            witness.validate_with_edge_limit(max_authority_edges)?;
         }
         Ok(())
     }
@@ -385,13 +1332,16 @@ impl Default for ProofBundle {
             id: String::new(),
             witnesses: Vec::new(),
             metadata: serde_json::Map::new(),
+            schema_version: Self::SCHEMA_VERSION,
         }
     }
 }
 
 /// Type of formal proof being verified.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ProofType {
+    #[default]
     BehavioralSoundness,
     AuthorityNonEscalation,
     EconomicInvariance,
@@ -399,7 +1349,29 @@ pub enum ProofType {
 }
 
 impl ProofType {
+    /// Every proof type, in declaration order.
+    pub const fn all() -> [ProofType; 4] {
+        // Exhaustive so that a new variant fails to compile until listed.
+        match Self::BehavioralSoundness {
+            Self::BehavioralSoundness
+            | Self::AuthorityNonEscalation
+            | Self::EconomicInvariance
+            | Self::SemanticPreservation => {}
+        }
+        [
+            Self::BehavioralSoundness,
+            Self::AuthorityNonEscalation,
+            Self::EconomicInvariance,
+            Self::SemanticPreservation,
+        ]
+    }
+
+    pub fn iter() -> impl Iterator<Item = ProofType> {
+        Self::all().into_iter()
+    }
+
     /// Parse a proof type from string, returning an error for unknown values.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> FakResult<Self> {
         match s.trim().to_lowercase().as_str() {
             "behavioral_soundness" | "behavioralsoundness" => Ok(Self::BehavioralSoundness),
@@ -421,12 +1393,6 @@ impl ProofType {
     }
 }
 
-impl Default for ProofType {
-    fn default() -> Self {
-        Self::BehavioralSoundness
-    }
-}
-
 impl From<ProofType> for String {
     fn from(pt: ProofType) -> Self {
         pt.as_str().to_string()
@@ -447,7 +1413,7 @@ impl std::str::FromStr for ProofType {
 }
 
 /// Context for verification operations, bundling all required inputs.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub struct VerificationContext<'a> {
     pub trace: &'a ExecutionTrace,
     pub capabilities: &'a CapabilityManifest,
@@ -464,6 +1430,77 @@ impl<'a> VerificationContext<'a> {
     ) -> Self {
         Self { trace, capabilities, cost_ledger, policy_ir }
     }
+
+    pub fn validate(&self) -> FakResult<()> {
+        self.validate_with_edge_limit(CapabilityManifest::MAX_AUTHORITY_EDGES)
+    }
+
+    /// [`validate`](Self::validate) with a caller-chosen authority edge limit
+    /// for the capability manifest.
+    pub fn validate_with_edge_limit(&self, max_authority_edges: usize) -> FakResult<()> {
+        self.trace.validate()?;
+        self.capabilities.validate_with_edge_limit(max_authority_edges)?;
+        self.cost_ledger.validate()?;
+        self.policy_ir.validate()?;
+        Ok(())
+    }
+}
+
+impl<'a> From<&VerificationContext<'a>> for VerificationContext<'a> {
+    fn from(ctx: &VerificationContext<'a>) -> Self {
+        *ctx
+    }
+}
+
+/// [`VerificationContext`] that owns its artifacts, so it can be built once,
+/// stored, and verified later.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OwnedVerificationContext {
+    pub trace: ExecutionTrace,
+    pub capabilities: CapabilityManifest,
+    pub cost_ledger: CostLedger,
+    pub policy_ir: PolicyIR,
+}
+
+impl OwnedVerificationContext {
+    pub fn new(
+        trace: ExecutionTrace,
+        capabilities: CapabilityManifest,
+        cost_ledger: CostLedger,
+        policy_ir: PolicyIR,
+    ) -> Self {
+        Self { trace, capabilities, cost_ledger, policy_ir }
+    }
+
+    /// Borrow the artifacts as a [`VerificationContext`].
+    pub fn as_ref(&self) -> VerificationContext<'_> {
+        VerificationContext::new(&self.trace, &self.capabilities, &self.cost_ledger, &self.policy_ir)
+    }
+}
+
+impl<'a> From<&'a OwnedVerificationContext> for VerificationContext<'a> {
+    fn from(ctx: &'a OwnedVerificationContext) -> Self {
+        ctx.as_ref()
+    }
+}
+
+impl From<VerificationContext<'_>> for OwnedVerificationContext {
+    fn from(ctx: VerificationContext<'_>) -> Self {
+        Self::new(
+            ctx.trace.clone(),
+            ctx.capabilities.clone(),
+            ctx.cost_ledger.clone(),
+            ctx.policy_ir.clone(),
+        )
+    }
+}
+
+impl From<(ExecutionTrace, CapabilityManifest, CostLedger, PolicyIR)> for OwnedVerificationContext {
+    fn from(
+        (trace, capabilities, cost_ledger, policy_ir): (ExecutionTrace, CapabilityManifest, CostLedger, PolicyIR),
+    ) -> Self {
+        Self::new(trace, capabilities, cost_ledger, policy_ir)
+    }
 }
 
 /// Compute a deterministic content-addressable hash for an artifact.
@@ -475,15 +1512,274 @@ pub fn compute_content_hash(obj: &serde_json::Value) -> String {
     format!("{:x}", hasher.finalize())
 }
 
-/// Produce canonical JSON with deterministic key ordering.
-fn canonical_json(value: &serde_json::Value) -> String {
+/// Digest algorithm for [`compute_content_hash_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgo {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl HashAlgo {
+    pub const ALL: [HashAlgo; 3] = [HashAlgo::Sha256, HashAlgo::Sha512, HashAlgo::Blake3];
+
+    /// Tag written before the hex digest in a tagged ID.
+    pub fn tag(self) -> &'static str {
+        match self {
+            HashAlgo::Sha256 => "sha256",
+            HashAlgo::Sha512 => "sha512",
+            HashAlgo::Blake3 => "blake3",
+        }
+    }
+
+    /// Algorithm named by the tag of an ID such as `"blake3:…"`, or `None`
+    /// for an untagged ID or an unknown tag.
+    pub fn from_tagged(id: &str) -> Option<HashAlgo> {
+        let (tag, _) = id.split_once(':')?;
+        Self::ALL.into_iter().find(|algo| algo.tag() == tag)
+    }
+
+    fn hex_digest(self, parts: &[&[u8]]) -> String {
+        match self {
+            HashAlgo::Sha256 => {
+                let mut hasher = Sha256::new();
+                parts.iter().for_each(|part| hasher.update(part));
+                format!("{:x}", hasher.finalize())
+            }
+            HashAlgo::Sha512 => {
+                let mut hasher = Sha512::new();
+                parts.iter().for_each(|part| hasher.update(part));
+                format!("{:x}", hasher.finalize())
+            }
+            HashAlgo::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                parts.iter().for_each(|part| {
+                    hasher.update(part);
+                });
+                hasher.finalize().to_hex().to_string()
+            }
+        }
+    }
+}
+
+/// Compute a content hash with `algo`, tagged with the algorithm
+/// (`"sha256:…"`) so stored IDs are self-describing.
+///
+/// The digest covers the same canonical bytes as [`compute_content_hash`],
+/// so `Sha256` yields that function's hash behind the `sha256:` tag.
+pub fn compute_content_hash_with(obj: &serde_json::Value, algo: HashAlgo) -> String {
+    compute_salted_content_hash_with(obj, &[], Some(algo))
+}
+
+/// Salted content hash: tagged with `algo`, or untagged SHA-256 when `algo`
+/// is `None`.
+///
+/// A non-empty salt is hashed behind its length as a big-endian `u64`, so
+/// no salt and artifact pair produces the same bytes as another pair.
+pub(crate) fn compute_salted_content_hash_with(
+    obj: &serde_json::Value,
+    salt: &[u8],
+    algo: Option<HashAlgo>,
+) -> String {
+    let canonical = canonical_json(obj);
+    let salt_len = (salt.len() as u64).to_be_bytes();
+    let salt_len: &[u8] = if salt.is_empty() { &[] } else { &salt_len };
+    let parts = [salt_len, salt, canonical.as_bytes()];
+    match algo {
+        Some(algo) => format!("{}:{}", algo.tag(), algo.hex_digest(&parts)),
+        None => HashAlgo::Sha256.hex_digest(&parts),
+    }
+}
+
+/// Compute a content hash with `salt`, length-prefixed, ahead of the
+/// canonical bytes.
+///
+/// Deployments sharing one artifact store use distinct salts so identical
+/// content gets distinct IDs. An empty salt gives [`compute_content_hash`].
+pub fn compute_salted_content_hash(obj: &serde_json::Value, salt: &[u8]) -> String {
+    compute_salted_content_hash_with(obj, salt, None)
+}
+
+/// Compute the same hash as [`compute_content_hash`] without building the
+/// canonical JSON string.
+///
+/// Canonical bytes are fed to the hasher as they are produced, so peak
+/// memory stays near the size of the largest object's key list rather than
+/// the size of the serialized artifact.
+pub fn compute_content_hash_streaming(obj: &serde_json::Value) -> String {
+    let mut hasher = Sha256::new();
+    write_canonical(obj, &mut HashWriter(&mut hasher));
+    format!("{:x}", hasher.finalize())
+}
+
+/// `io::Write` adapter feeding bytes into a hasher.
+struct HashWriter<'a>(&'a mut Sha256);
+
+impl std::io::Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Write the bytes of [`canonical_json`] for `value` to `out`.
+fn write_canonical(value: &serde_json::Value, out: &mut HashWriter<'_>) {
+    use std::io::Write;
+
+    // Writes into a hasher cannot fail.
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut keys: Vec<_> = map.keys().collect();
+            keys.sort();
+            let _ = out.write_all(b"{");
+            for (i, k) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    let _ = out.write_all(b",");
+                }
+                let _ = serde_json::to_writer(&mut *out, k);
+                let _ = out.write_all(b":");
+                write_canonical(&map[k], out);
+            }
+            let _ = out.write_all(b"}");
+        }
+        serde_json::Value::Array(arr) => {
+            let _ = out.write_all(b"[");
+            for (i, item) in arr.iter().enumerate() {
+                if i > 0 {
+                    let _ = out.write_all(b",");
+                }
+                write_canonical(item, out);
+            }
+            let _ = out.write_all(b"]");
+        }
+        serde_json::Value::Number(n) => {
+            let _ = write!(out, "{}", n);
+        }
+        _ => {
+            let _ = serde_json::to_writer(&mut *out, value);
+        }
+    }
+}
+
+/// Running content hash over an array that grows one element at a time.
+///
+/// Finalizing yields exactly what [`compute_content_hash`] returns for the
+/// assembled `serde_json::Value::Array`, so a growing trace's `steps` can be
+/// hashed without re-serializing earlier steps on every append. Only arrays
+/// are supported: canonical objects sort their keys, so an object's hash
+/// cannot be fixed until every key is known.
+#[derive(Debug, Clone)]
+pub struct IncrementalHasher {
+    hasher: Sha256,
+    len: usize,
+}
+
+impl IncrementalHasher {
+    pub fn new() -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(b"[");
+        Self { hasher, len: 0 }
+    }
+
+    /// Append the next array element.
+    pub fn update(&mut self, item: &serde_json::Value) {
+        if self.len > 0 {
+            self.hasher.update(b",");
+        }
+        self.hasher.update(canonical_json(item).as_bytes());
+        self.len += 1;
+    }
+
+    /// Number of elements appended so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Hash of the elements appended so far, leaving the hasher usable.
+    pub fn current_hash(&self) -> String {
+        self.clone().finalize()
+    }
+
+    /// Hash of the complete array.
+    pub fn finalize(mut self) -> String {
+        self.hasher.update(b"]");
+        format!("{:x}", self.hasher.finalize())
+    }
+}
+
+impl Default for IncrementalHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Number of hex characters kept by [`short_id`].
+pub const SHORT_ID_LEN: usize = 12;
+
+/// Abbreviate a content hash to its first [`SHORT_ID_LEN`] characters for display.
+///
+/// Hashes shorter than the prefix length are returned unchanged.
+pub fn short_id(hash: &str) -> String {
+    hash.chars().take(SHORT_ID_LEN).collect()
+}
+
+/// Check whether the short forms of `hashes` are pairwise distinct.
+///
+/// Repeated full hashes are not collisions; only distinct hashes sharing a
+/// short form are. Tooling should fall back to longer prefixes when this fails.
+pub fn short_id_is_unique_among(hashes: &[String]) -> bool {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    for hash in hashes {
+        if let Some(existing) = seen.insert(short_id(hash), hash) {
+            if existing != hash {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Canonical JSON text of `value`: the exact bytes content hashes cover.
+///
+/// - Object keys are sorted by their UTF-8 bytes, and each member is
+///   written as `"key":value`, with members joined by `,` inside `{}`.
+/// - Array items keep their order, joined by `,` inside `[]`.
+/// - No whitespace is written anywhere.
+/// - Strings, including keys, are escaped as `serde_json` writes them:
+///   `"` and `\` and control characters are escaped, and everything else,
+///   non-ASCII included, is written as is.
+/// - Numbers are written as `serde_json` prints them (`1`, `-2`, `0.5`,
+///   `1e100`); with the `arbitrary_precision` feature, exactly as parsed.
+///   A `Value` never holds NaN or an infinity: `serde_json` turns them into
+///   `null` when the `Value` is built, before this function sees it. Float
+///   fields of the artifact types are therefore checked finite by their
+///   `validate`, which runs before they are hashed.
+/// - `true`, `false` and `null` are written as such.
+///
+/// An artifact's ID is the lowercase hex SHA-256 of these bytes. Every
+/// `Value` has a canonical form, so this never fails and never substitutes
+/// one value for another.
+///
+/// ```
+/// let value = serde_json::json!({"b": 2, "a": 1});
+/// assert_eq!(fak::canonical_json(&value), r#"{"a":1,"b":2}"#);
+/// ```
+pub fn canonical_json(value: &serde_json::Value) -> String {
     match value {
         serde_json::Value::Object(map) => {
             let mut keys: Vec<_> = map.keys().collect();
             keys.sort();
             let pairs: Vec<String> = keys
                 .into_iter()
-                .map(|k| format!("{}:{}", serde_json::to_string(k).unwrap_or_default(), canonical_json(&map[k])))
+                .map(|k| format!("{}:{}", json_string(k), canonical_json(&map[k])))
                 .collect();
             format!("{{{}}}", pairs.join(","))
         }
@@ -491,6 +1787,16 @@ fn canonical_json(value: &serde_json::Value) -> String {
             let items: Vec<String> = arr.iter().map(canonical_json).collect();
             format!("[{}]", items.join(","))
         }
-        _ => serde_json::to_string(value).unwrap_or_else(|_| "null".to_string()),
+        // Numbers keep their lexical form; with `arbitrary_precision` this is
+        // exactly the digits that were parsed, so large integers never pass through f64.
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => json_string(s),
+        serde_json::Value::Bool(b) => b.to_string(),
+        serde_json::Value::Null => "null".to_string(),
     }
+}
+
+/// `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    serde_json::to_string(s).expect("a str always serializes")
 }
\ No newline at end of file
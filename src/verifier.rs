@@ -1,9 +1,22 @@
 //! Standalone verifier for FAK proof bundles.
 
+use crate::artifacts::ArtifactSnapshot;
+use crate::clock::elapsed_secs;
+use crate::compact::CompactBundle;
 use crate::engine::{EngineConfig, ProofEngine};
-use crate::error::FakResult;
-use crate::types::{compute_content_hash, ProofBundle, ProofWitness};
+use crate::error::{FakError, FakResult};
+use crate::metadata::normalize_timestamps;
+use crate::report::format_bundle_result;
+use crate::sandbox::MemoryGuard;
+use crate::types::{
+    compute_content_hash, compute_content_hash_streaming, short_id, CounterExample, ExternalProof,
+    ProofBundle, ProofType, ProofWitness,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
 
 /// Verification result for a single witness.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -12,7 +25,36 @@ pub struct WitnessResult {
     pub success: bool,
     pub invariant_count: usize,
     pub counterexample_count: usize,
+    /// Counterexamples in which the invariant was found violated.
+    #[serde(default)]
+    pub violation_count: usize,
+    /// Counterexamples in which the checker itself failed or timed out
+    /// (see [`CounterExample::is_check_error`]).
+    #[serde(default)]
+    pub error_count: usize,
     pub error: Option<String>,
+    /// Remediation hints for failing invariants, keyed by invariant name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remediations: BTreeMap<String, String>,
+    /// Invariants skipped because a gating invariant failed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_evaluated: Vec<String>,
+    /// Invariants that held vacuously: their precondition was never true.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vacuous: Vec<String>,
+    /// Invariants taken as satisfied on the strength of an external proof,
+    /// keyed by invariant name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub externally_verified: BTreeMap<String, ExternalProof>,
+    /// Content hash of the whole witness as verified; empty when the witness
+    /// was rejected before its invariants were checked. Lets
+    /// [`Verifier::verify_bundle_incremental`] recognise an unchanged witness.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub witness_hash: String,
+    /// Counterexamples found when the invariants were re-checked;
+    /// `counterexample_count` is their number.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub counterexamples: Vec<CounterExample>,
 }
 
 /// Verification result for an entire bundle.
@@ -22,12 +64,201 @@ pub struct BundleResult {
     pub success: bool,
     pub witness_results: Vec<WitnessResult>,
     pub error: Option<String>,
+    /// Non-fatal findings, such as a differing pinned engine config.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl BundleResult {
+    pub(crate) fn failure(bundle_id: &str, error: String) -> Self {
+        Self {
+            bundle_id: bundle_id.to_string(),
+            success: false,
+            witness_results: Vec::new(),
+            error: Some(error),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Output shape for [`Verifier::write_result`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Compact JSON serialization of [`BundleResult`].
+    Json,
+    /// Indented JSON serialization of [`BundleResult`].
+    JsonPretty,
+    /// Plain-text summary, one line per witness.
+    Summary,
+    /// JUnit XML with one test case per witness.
+    Junit,
+}
+
+/// Cross-witness rule evaluated over the proof types a bundle covers.
+///
+/// A witness covers a proof type when it declares at least one invariant of
+/// that type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BundleInvariant {
+    /// If any witness covers `if_present`, some witness must also cover `then_required`.
+    Requires {
+        if_present: ProofType,
+        then_required: ProofType,
+    },
+}
+
+impl BundleInvariant {
+    /// Check the rule against the set of covered proof types.
+    pub fn check(&self, covered: &HashSet<ProofType>) -> Result<(), String> {
+        match self {
+            Self::Requires { if_present, then_required } => {
+                if covered.contains(if_present) && !covered.contains(then_required) {
+                    return Err(format!(
+                        "bundle covers '{}' but no witness covers required '{}'",
+                        if_present, then_required
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Named set of invariants every witness in a bundle must declare.
+///
+/// Profiles let an organization pin verification standards centrally; the
+/// name conventionally carries a version (e.g. `"baseline-v2"`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    /// Required `(invariant name, proof type)` pairs.
+    pub required: Vec<(String, ProofType)>,
+}
+
+impl Profile {
+    pub fn new(name: String, required: Vec<(String, ProofType)>) -> Self {
+        Self { name, required }
+    }
+
+    /// List every way `witness` deviates from the profile; empty when it conforms.
+    pub fn nonconformances(&self, witness: &ProofWitness) -> Vec<String> {
+        let mut issues = Vec::new();
+        for (name, expected) in &self.required {
+            match witness.invariants.iter().find(|i| &i.name == name) {
+                None => issues.push(format!("missing invariant '{}' ({})", name, expected)),
+                Some(inv) if inv.invariant_type != *expected => issues.push(format!(
+                    "invariant '{}' is {}, expected {}",
+                    name, inv.invariant_type, expected
+                )),
+                Some(_) => {}
+            }
+        }
+        issues
+    }
+}
+
+/// Append-only log of bundles linked into a hash chain.
+///
+/// Every bundle after the first names its predecessor's ID under
+/// [`PREV_ID_KEY`](Self::PREV_ID_KEY) in its metadata. Because the link is
+/// metadata, it is covered by the bundle's own ID, so rewriting any bundle
+/// breaks every later link. Chain checks are structural; verify bundle
+/// contents separately with [`Verifier::verify_bundle`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BundleChain;
+
+impl BundleChain {
+    /// Metadata key holding the previous bundle's ID.
+    pub const PREV_ID_KEY: &'static str = "prev_id";
+
+    /// Link `bundle` after `prev`, re-deriving its ID over the new metadata.
+    pub fn link(mut bundle: ProofBundle, prev: &ProofBundle) -> ProofBundle {
+        bundle.metadata.insert(
+            Self::PREV_ID_KEY.to_string(),
+            serde_json::Value::String(prev.id.clone()),
+        );
+        bundle.id = bundle.content_id();
+        bundle
+    }
+
+    /// Check that each bundle links to the actual ID of the one before it.
+    ///
+    /// The first bundle is the genesis of the sequence and its link, if
+    /// any, is not checked. Fails at the first bundle whose `prev_id` is
+    /// missing or differs from its predecessor's recomputed ID.
+    pub fn verify(bundles: &[ProofBundle]) -> FakResult<()> {
+        for (i, pair) in bundles.windows(2).enumerate() {
+            let (prev, bundle) = (&pair[0], &pair[1]);
+            let expected = prev.content_id();
+            let message = match bundle.metadata.get(Self::PREV_ID_KEY) {
+                Some(serde_json::Value::String(prev_id)) if *prev_id == expected => continue,
+                Some(serde_json::Value::String(prev_id)) => format!(
+                    "bundle {} ('{}') links to '{}', but bundle {} has ID '{}'",
+                    i + 1,
+                    bundle.id,
+                    prev_id,
+                    i,
+                    expected
+                ),
+                Some(other) => format!(
+                    "bundle {} ('{}') has a non-string prev_id: {}",
+                    i + 1,
+                    bundle.id,
+                    other
+                ),
+                None => format!("bundle {} ('{}') has no prev_id", i + 1, bundle.id),
+            };
+            return Err(FakError::Validation {
+                field: Self::PREV_ID_KEY.to_string(),
+                message,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Verifier-level options layered over the engine configuration.
+#[derive(Debug, Clone, Default)]
+pub struct VerifierConfig {
+    pub engine: EngineConfig,
+    /// Bundle-level rules; empty by default so plain bundles are unaffected.
+    pub bundle_invariants: Vec<BundleInvariant>,
+    /// Dotted witness field paths (e.g. `"capability_manifest.agent_id"`) on
+    /// which all witnesses must agree. Empty by default so intentionally
+    /// heterogeneous bundles verify as before.
+    pub consistent_fields: Vec<String>,
+    /// Fail, rather than warn, when a bundle pins a materially different
+    /// engine config (see [`EngineConfig::material_summary`]).
+    pub strict_config: bool,
+    /// Migrate bundles written under an older format version to the current
+    /// one before verifying them (see [`ProofBundle::migrate`]).
+    pub auto_migrate: bool,
+    /// Fail witnesses that declare no invariants, since they verify
+    /// vacuously. Off by default because [`ArtifactManager::create_bundle`]
+    /// produces such witnesses; enabling it is recommended wherever bundles
+    /// are expected to carry real proofs.
+    ///
+    /// [`ArtifactManager::create_bundle`]: crate::ArtifactManager::create_bundle
+    pub require_nonempty_invariants: bool,
+    /// Fail bundles in which witnesses share an `execution_trace.id` but
+    /// carry different trace contents. Off by default because reusing one
+    /// trace across several witnesses is legitimate.
+    pub unique_trace_ids: bool,
+    /// Wall-clock budget, in seconds, for verifying all of a bundle's
+    /// witnesses. Once it is spent, the remaining witnesses fail as not
+    /// evaluated; each witness's engine timeout is also capped by what is
+    /// left of the budget. `None` (the default) means no bundle-wide limit.
+    pub bundle_timeout_secs: Option<f64>,
+    /// Fail witnesses with invariants discharged by an external tool, since
+    /// FAK cannot check those claims. Off by default.
+    pub reject_external_discharge: bool,
 }
 
 /// Standalone verifier for proof bundles.
 #[derive(Debug, Clone)]
 pub struct Verifier {
     engine: ProofEngine,
+    config: VerifierConfig,
 }
 
 impl Verifier {
@@ -38,69 +269,363 @@ impl Verifier {
 
     /// Create a verifier with custom engine configuration.
     pub fn with_config(config: EngineConfig) -> Self {
+        Self::with_verifier_config(VerifierConfig {
+            engine: config,
+            ..VerifierConfig::default()
+        })
+    }
+
+    /// Create a verifier with full verifier-level configuration.
+    pub fn with_verifier_config(config: VerifierConfig) -> Self {
         Self {
-            engine: ProofEngine::with_config(config),
+            engine: ProofEngine::with_config(config.engine.clone()),
+            config,
         }
     }
 
     /// Verify a proof bundle, returning structured results.
     pub fn verify_bundle(&self, bundle: &ProofBundle) -> BundleResult {
+        self.verify_bundle_within(bundle, None, None)
+            .unwrap_or_else(|e| BundleResult::failure(&bundle.id, e.to_string()))
+    }
+
+    /// Re-verify a bundle, reusing results from `previous` for witnesses it
+    /// already verified successfully.
+    ///
+    /// A prior witness result is reused only when its `proof_id` and
+    /// `witness_hash` both match the witness now in the bundle: `proof_id`
+    /// covers the artifacts and invariants but not the recorded
+    /// counterexamples, so the hash is what shows the witness is unchanged.
+    /// New, changed and previously failed witnesses are verified in full,
+    /// as are all bundle-level checks. `previous` should come from a
+    /// verifier with the same configuration.
+    pub fn verify_bundle_incremental(&self, bundle: &ProofBundle, previous: &BundleResult) -> BundleResult {
+        self.verify_bundle_within(bundle, None, Some(previous))
+            .unwrap_or_else(|e| BundleResult::failure(&bundle.id, e.to_string()))
+    }
+
+    /// Verify a bundle while keeping peak heap growth within `max_bytes`.
+    ///
+    /// Requires [`crate::sandbox::TrackingAllocator`] as the global
+    /// allocator; see [`crate::sandbox`] for how precise the limit is.
+    /// Exceeding it aborts verification with
+    /// `ResourceLimit { resource: "memory" }`.
+    pub fn verify_bundle_with_memory_limit(
+        &self,
+        bundle: &ProofBundle,
+        max_bytes: usize,
+    ) -> FakResult<BundleResult> {
+        let guard = MemoryGuard::start(max_bytes)?;
+        self.verify_bundle_within(bundle, Some(&guard), None)
+    }
+
+    /// Only memory-limit violations are returned as errors; every other
+    /// failure is reported in the result.
+    fn verify_bundle_within(
+        &self,
+        bundle: &ProofBundle,
+        memory: Option<&MemoryGuard>,
+        previous: Option<&BundleResult>,
+    ) -> FakResult<BundleResult> {
+        let check_memory = || memory.map_or(Ok(()), MemoryGuard::check);
+
         // Validate bundle structure
-        if let Err(e) = bundle.validate() {
-            return BundleResult {
-                bundle_id: bundle.id.clone(),
-                success: false,
-                witness_results: Vec::new(),
-                error: Some(e.to_string()),
-            };
+        if let Err(e) = bundle.validate_with_edge_limit(self.config.engine.max_authority_edges) {
+            return Ok(BundleResult::failure(&bundle.id, e.to_string()));
+        }
+
+        if self.config.auto_migrate {
+            if let Some(version) = bundle.format_version() {
+                if version < ProofBundle::FORMAT_VERSION {
+                    return match bundle.clone().migrate(ProofBundle::FORMAT_VERSION) {
+                        Ok(migrated) => {
+                            let mut result = self.verify_bundle_within(&migrated, memory, previous)?;
+                            result.warnings.push(format!(
+                                "bundle '{}' migrated from format version {} to {}",
+                                bundle.id,
+                                version,
+                                ProofBundle::FORMAT_VERSION
+                            ));
+                            Ok(result)
+                        }
+                        Err(e) => Ok(BundleResult::failure(&bundle.id, e.to_string())),
+                    };
+                }
+            }
         }
 
         // Verify bundle ID integrity
-        let expected_id = self.compute_bundle_id(bundle);
+        let timestamp_keys = &self.config.engine.timestamp_keys;
+        let expected_id = if timestamp_keys.is_empty() {
+            bundle.content_id()
+        } else {
+            let mut metadata = bundle.metadata.clone();
+            if let Err(e) = normalize_timestamps(&mut metadata, timestamp_keys) {
+                return Ok(BundleResult::failure(&bundle.id, e.to_string()));
+            }
+            ProofBundle::compute_id(&bundle.witnesses, &metadata)
+        };
         if expected_id != bundle.id {
-            return BundleResult {
-                bundle_id: bundle.id.clone(),
-                success: false,
-                witness_results: Vec::new(),
-                error: Some(format!(
-                    "Bundle ID mismatch: expected '{}', got '{}'",
-                    expected_id, bundle.id
-                )),
-            };
+            return Ok(BundleResult::failure(
+                &bundle.id,
+                format!("Bundle ID mismatch: expected '{}', got '{}'", expected_id, bundle.id),
+            ));
         }
+        check_memory()?;
 
         // Verify each witness
         let mut witness_results = Vec::new();
         let mut overall_success = true;
+        let clock = self.config.engine.clock.as_ref();
+        let started = clock.now_secs();
+        let mut budget_error = None;
 
-        for witness in &bundle.witnesses {
-            let result = self.verify_witness(witness);
+        let reusable: BTreeMap<&str, &WitnessResult> = previous
+            .into_iter()
+            .flat_map(|previous| &previous.witness_results)
+            .filter(|result| result.success && !result.witness_hash.is_empty())
+            .map(|result| (result.proof_id.as_str(), result))
+            .collect();
+
+        for (i, witness) in bundle.witnesses.iter().enumerate() {
+            if let Some(prior) = reusable
+                .get(witness.proof_id.as_str())
+                .filter(|prior| prior.witness_hash == Self::witness_hash(witness))
+            {
+                witness_results.push((*prior).clone());
+                continue;
+            }
+            let result = match self.config.bundle_timeout_secs {
+                None => self.verify_witness_with(&self.engine, witness),
+                Some(budget) => {
+                    let remaining = budget - elapsed_secs(clock, started);
+                    if remaining < 0.0 || budget_error.is_some() {
+                        budget_error.get_or_insert_with(|| {
+                            format!(
+                                "bundle verification budget of {}s exhausted after {} of {} witnesses",
+                                budget,
+                                i,
+                                bundle.witnesses.len()
+                            )
+                        });
+                        Self::unevaluated_witness(witness)
+                    } else if remaining < self.config.engine.timeout_secs {
+                        let engine = ProofEngine::with_config(EngineConfig {
+                            timeout_secs: remaining,
+                            ..self.config.engine.clone()
+                        });
+                        self.verify_witness_with(&engine, witness)
+                    } else {
+                        self.verify_witness_with(&self.engine, witness)
+                    }
+                }
+            };
             if !result.success {
                 overall_success = false;
             }
             witness_results.push(result);
+            check_memory()?;
+        }
+
+        let mut warnings = Vec::new();
+        let mut config_error = None;
+        if let Some(mismatch) = self.check_pinned_config(bundle) {
+            if self.config.strict_config {
+                config_error = Some(mismatch);
+            } else {
+                warnings.push(mismatch);
+            }
         }
 
-        BundleResult {
+        let bundle_errors: Vec<String> = [
+            config_error,
+            budget_error,
+            self.check_bundle_invariants(bundle),
+            self.check_consistency(bundle),
+            self.check_trace_ids(bundle),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let error = if bundle_errors.is_empty() {
+            None
+        } else {
+            overall_success = false;
+            Some(bundle_errors.join("; "))
+        };
+
+        Ok(BundleResult {
             bundle_id: bundle.id.clone(),
             success: overall_success,
             witness_results,
-            error: None,
+            error,
+            warnings,
+        })
+    }
+
+    /// Compare the engine config pinned in bundle metadata with our own.
+    ///
+    /// Bundles without a pinned config are accepted silently.
+    fn check_pinned_config(&self, bundle: &ProofBundle) -> Option<String> {
+        let pinned = bundle.metadata.get(EngineConfig::METADATA_KEY)?;
+        let ours = &self.config.engine;
+        if pinned.get("fingerprint").and_then(|f| f.as_str()) == Some(ours.fingerprint().as_str()) {
+            return None;
+        }
+
+        let expected = ours.material_summary();
+        let differences: Vec<String> = expected
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(key, value)| pinned.get(key.as_str()) != Some(*value))
+            .map(|(key, value)| {
+                let recorded = pinned.get(key.as_str()).cloned().unwrap_or_default();
+                format!("{} {} vs {}", key, recorded, value)
+            })
+            .collect();
+        Some(format!(
+            "bundle was produced under a different engine config ({})",
+            if differences.is_empty() {
+                "fingerprint mismatch".to_string()
+            } else {
+                differences.join(", ")
+            }
+        ))
+    }
+
+    /// Verify a bundle and additionally require every witness to conform to `profile`.
+    pub fn verify_bundle_against_profile(
+        &self,
+        bundle: &ProofBundle,
+        profile: &Profile,
+    ) -> BundleResult {
+        let mut result = self.verify_bundle(bundle);
+
+        for (witness, wr) in bundle.witnesses.iter().zip(result.witness_results.iter_mut()) {
+            let issues = profile.nonconformances(witness);
+            if issues.is_empty() {
+                continue;
+            }
+            let diff = format!(
+                "profile '{}' nonconformance: {}",
+                profile.name,
+                issues.join("; ")
+            );
+            wr.success = false;
+            wr.error = Some(match wr.error.take() {
+                Some(existing) => format!("{}; {}", existing, diff),
+                None => diff,
+            });
+            result.success = false;
         }
+
+        result
     }
 
-    fn verify_witness(&self, witness: &ProofWitness) -> WitnessResult {
-        if let Err(e) = witness.validate() {
-            return WitnessResult {
-                proof_id: witness.proof_id.clone(),
-                success: false,
-                invariant_count: witness.invariants.len(),
-                counterexample_count: 0,
-                error: Some(e.to_string()),
-            };
+    /// Verify a compact bundle by expanding it first.
+    ///
+    /// Results are identical to verifying the expanded bundle; a pool that
+    /// fails to expand is reported as a bundle-level error.
+    pub fn verify_compact_bundle(&self, compact: &CompactBundle) -> BundleResult {
+        match compact.expand() {
+            Ok(bundle) => self.verify_bundle(&bundle),
+            Err(e) => BundleResult::failure(&compact.id, e.to_string()),
         }
+    }
 
-        match self.engine.verify_invariants(
+    /// Verify a thin compact bundle, resolving artifacts absent from its pool
+    /// in a frozen store snapshot.
+    ///
+    /// Resolving against a snapshot rather than a live manager means
+    /// concurrent stores cannot change what a long verification sees.
+    pub fn verify_compact_bundle_in(
+        &self,
+        compact: &CompactBundle,
+        snapshot: &ArtifactSnapshot,
+    ) -> BundleResult {
+        match compact.expand_from(snapshot) {
+            Ok(bundle) => self.verify_bundle(&bundle),
+            Err(e) => BundleResult::failure(&compact.id, e.to_string()),
+        }
+    }
+
+    fn check_bundle_invariants(&self, bundle: &ProofBundle) -> Option<String> {
+        if self.config.bundle_invariants.is_empty() {
+            return None;
+        }
+
+        let covered: HashSet<ProofType> = bundle
+            .witnesses
+            .iter()
+            .flat_map(|w| w.invariants.iter().map(|i| i.invariant_type))
+            .collect();
+
+        let failures: Vec<String> = self
+            .config
+            .bundle_invariants
+            .iter()
+            .filter_map(|rule| rule.check(&covered).err())
+            .collect();
+
+        if failures.is_empty() {
+            None
+        } else {
+            Some(format!("bundle invariant violated: {}", failures.join("; ")))
+        }
+    }
+
+    /// Verify a single witness outside any bundle, exactly as
+    /// [`Self::verify_bundle`] verifies each of its witnesses.
+    ///
+    /// The invariants are re-checked and the `proof_id` is re-derived; a
+    /// derived ID that differs from the witness's own fails the result with
+    /// a proof ID mismatch. The `proof_id` covers the content hash of each
+    /// embedded artifact and of the invariants, so it exposes a witness whose
+    /// artifacts were swapped, re-issued or edited in place.
+    pub fn verify_witness(&self, witness: &ProofWitness) -> WitnessResult {
+        self.verify_witness_with(&self.engine, witness)
+    }
+
+    /// Lazily verify each witness of an unbounded stream as it is pulled.
+    ///
+    /// Each witness is checked as by [`Self::verify_witness`]. There is no
+    /// bundle, so no bundle ID is checked, `ProofBundle::MAX_WITNESSES` does
+    /// not apply, and neither do bundle-level checks such as bundle
+    /// invariants or the bundle budget.
+    pub fn verify_witness_stream<'a>(
+        &'a self,
+        witnesses: impl Iterator<Item = ProofWitness> + 'a,
+    ) -> impl Iterator<Item = WitnessResult> + 'a {
+        witnesses.map(move |witness| self.verify_witness(&witness))
+    }
+
+    fn verify_witness_with(&self, engine: &ProofEngine, witness: &ProofWitness) -> WitnessResult {
+        if let Err(e) = witness.validate_with_edge_limit(engine.config().max_authority_edges) {
+            return Self::failed_witness(witness, e.to_string());
+        }
+        if self.config.require_nonempty_invariants && witness.invariants.is_empty() {
+            return Self::failed_witness(
+                witness,
+                "witness declares no invariants, so it proves nothing".to_string(),
+            );
+        }
+        if self.config.reject_external_discharge {
+            let discharged: Vec<&str> = witness
+                .invariants
+                .iter()
+                .filter(|i| i.external_discharge.as_ref().is_some_and(ExternalProof::discharges))
+                .map(|i| i.name.as_str())
+                .collect();
+            if !discharged.is_empty() {
+                return Self::failed_witness(
+                    witness,
+                    format!("externally discharged invariants are not accepted: {}", discharged.join(", ")),
+                );
+            }
+        }
+
+        match engine.verify_invariants(
             &witness.execution_trace,
             &witness.capability_manifest,
             &witness.cost_ledger,
@@ -108,43 +633,233 @@ impl Verifier {
             &witness.invariants,
         ) {
             Ok(reverified) => {
-                if reverified.proof_id != witness.proof_id {
-                    return WitnessResult {
-                        proof_id: witness.proof_id.clone(),
-                        success: false,
-                        invariant_count: witness.invariants.len(),
-                        counterexample_count: reverified.counterexamples.len(),
-                        error: Some(format!(
-                            "Proof ID mismatch: expected '{}', got '{}'",
-                            witness.proof_id, reverified.proof_id
-                        )),
-                    };
-                }
-
-                WitnessResult {
+                let error_count =
+                    reverified.counterexamples.iter().filter(|ce| ce.is_check_error()).count();
+                let mut result = WitnessResult {
                     proof_id: witness.proof_id.clone(),
                     success: reverified.counterexamples.is_empty(),
                     invariant_count: witness.invariants.len(),
                     counterexample_count: reverified.counterexamples.len(),
+                    violation_count: reverified.counterexamples.len() - error_count,
+                    error_count,
                     error: None,
+                    remediations: Self::remediations(witness, &reverified),
+                    not_evaluated: reverified.not_evaluated.clone(),
+                    vacuous: reverified.vacuous.clone(),
+                    externally_verified: witness
+                        .invariants
+                        .iter()
+                        .filter_map(|i| Some((i.name.clone(), i.external_discharge.clone()?)))
+                        .filter(|(_, proof)| proof.discharges())
+                        .collect(),
+                    witness_hash: Self::witness_hash(witness),
+                    counterexamples: reverified.counterexamples.clone(),
+                };
+                if reverified.proof_id != witness.proof_id {
+                    result.success = false;
+                    result.error = Some(format!(
+                        "Proof ID mismatch: expected '{}', got '{}'",
+                        witness.proof_id, reverified.proof_id
+                    ));
                 }
+                result
             }
-            Err(e) => WitnessResult {
-                proof_id: witness.proof_id.clone(),
-                success: false,
-                invariant_count: witness.invariants.len(),
-                counterexample_count: 0,
-                error: Some(e.to_string()),
-            },
+            Err(e) => Self::failed_witness(witness, e.to_string()),
         }
     }
 
-    fn compute_bundle_id(&self, bundle: &ProofBundle) -> String {
-        let content = serde_json::json!({
-            "witnesses": bundle.witnesses.iter().map(|w| w.proof_id.clone()).collect::<Vec<_>>(),
-            "metadata": bundle.metadata.clone(),
-        });
-        compute_content_hash(&content)
+    /// Result for a witness skipped once the bundle budget ran out.
+    fn unevaluated_witness(witness: &ProofWitness) -> WitnessResult {
+        WitnessResult {
+            not_evaluated: witness.invariants.iter().map(|i| i.name.clone()).collect(),
+            ..Self::failed_witness(
+                witness,
+                "not evaluated: bundle verification budget exhausted".to_string(),
+            )
+        }
+    }
+
+    fn failed_witness(witness: &ProofWitness, error: String) -> WitnessResult {
+        WitnessResult {
+            proof_id: witness.proof_id.clone(),
+            success: false,
+            invariant_count: witness.invariants.len(),
+            counterexample_count: 0,
+            violation_count: 0,
+            error_count: 0,
+            error: Some(error),
+            remediations: BTreeMap::new(),
+            not_evaluated: Vec::new(),
+            vacuous: Vec::new(),
+            externally_verified: BTreeMap::new(),
+            witness_hash: String::new(),
+            counterexamples: Vec::new(),
+        }
+    }
+
+    fn witness_hash(witness: &ProofWitness) -> String {
+        serde_json::to_value(witness)
+            .map(|value| compute_content_hash_streaming(&value))
+            .unwrap_or_default()
+    }
+
+    /// Collect remediation hints for invariants that produced counterexamples.
+    fn remediations(witness: &ProofWitness, reverified: &ProofWitness) -> BTreeMap<String, String> {
+        reverified
+            .counterexamples
+            .iter()
+            .filter_map(|ce| {
+                witness
+                    .invariants
+                    .iter()
+                    .find(|i| i.name == ce.invariant_name)
+                    .and_then(|i| i.remediation.clone())
+                    .map(|hint| (ce.invariant_name.clone(), hint))
+            })
+            .collect()
+    }
+
+    fn check_consistency(&self, bundle: &ProofBundle) -> Option<String> {
+        if self.config.consistent_fields.is_empty() {
+            return None;
+        }
+
+        let witnesses: Vec<(String, serde_json::Value)> = bundle
+            .witnesses
+            .iter()
+            .map(|w| (short_id(&w.proof_id), serde_json::to_value(w).unwrap_or_default()))
+            .collect();
+
+        let mut failures = Vec::new();
+        for field in &self.config.consistent_fields {
+            let pointer = format!("/{}", field.replace('.', "/"));
+            let mut values: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+            for (proof_id, value) in &witnesses {
+                let found = value.pointer(&pointer).cloned().unwrap_or_default();
+                values.entry(found.to_string()).or_default().push(proof_id);
+            }
+            if values.len() > 1 {
+                let divergent: Vec<String> = values
+                    .iter()
+                    .map(|(value, ids)| format!("{} (witnesses {})", value, ids.join(", ")))
+                    .collect();
+                failures.push(format!(
+                    "witnesses disagree on '{}': {}",
+                    field,
+                    divergent.join(" vs ")
+                ));
+            }
+        }
+
+        if failures.is_empty() {
+            None
+        } else {
+            Some(failures.join("; "))
+        }
+    }
+
+    /// Report trace IDs used by witnesses whose traces hash differently.
+    fn check_trace_ids(&self, bundle: &ProofBundle) -> Option<String> {
+        if !self.config.unique_trace_ids {
+            return None;
+        }
+
+        let mut hashes: BTreeMap<&str, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+        for witness in &bundle.witnesses {
+            let trace = &witness.execution_trace;
+            let hash = serde_json::to_value(trace)
+                .map(|v| compute_content_hash(&v))
+                .unwrap_or_default();
+            hashes
+                .entry(trace.id.as_str())
+                .or_default()
+                .entry(hash)
+                .or_default()
+                .push(short_id(&witness.proof_id));
+        }
+
+        let conflicts: Vec<String> = hashes
+            .iter()
+            .filter(|(_, by_hash)| by_hash.len() > 1)
+            .map(|(id, by_hash)| {
+                let variants: Vec<String> = by_hash
+                    .iter()
+                    .map(|(hash, ids)| format!("{} (witnesses {})", short_id(hash), ids.join(", ")))
+                    .collect();
+                format!("trace ID '{}' names different traces: {}", id, variants.join(" vs "))
+            })
+            .collect();
+
+        if conflicts.is_empty() {
+            None
+        } else {
+            Some(conflicts.join("; "))
+        }
+    }
+
+    /// Verify a bundle and write the result to `w` in the requested format.
+    pub fn write_result<W: Write>(
+        &self,
+        bundle: &ProofBundle,
+        mut w: W,
+        format: OutputFormat,
+    ) -> FakResult<BundleResult> {
+        let result = self.verify_bundle(bundle);
+        match format {
+            OutputFormat::Json => serde_json::to_writer(&mut w, &result)?,
+            OutputFormat::JsonPretty => serde_json::to_writer_pretty(&mut w, &result)?,
+            OutputFormat::Summary => Self::write_summary(&result, &mut w)?,
+            OutputFormat::Junit => Self::write_junit(&result, &mut w)?,
+        }
+        w.flush()?;
+        Ok(result)
+    }
+
+    fn write_summary<W: Write>(result: &BundleResult, w: &mut W) -> FakResult<()> {
+        w.write_all(format_bundle_result(result).as_bytes())?;
+        Ok(())
+    }
+
+    fn write_junit<W: Write>(result: &BundleResult, w: &mut W) -> FakResult<()> {
+        let mut tests = result.witness_results.len();
+        let mut failures = result.witness_results.iter().filter(|wr| !wr.success).count();
+        if result.error.is_some() {
+            tests += 1;
+            failures += 1;
+        }
+
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<testsuite name="{}" tests="{}" failures="{}">"#,
+            xml_escape(&result.bundle_id),
+            tests,
+            failures
+        )?;
+        if let Some(error) = &result.error {
+            writeln!(w, r#"  <testcase name="bundle" classname="fak.bundle">"#)?;
+            writeln!(w, r#"    <failure message="{}"/>"#, xml_escape(error))?;
+            writeln!(w, "  </testcase>")?;
+        }
+        for wr in &result.witness_results {
+            writeln!(
+                w,
+                r#"  <testcase name="{}" classname="fak.witness">"#,
+                xml_escape(&wr.proof_id)
+            )?;
+            if !wr.success {
+                let message = wr.error.clone().unwrap_or_else(|| {
+                    format!(
+                        "{} violation(s), {} check error(s)",
+                        wr.violation_count, wr.error_count
+                    )
+                });
+                writeln!(w, r#"    <failure message="{}"/>"#, xml_escape(&message))?;
+            }
+            writeln!(w, "  </testcase>")?;
+        }
+        writeln!(w, "</testsuite>")?;
+        Ok(())
     }
 
     /// Verify bundle and return JSON result (legacy API compatibility).
@@ -164,6 +879,136 @@ impl Default for Verifier {
     fn default() -> Self {
         Self {
             engine: ProofEngine::new(),
+            config: VerifierConfig::default(),
+        }
+    }
+}
+
+type PipelineCheck = Arc<dyn Fn(&ProofBundle) -> BundleResult + Send + Sync>;
+
+#[derive(Clone)]
+struct PipelineStep {
+    name: String,
+    optional: bool,
+    check: PipelineCheck,
+}
+
+/// Ordered verification passes merged into a single [`BundleResult`].
+///
+/// Steps run in registration order. A failing mandatory step stops the
+/// pipeline and becomes the result's error, prefixed with the step name; a
+/// failing optional step is recorded as a warning and the pipeline goes on.
+/// Warnings from every step are kept, and witness results from passing or
+/// mandatory steps are merged by position.
+#[derive(Clone, Default)]
+pub struct VerifierPipeline {
+    steps: Vec<PipelineStep>,
+}
+
+impl VerifierPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a mandatory step.
+    pub fn with_step<F>(self, name: &str, check: F) -> Self
+    where
+        F: Fn(&ProofBundle) -> BundleResult + Send + Sync + 'static,
+    {
+        self.push(name, false, Arc::new(check))
+    }
+
+    /// Append a step whose failure is only a warning.
+    pub fn with_optional_step<F>(self, name: &str, check: F) -> Self
+    where
+        F: Fn(&ProofBundle) -> BundleResult + Send + Sync + 'static,
+    {
+        self.push(name, true, Arc::new(check))
+    }
+
+    /// Append a mandatory step running [`Verifier::verify_bundle`].
+    pub fn with_verifier(self, name: &str, verifier: Verifier) -> Self {
+        self.with_step(name, move |bundle| verifier.verify_bundle(bundle))
+    }
+
+    fn push(mut self, name: &str, optional: bool, check: PipelineCheck) -> Self {
+        self.steps.push(PipelineStep {
+            name: name.to_string(),
+            optional,
+            check,
+        });
+        self
+    }
+
+    /// Run the steps against `bundle`.
+    pub fn verify(&self, bundle: &ProofBundle) -> BundleResult {
+        let mut merged = BundleResult {
+            bundle_id: bundle.id.clone(),
+            success: true,
+            witness_results: Vec::new(),
+            error: None,
+            warnings: Vec::new(),
+        };
+
+        for step in &self.steps {
+            let result = (step.check)(bundle);
+            merged
+                .warnings
+                .extend(result.warnings.iter().map(|w| format!("{}: {}", step.name, w)));
+            let error = || result.error.clone().unwrap_or_else(|| "verification failed".to_string());
+            if !result.success && step.optional {
+                merged
+                    .warnings
+                    .push(format!("optional step '{}' failed: {}", step.name, error()));
+                continue;
+            }
+            merge_witness_results(&mut merged.witness_results, result.witness_results.clone());
+            if !result.success {
+                merged.success = false;
+                merged.error = Some(format!("{}: {}", step.name, error()));
+                break;
+            }
         }
+        merged
+    }
+}
+
+impl fmt::Debug for VerifierPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.steps.iter().map(|step| {
+                if step.optional {
+                    format!("{} (optional)", step.name)
+                } else {
+                    step.name.clone()
+                }
+            }))
+            .finish()
     }
+}
+
+/// Fold `incoming` into `merged` witness by witness; a witness fails if
+/// any step failed it, and step errors are joined in order.
+fn merge_witness_results(merged: &mut Vec<WitnessResult>, incoming: Vec<WitnessResult>) {
+    for (i, wr) in incoming.into_iter().enumerate() {
+        let Some(existing) = merged.get_mut(i) else {
+            merged.push(wr);
+            continue;
+        };
+        existing.success &= wr.success;
+        if let Some(error) = wr.error {
+            existing.error = Some(match existing.error.take() {
+                Some(previous) if previous != error => format!("{}; {}", previous, error),
+                _ => error,
+            });
+        }
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
 }
\ No newline at end of file
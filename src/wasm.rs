@@ -0,0 +1,26 @@
+//! WebAssembly entry point for client-side bundle checks.
+//!
+//! Built with the `wasm` feature. On `wasm32` the engine has no clock, so
+//! `timeout_secs` and `bundle_timeout_secs` are not enforced.
+
+#![cfg(feature = "wasm")]
+
+use crate::types::ProofBundle;
+use crate::verifier::{BundleResult, Verifier};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Verify a JSON-encoded [`ProofBundle`] with the default verifier and
+/// return the JSON-encoded [`BundleResult`].
+///
+/// A bundle that cannot be decoded yields a failed result with an empty
+/// `bundle_id`, so callers always receive a result.
+#[wasm_bindgen(js_name = verifyBundleJson)]
+pub fn verify_bundle_json(bundle_json: &str) -> String {
+    let result = match ProofBundle::from_json(bundle_json) {
+        Ok(bundle) => Verifier::new().verify_bundle(&bundle),
+        Err(e) => BundleResult::failure("", e.to_string()),
+    };
+    serde_json::to_string(&result).unwrap_or_else(|e| {
+        serde_json::json!({"bundle_id": "", "success": false, "error": e.to_string()}).to_string()
+    })
+}
@@ -1,11 +1,19 @@
 //! Integration tests for FAK deployment validation.
 
 use fak::{
-    ArtifactManager, FakError, InvariantDSL, ProofEngine, Verifier,
-    CapabilityManifest, CostLedger, ExecutionTrace, InvariantSpec, 
-    PolicyIR, ProofType, compute_content_hash,
+    ArtifactManager, AuthorityEdge, CapabilityDiff, FakError, InvariantDSL, ProofEngine, Verifier,
+    CapabilityManifest, CostLedger, CounterExample, ExecutionTrace, ExternalProof, HashAlgo, IncrementalHasher, InvariantSpec, 
+    OwnedVerificationContext, PolicyIR, ProofType, RateLimit, VerificationContext, compute_content_hash, compute_content_hash_streaming, compute_content_hash_with, compute_salted_content_hash,
+    short_id, short_id_is_unique_among, SHORT_ID_LEN,
 };
+use fak::artifacts::{ArtifactEvent, ArtifactEventKind, ArtifactStore, FileArtifactStore};
+use fak::engine::{EngineConfig, ValidationHooks};
+use fak::verifier::{BundleInvariant, BundleResult, OutputFormat, Profile, VerifierConfig, VerifierPipeline};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[global_allocator]
+static ALLOC: fak::sandbox::TrackingAllocator = fak::sandbox::TrackingAllocator;
 
 // ============================================================================
 // Test Fixtures
@@ -67,6 +75,20 @@ fn test_artifact_manager_store_retrieve() {
     assert_eq!(artifact, retrieved);
 }
 
+#[test]
+fn test_artifact_manager_retrieve_typed() {
+    let mgr = ArtifactManager::new();
+    let ledger = sample_cost_ledger();
+    let id = mgr.store_artifact(&serde_json::to_value(&ledger).unwrap()).expect("store");
+
+    let retrieved: CostLedger = mgr.retrieve_artifact_as(&id).expect("typed retrieve");
+    assert_eq!(retrieved, ledger);
+    assert!(matches!(
+        mgr.retrieve_artifact_as::<ExecutionTrace>(&id),
+        Err(FakError::Serialization { .. })
+    ));
+}
+
 #[test]
 fn test_artifact_manager_integrity_validation() {
     let mgr = ArtifactManager::new();
@@ -196,6 +218,72 @@ fn test_proof_engine_resource_limit() {
     ));
 }
 
+#[test]
+fn test_proof_engine_trace_step_limit() {
+    let engine = ProofEngine::with_config(EngineConfig {
+        max_trace_steps: 2,
+        ..EngineConfig::default()
+    });
+    let trace = ExecutionTrace::new(
+        "trace-big".to_string(),
+        (0..3).map(|i| serde_json::json!({"step": i})).collect(),
+        serde_json::Map::new(),
+    );
+    let result = engine.verify_invariants(&trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[]);
+    assert!(matches!(
+        result,
+        Err(FakError::ResourceLimit { resource, limit: 2, actual: 3 }) if resource == "trace_steps"
+    ));
+}
+
+#[test]
+fn test_proof_engine_timeout_counterexample() {
+    let engine = ProofEngine::with_config(EngineConfig {
+        timeout_secs: 1e-9,
+        ..EngineConfig::default()
+    });
+    let invariants: Vec<InvariantSpec> = (0..500)
+        .map(|i| InvariantSpec::new(
+            format!("inv_{}", i),
+            String::new(),
+            None,
+            Some("trace.steps.len >= 0".to_string()),
+            vec![],
+            ProofType::BehavioralSoundness,
+        ))
+        .collect();
+
+    let witness = engine
+        .verify_invariants(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &invariants)
+        .expect("verify");
+    let timeout = witness.counterexamples.last().expect("timeout counterexample");
+    assert_eq!(timeout.error_type, "timeout");
+    assert_eq!(timeout.details["limit_secs"], 1e-9);
+    assert!(timeout.details["elapsed_secs"].as_f64().unwrap() > 1e-9);
+}
+
+#[test]
+fn test_noop_clock_disables_timeout() {
+    let engine = ProofEngine::with_config(EngineConfig {
+        timeout_secs: 1e-9,
+        clock: std::sync::Arc::new(fak::clock::NoopClock),
+        ..EngineConfig::default()
+    });
+    let invariant = InvariantSpec::new(
+        "steps".to_string(),
+        String::new(),
+        None,
+        Some("trace.steps.len >= 0".to_string()),
+        vec![],
+        ProofType::BehavioralSoundness,
+    );
+
+    let witness = engine
+        .verify_invariants(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[invariant])
+        .expect("verify");
+    assert!(witness.counterexamples.is_empty());
+}
+
 #[test]
 fn test_proof_engine_generate_bundle() {
     let engine = ProofEngine::new();
@@ -263,6 +351,76 @@ fn test_verifier_json_output() {
     assert!(json.get("bundle_id").is_some());
 }
 
+#[test]
+fn test_verify_loose_witness_detects_tampering() {
+    let witness = ProofEngine::new()
+        .verify_invariants(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[])
+        .expect("witness");
+    let verifier = Verifier::new();
+    let result = verifier.verify_witness(&witness);
+    assert!(result.success, "{:?}", result.error);
+    let bundle = ProofEngine::new().generate_bundle(std::slice::from_ref(&witness)).unwrap();
+    assert_eq!(result, verifier.verify_bundle(&bundle).witness_results[0]);
+
+    // Edited in place under the same ledger ID.
+    let mut edited = witness.clone();
+    edited.cost_ledger.entries[0]["cost"] = serde_json::json!(0.0);
+    edited.cost_ledger.total_cost = 0.0;
+    let result = verifier.verify_witness(&edited);
+    assert!(!result.success);
+    assert!(result.error.as_deref().unwrap_or_default().starts_with("Proof ID mismatch"), "{:?}", result.error);
+
+    let mut tampered = witness;
+    tampered.cost_ledger = CostLedger::new(
+        "cost-002".to_string(),
+        vec![serde_json::json!({"op": "inference", "cost": 0.0})],
+        0.0,
+        serde_json::Map::new(),
+    );
+    let result = verifier.verify_witness(&tampered);
+    assert!(!result.success);
+    assert!(result.error.as_deref().unwrap_or_default().starts_with("Proof ID mismatch"), "{:?}", result.error);
+}
+
+#[test]
+fn test_verify_witness_stream() {
+    let engine = ProofEngine::new();
+    let mut failing = behavioral_invariant("never_empty");
+    failing.postcondition = Some("trace.steps.len < 0".to_string());
+    let witnesses = [vec![], vec![behavioral_invariant("ok")], vec![failing]].into_iter().map(|invariants| {
+        engine
+            .verify_invariants(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &invariants)
+            .expect("witness")
+    });
+
+    let verifier = Verifier::new();
+    let mut results = verifier.verify_witness_stream(witnesses);
+    assert!(results.next().expect("first").success);
+    let rest: Vec<_> = results.collect();
+    assert_eq!(rest.len(), 2);
+    assert!(rest[0].success);
+    assert!(!rest[1].success);
+    assert_eq!(rest[1].counterexamples[0].invariant_name, "never_empty");
+}
+
+#[test]
+fn test_bundle_result_carries_counterexamples() {
+    let mut failing = behavioral_invariant("never_empty");
+    failing.postcondition = Some("trace.steps.len < 0".to_string());
+    let witness = ProofEngine::new()
+        .verify_invariants(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[failing])
+        .expect("witness");
+    let bundle = ProofEngine::new().generate_bundle(&[witness]).expect("bundle");
+
+    let result = Verifier::new().verify_bundle(&bundle);
+    let witness_result = &result.witness_results[0];
+    assert!(!witness_result.success);
+    assert_eq!(witness_result.counterexample_count, witness_result.counterexamples.len());
+    assert_eq!(witness_result.counterexamples[0].invariant_name, "never_empty");
+    let json = Verifier::new().verify_bundle_json(&bundle);
+    assert_eq!(json["witness_results"][0]["counterexamples"][0]["invariant_name"], "never_empty");
+}
+
 // ============================================================================
 // DSL Tests
 // ============================================================================
@@ -284,6 +442,63 @@ fn test_dsl_parse_invariant() {
     assert_eq!(parsed.temporal_properties, vec!["always cost_valid"]);
 }
 
+#[test]
+fn test_dsl_multiline_field_value() {
+    let spec = r#"
+        invariant bounded_spend
+        precondition: budget > 0
+            && spent >= 0   # wrapped for readability
+        postcondition: spent <= budget
+        type: economic_invariance
+    "#;
+
+    let parsed = InvariantDSL::parse_invariant(spec).expect("parse");
+    assert_eq!(parsed.precondition, Some("budget > 0 && spent >= 0".to_string()));
+    assert_eq!(parsed.postcondition, Some("spent <= budget".to_string()));
+    assert_eq!(parsed.invariant_type, ProofType::EconomicInvariance);
+}
+
+#[test]
+fn test_dsl_parse_invariant_document() {
+    let doc = r#"
+        # Spending policy
+
+        invariant bounded_spend
+        precondition: budget > 0
+        postcondition: spent <= budget
+        type: economic_invariance
+
+        # Authority
+        invariant no_escalation
+        type: authority_non_escalation
+
+        invariant steps_ordered
+        postcondition: step >= 0
+    "#;
+    let invariants = InvariantDSL::parse_invariants(doc).expect("parse");
+    let names: Vec<&str> = invariants.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, ["bounded_spend", "no_escalation", "steps_ordered"]);
+    assert_eq!(invariants[0].invariant_type, ProofType::EconomicInvariance);
+    assert_eq!(invariants[1].invariant_type, ProofType::AuthorityNonEscalation);
+    assert_eq!(invariants[2].postcondition.as_deref(), Some("step >= 0"));
+    assert!(InvariantDSL::parse_invariants("# nothing here\n").expect("parse").is_empty());
+}
+
+#[test]
+fn test_dsl_parse_invariant_document_reports_each_bad_block() {
+    let doc = "invariant first\npostcondition: x > 0\n\ninvariant second\npostcondition: x >\n\ninvariant third\ntolerance: lots\n";
+    let Err(FakError::ParseError { source, message, .. }) = InvariantDSL::parse_invariants(doc) else {
+        panic!("expected a parse error");
+    };
+    assert_eq!(source, "invariant_document");
+    assert!(!message.contains("'first'"), "{}", message);
+    assert!(message.starts_with("line 4 (invariant 'second'):"), "{}", message);
+    assert!(message.contains("; line 7 (invariant 'third'): invalid tolerance 'lots'"), "{}", message);
+
+    let stray = InvariantDSL::parse_invariants("precondition: x > 0\ninvariant ok\n");
+    assert!(matches!(stray, Err(FakError::ParseError { message, .. }) if message.starts_with("line 1:")));
+}
+
 #[test]
 fn test_dsl_parse_with_comments() {
     let spec = r#"
@@ -304,6 +519,65 @@ fn test_dsl_missing_name() {
     assert!(matches!(result, Err(FakError::ParseError { .. })));
 }
 
+#[test]
+fn test_dsl_missing_name_reports_position() {
+    let spec = "# bounded spend\n\n    invariant   # name forgotten\n    precondition: x > 0\n";
+    let err = InvariantDSL::parse_invariant(spec).unwrap_err();
+    assert!(matches!(err, FakError::ParseError { line: Some(3), column: Some(14), .. }), "{:?}", err);
+    assert!(err.to_string().ends_with("at line 3, column 14"), "{}", err);
+
+    let err = InvariantDSL::parse_invariant("\n\n  type: economic_invariance\n  postcondition: x > 0").unwrap_err();
+    assert!(matches!(err, FakError::ParseError { line: Some(3), column: Some(3), .. }), "{:?}", err);
+}
+
+#[test]
+fn test_dsl_configured_comment_prefix() {
+    let config = fak::DslConfig { comment_prefix: "//".to_string() };
+    let spec = "// migrated spec\ninvariant budget // the cap\n  postcondition: total_cost <= 100 // hard limit";
+    let inv = InvariantDSL::parse_invariant_with_config(spec, &config).expect("parse");
+    assert_eq!(inv.name, "budget");
+    assert_eq!(inv.postcondition.as_deref(), Some("total_cost <= 100"));
+}
+
+#[test]
+fn test_dsl_document_uses_configured_comment_prefix() {
+    let config = fak::DslConfig { comment_prefix: "//".to_string() };
+    let doc = "// budget rules\ninvariant budget // the cap\n  postcondition: total_cost <= 100\n\n// next\ninvariant tagged\n  postcondition: label == \"#1\"";
+    let invariants = InvariantDSL::parse_invariants_with_config(doc, &config).expect("parse");
+    assert_eq!(invariants.len(), 2);
+    assert_eq!(invariants[0].postcondition.as_deref(), Some("total_cost <= 100"));
+    assert_eq!(invariants[1].postcondition.as_deref(), Some("label == \"#1\""));
+    assert!(InvariantDSL::parse_invariants(doc).is_err());
+}
+
+#[test]
+fn test_dsl_keeps_comment_prefix_inside_quotes() {
+    let spec = "invariant tagged\n  postcondition: label == \"build #42\" # trailing comment\n  description: don't # strip me";
+    let inv = InvariantDSL::parse_invariant(spec).expect("parse");
+    assert_eq!(inv.postcondition.as_deref(), Some("label == \"build #42\""));
+    assert_eq!(inv.description, "don't");
+}
+
+#[test]
+fn test_dsl_interpolates_vars() {
+    let spec = "invariant budget\n  postcondition: total_cost <= ${BUDGET_LIMIT}\n  description: cap ${ENV}";
+    let vars: HashMap<String, String> = [("BUDGET_LIMIT", "250"), ("ENV", "staging")]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let inv = InvariantDSL::parse_invariant_with_vars(spec, &vars).expect("parse");
+    assert_eq!(inv.postcondition.as_deref(), Some("total_cost <= 250"));
+    assert_eq!(inv.description, "cap staging");
+}
+
+#[test]
+fn test_dsl_missing_var_is_parse_error() {
+    let spec = "invariant budget\n  postcondition: total_cost <= ${BUDGET_LIMIT}";
+    let err = InvariantDSL::parse_invariant_with_vars(spec, &HashMap::new()).unwrap_err();
+    assert!(matches!(err, FakError::ParseError { line: Some(2), column: Some(32), .. }), "{:?}", err);
+    assert!(err.to_string().contains("BUDGET_LIMIT"), "{}", err);
+}
+
 #[test]
 fn test_dsl_parse_temporal_property() {
     let prop = InvariantDSL::parse_temporal_property("always x > 0").expect("parse");
@@ -312,6 +586,57 @@ fn test_dsl_parse_temporal_property() {
 
     let prop = InvariantDSL::parse_temporal_property("eventually done").expect("parse");
     assert_eq!(prop.operator, "eventually");
+    assert_eq!(prop.left, "done");
+    assert_eq!(prop.right, None);
+}
+
+#[test]
+fn test_dsl_parse_until_property() {
+    let prop = InvariantDSL::parse_temporal_property("pending until approved").expect("parse");
+    assert_eq!(prop.operator, "until");
+    assert_eq!(prop.left, "pending");
+    assert_eq!(prop.right.as_deref(), Some("approved"));
+
+    let prop = InvariantDSL::parse_temporal_property("until_done until x > 0").expect("parse");
+    assert_eq!(prop.left, "until_done");
+
+    // A unary keyword must stand alone, so identifiers that start with one
+    // are left operands of `until`.
+    for (input, left) in [("nextval until x", "nextval"), ("always_on until y", "always_on"), ("eventually2 until z", "eventually2")] {
+        let prop = InvariantDSL::parse_temporal_property(input).expect("parse");
+        assert_eq!(prop.operator, "until", "{}", input);
+        assert_eq!(prop.left, left);
+    }
+    assert_eq!(InvariantDSL::parse_temporal_property("next(x > 0)").expect("parse").left, "(x > 0)");
+
+    for input in ["until x > 0", "pending until", "until"] {
+        assert!(matches!(
+            InvariantDSL::parse_temporal_property(input),
+            Err(FakError::ParseError { .. })
+        ));
+    }
+}
+
+#[test]
+fn test_dsl_parse_bounded_eventually() {
+    let prop = InvariantDSL::parse_temporal_property("eventually[5] done").expect("parse");
+    assert_eq!(prop.operator, "eventually");
+    assert_eq!(prop.bound, Some(5));
+    assert_eq!(prop.expression, "done");
+
+    let prop = InvariantDSL::parse_temporal_property("within 3 response_sent").expect("parse");
+    assert_eq!(prop.operator, "eventually");
+    assert_eq!(prop.bound, Some(3));
+    assert_eq!(prop.left, "response_sent");
+
+    assert_eq!(InvariantDSL::parse_temporal_property("eventually done").expect("parse").bound, None);
+
+    for input in ["eventually[x] done", "eventually[-1] done", "eventually[5 done", "within x done", "eventually[5]"] {
+        assert!(matches!(
+            InvariantDSL::parse_temporal_property(input),
+            Err(FakError::ParseError { .. })
+        ));
+    }
 }
 
 #[test]
@@ -342,6 +667,37 @@ fn test_execution_trace_validation() {
     assert!(valid.validate().is_ok());
 }
 
+#[test]
+fn test_execution_trace_validate_steps() {
+    let trace = |steps: Vec<serde_json::Value>| ExecutionTrace::new("t".to_string(), steps, serde_json::Map::new());
+    assert!(sample_trace().validate_steps().is_ok());
+    assert!(trace(vec![
+        serde_json::json!({"step": 1, "action": "init"}),
+        serde_json::json!({"action": "note"}),
+        serde_json::json!({"step": 3, "action": "done"}),
+    ])
+    .validate_steps()
+    .is_ok());
+
+    let bare_number = trace(vec![serde_json::json!({"step": 1}), serde_json::json!(2)]);
+    assert!(bare_number.validate().is_ok());
+    match bare_number.validate_steps() {
+        Err(FakError::Validation { field, message }) => {
+            assert_eq!(field, "steps");
+            assert!(message.starts_with("step 1:"), "{}", message);
+        }
+        other => panic!("expected validation error, got {:?}", other),
+    }
+
+    for steps in [
+        vec![serde_json::json!({"step": 2}), serde_json::json!({"step": 2})],
+        vec![serde_json::json!({"step": -1})],
+        vec![serde_json::json!({"step": "1"})],
+    ] {
+        assert!(matches!(trace(steps).validate_steps(), Err(FakError::Validation { field, .. }) if field == "steps"));
+    }
+}
+
 #[test]
 fn test_capability_manifest_validation() {
     let empty_id = CapabilityManifest::new(
@@ -369,6 +725,66 @@ fn test_capability_manifest_validation() {
     ));
 }
 
+#[test]
+fn test_authority_edge_limit() {
+    let graph: HashMap<String, Vec<String>> = [
+        ("a".to_string(), vec!["b".to_string(), "c".to_string()]),
+        ("b".to_string(), vec!["c".to_string()]),
+    ]
+    .into_iter()
+    .collect();
+    let caps = CapabilityManifest::new(
+        "caps".to_string(),
+        "agent".to_string(),
+        vec![],
+        graph,
+        serde_json::Map::new(),
+    );
+    assert!(caps.validate().is_ok());
+    assert!(matches!(
+        caps.validate_with_edge_limit(2),
+        Err(FakError::ResourceLimit { resource, limit: 2, actual: 3 }) if resource == "authority_edges"
+    ));
+
+    let engine = ProofEngine::with_config(EngineConfig {
+        max_authority_edges: 2,
+        ..EngineConfig::default()
+    });
+    let result = engine.verify_invariants(
+        &sample_trace(),
+        &caps,
+        &sample_cost_ledger(),
+        &sample_policy_ir(),
+        &[],
+    );
+    assert!(matches!(result, Err(FakError::ResourceLimit { .. })));
+}
+
+#[test]
+fn test_raised_authority_edge_limit_applies_to_witnesses() {
+    let limit = CapabilityManifest::MAX_AUTHORITY_EDGES + 1;
+    let targets = (0..limit).map(|i| i.to_string()).collect();
+    let caps = CapabilityManifest::new(
+        "caps".to_string(),
+        "agent".to_string(),
+        vec![],
+        [("agent".to_string(), targets)].into_iter().collect(),
+        serde_json::Map::new(),
+    );
+    let config = EngineConfig { max_authority_edges: limit, ..EngineConfig::default() };
+    let engine = ProofEngine::with_config(config.clone());
+    let witness = engine
+        .verify_invariants(&sample_trace(), &caps, &sample_cost_ledger(), &sample_policy_ir(), &[])
+        .expect("witness");
+    let bundle = engine.generate_bundle(std::slice::from_ref(&witness)).expect("bundle");
+
+    let raised = Verifier::with_config(config);
+    assert!(raised.verify_witness(&witness).success);
+    assert!(raised.verify_bundle(&bundle).success);
+    let default = Verifier::new().verify_witness(&witness);
+    assert!(default.error.unwrap().contains("authority_edges"));
+}
+
 #[test]
 fn test_cost_ledger_validation() {
     let negative = CostLedger::new("id".to_string(), vec![], -1.0, serde_json::Map::new());
@@ -390,6 +806,16 @@ fn test_cost_ledger_validation() {
     ));
 }
 
+#[test]
+fn test_non_finite_ledger_is_rejected_before_hashing() {
+    let nan = CostLedger::new("cost-nan".to_string(), vec![], f64::NAN, serde_json::Map::new());
+    let result = ArtifactManager::new().create_bundle(&sample_trace(), &sample_capabilities(), &nan, &sample_policy_ir());
+    assert!(matches!(result, Err(FakError::Validation { field, .. }) if field == "total_cost"));
+
+    // serde_json has no NaN: a Value built from one is already null.
+    assert_eq!(serde_json::json!({"cost": f64::NAN}), serde_json::json!({"cost": null}));
+}
+
 #[test]
 fn test_policy_ir_validation() {
     let empty = PolicyIR::new(String::new(), serde_json::Map::new(), vec![], serde_json::Map::new());
@@ -446,6 +872,33 @@ fn test_proof_type_display() {
     assert_eq!(format!("{}", ProofType::BehavioralSoundness), "behavioral_soundness");
 }
 
+#[test]
+fn test_invariant_validate_for_type() {
+    let mut econ = typed_invariant("budget", ProofType::EconomicInvariance);
+    econ.validate().expect("structurally valid");
+    assert!(matches!(
+        econ.validate_for_type(),
+        Err(FakError::Validation { field, message }) if field == "postcondition" && message.contains("economic_invariance")
+    ));
+    econ.postcondition = Some("total_cost <= budget".to_string());
+    econ.validate_for_type().expect("valid");
+
+    let mut authority = typed_invariant("no_escalation", ProofType::AuthorityNonEscalation);
+    authority.postcondition = Some("cost.total_cost >= 0".to_string());
+    assert!(authority.validate_for_type().is_err());
+    authority.postcondition = Some("caps.capabilities.len <= 3".to_string());
+    authority.validate_for_type().expect("valid");
+    typed_invariant("replay", ProofType::BehavioralSoundness).validate_for_type().expect("valid");
+}
+
+#[test]
+fn test_proof_type_all_round_trips() {
+    assert_eq!(ProofType::all().len(), 4);
+    for proof_type in ProofType::iter() {
+        assert_eq!(ProofType::from_str(proof_type.as_str()).unwrap(), proof_type);
+    }
+}
+
 // ============================================================================
 // Content Hash Tests
 // ============================================================================
@@ -553,4 +1006,3382 @@ fn test_full_workflow() {
     assert!(result.witness_results[0].success);
     assert_eq!(result.witness_results[0].invariant_count, 1);
     assert_eq!(result.witness_results[0].counterexample_count, 0);
-}
\ No newline at end of file
+}
+// ============================================================================
+// Trace Causality Tests
+// ============================================================================
+
+fn causal_trace(steps: Vec<serde_json::Value>) -> ExecutionTrace {
+    ExecutionTrace::new("trace-causal".to_string(), steps, serde_json::Map::new())
+}
+
+fn behavioral_invariant(name: &str) -> InvariantSpec {
+    InvariantSpec::new(
+        name.to_string(),
+        String::new(),
+        None,
+        None,
+        vec![],
+        ProofType::BehavioralSoundness,
+    )
+}
+
+#[test]
+fn test_causality_backward_dependencies_pass() {
+    let engine = ProofEngine::new();
+    let trace = causal_trace(vec![
+        serde_json::json!({"step": 0, "action": "init"}),
+        serde_json::json!({"step": 1, "action": "read", "depends_on": [0]}),
+        serde_json::json!({"step": 2, "action": "write", "depends_on": [0, 1]}),
+    ]);
+
+    let witness = engine
+        .verify_invariants(
+            &trace,
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[behavioral_invariant("causal").with_causality(true)],
+        )
+        .expect("verify");
+
+    assert!(witness.counterexamples.is_empty());
+}
+
+#[test]
+fn test_causality_forward_reference() {
+    let engine = ProofEngine::new();
+    let trace = causal_trace(vec![
+        serde_json::json!({"step": 0, "action": "init"}),
+        serde_json::json!({"step": 1, "action": "read", "depends_on": [2]}),
+        serde_json::json!({"step": 2, "action": "write", "depends_on": [1]}),
+    ]);
+
+    let witness = engine
+        .verify_invariants(
+            &trace,
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[behavioral_invariant("causal").with_causality(true)],
+        )
+        .expect("verify");
+
+    assert_eq!(witness.counterexamples.len(), 1);
+    let ce = &witness.counterexamples[0];
+    assert_eq!(ce.error_type, "causality_violation");
+    assert_eq!(ce.step_index, Some(1));
+    assert_eq!(ce.details["dependency"], 2);
+}
+
+#[test]
+fn test_causality_self_reference_is_forward() {
+    let engine = ProofEngine::new();
+    let trace = causal_trace(vec![serde_json::json!({"step": 0, "depends_on": [0]})]);
+
+    let witness = engine
+        .verify_invariants(
+            &trace,
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[behavioral_invariant("causal").with_causality(true)],
+        )
+        .expect("verify");
+
+    assert_eq!(witness.counterexamples.len(), 1);
+    assert_eq!(witness.counterexamples[0].step_index, Some(0));
+}
+
+#[test]
+fn test_causality_out_of_range() {
+    let engine = ProofEngine::new();
+    let trace = causal_trace(vec![
+        serde_json::json!({"step": 0, "action": "init"}),
+        serde_json::json!({"step": 1, "action": "read", "depends_on": [0]}),
+        serde_json::json!({"step": 2, "action": "write", "depends_on": [7]}),
+    ]);
+
+    let witness = engine
+        .verify_invariants(
+            &trace,
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[behavioral_invariant("causal").with_causality(true)],
+        )
+        .expect("verify");
+
+    assert_eq!(witness.counterexamples.len(), 1);
+    let ce = &witness.counterexamples[0];
+    assert_eq!(ce.error_type, "causality_violation");
+    assert_eq!(ce.step_index, Some(2));
+    assert_eq!(ce.details["reason"], "dependency index out of range");
+}
+
+#[test]
+fn test_causality_is_opt_in() {
+    let trace = causal_trace(vec![serde_json::json!({"step": 0, "depends_on": [5]})]);
+    let (caps, cost, policy) = (sample_capabilities(), sample_cost_ledger(), sample_policy_ir());
+    let ctx = VerificationContext::new(&trace, &caps, &cost, &policy);
+    let engine = ProofEngine::new();
+    assert_eq!(engine.check_one(&ctx, &behavioral_invariant("plain")).expect("check"), None);
+
+    let parsed = InvariantDSL::parse_invariant("invariant causal\ncausality: true").expect("parse");
+    assert!(parsed.check_causality);
+    assert!(engine.check_one(&ctx, &parsed).expect("check").is_some());
+}
+
+// ============================================================================
+// Number Precision Tests
+// ============================================================================
+
+#[test]
+fn test_large_integer_round_trip_hash_stability() {
+    let mgr = ArtifactManager::new();
+    let artifact: serde_json::Value =
+        serde_json::from_str(r#"{"ts": 9007199254740993}"#).expect("parse");
+
+    let id = mgr.store_artifact(&artifact).expect("store");
+    let retrieved = mgr.retrieve_artifact(&id).expect("retrieve");
+    assert_eq!(artifact, retrieved);
+
+    let reparsed: serde_json::Value =
+        serde_json::from_str(&serde_json::to_string(&retrieved).expect("serialize")).expect("parse");
+    assert_eq!(reparsed.to_string(), r#"{"ts":9007199254740993}"#);
+    assert_eq!(compute_content_hash(&reparsed), id);
+    assert!(mgr.validate_artifact_integrity(&id, &reparsed));
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_arbitrary_precision_beyond_u64() {
+    let raw = r#"{"ts": 18446744073709551617}"#;
+    let a: serde_json::Value = serde_json::from_str(raw).expect("parse");
+    let b: serde_json::Value =
+        serde_json::from_str(&serde_json::to_string(&a).expect("serialize")).expect("parse");
+
+    assert_eq!(a.to_string(), r#"{"ts":18446744073709551617}"#);
+    assert_eq!(compute_content_hash(&a), compute_content_hash(&b));
+    assert_ne!(
+        compute_content_hash(&a),
+        compute_content_hash(&serde_json::json!({"ts": 18446744073709551616.0_f64}))
+    );
+}
+
+// ============================================================================
+// Single Invariant Tests
+// ============================================================================
+
+#[test]
+fn test_check_one_pass_and_fail() {
+    let engine = ProofEngine::new();
+    let caps = sample_capabilities();
+    let cost = sample_cost_ledger();
+    let policy = sample_policy_ir();
+
+    let trace = sample_trace();
+    let ctx = VerificationContext::new(&trace, &caps, &cost, &policy);
+    let result = engine.check_one(&ctx, &behavioral_invariant("causal")).expect("check");
+    assert!(result.is_none());
+
+    let bad_trace = causal_trace(vec![serde_json::json!({"step": 0, "depends_on": [3]})]);
+    let ctx = VerificationContext::new(&bad_trace, &caps, &cost, &policy);
+    let ce = engine
+        .check_one(&ctx, &behavioral_invariant("causal").with_causality(true))
+        .expect("check")
+        .expect("counterexample");
+    assert_eq!(ce.invariant_name, "causal");
+    assert_eq!(ce.step_index, Some(0));
+}
+
+#[test]
+fn test_check_one_invalid_spec() {
+    let engine = ProofEngine::new();
+    let trace = sample_trace();
+    let caps = sample_capabilities();
+    let cost = sample_cost_ledger();
+    let policy = sample_policy_ir();
+    let ctx = VerificationContext::new(&trace, &caps, &cost, &policy);
+
+    let result = engine.check_one(&ctx, &InvariantSpec::default());
+    assert!(matches!(result, Err(FakError::Validation { field, .. }) if field == "name"));
+}
+
+// ============================================================================
+// Short ID Tests
+// ============================================================================
+
+#[test]
+fn test_short_id_prefix() {
+    let hash = compute_content_hash(&serde_json::json!({"a": 1}));
+    let short = short_id(&hash);
+    assert_eq!(short.len(), SHORT_ID_LEN);
+    assert!(hash.starts_with(&short));
+    assert_eq!(short_id("abc"), "abc");
+}
+
+#[test]
+fn test_short_id_collision_detection() {
+    let a = compute_content_hash(&serde_json::json!({"a": 1}));
+    let b = compute_content_hash(&serde_json::json!({"b": 2}));
+    assert!(short_id_is_unique_among(&[a.clone(), b.clone()]));
+    assert!(short_id_is_unique_among(&[a.clone(), a.clone()]));
+
+    let colliding = format!("{}ffff", &a[..SHORT_ID_LEN]);
+    assert!(!short_id_is_unique_among(&[a, b, colliding]));
+}
+
+// ============================================================================
+// Bundle Invariant Tests
+// ============================================================================
+
+fn typed_invariant(name: &str, invariant_type: ProofType) -> InvariantSpec {
+    InvariantSpec::new(name.to_string(), String::new(), None, None, vec![], invariant_type)
+}
+
+fn witness_with(invariants: &[InvariantSpec]) -> fak::ProofWitness {
+    ProofEngine::new()
+        .verify_invariants(
+            &sample_trace(),
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            invariants,
+        )
+        .expect("verify")
+}
+
+fn economic_requires_authority() -> VerifierConfig {
+    VerifierConfig {
+        bundle_invariants: vec![BundleInvariant::Requires {
+            if_present: ProofType::EconomicInvariance,
+            then_required: ProofType::AuthorityNonEscalation,
+        }],
+        ..VerifierConfig::default()
+    }
+}
+
+#[test]
+fn test_bundle_invariant_violated() {
+    let engine = ProofEngine::new();
+    let bundle = engine
+        .generate_bundle(&[witness_with(&[typed_invariant("econ", ProofType::EconomicInvariance)])])
+        .expect("bundle");
+
+    assert!(Verifier::new().verify_bundle(&bundle).success);
+
+    let result = Verifier::with_verifier_config(economic_requires_authority()).verify_bundle(&bundle);
+    assert!(!result.success);
+    let error = result.error.expect("error");
+    assert!(error.contains("economic_invariance"));
+    assert!(error.contains("authority_non_escalation"));
+}
+
+#[test]
+fn test_bundle_invariant_satisfied_across_witnesses() {
+    let engine = ProofEngine::new();
+    let bundle = engine
+        .generate_bundle(&[
+            witness_with(&[typed_invariant("econ", ProofType::EconomicInvariance)]),
+            witness_with(&[typed_invariant("auth", ProofType::AuthorityNonEscalation)]),
+        ])
+        .expect("bundle");
+
+    let result = Verifier::with_verifier_config(economic_requires_authority()).verify_bundle(&bundle);
+    assert!(result.success, "{:?}", result.error);
+}
+
+// ============================================================================
+// Artifact Event Tests
+// ============================================================================
+
+fn recording_manager(
+    log_reads: bool,
+) -> (ArtifactManager, std::sync::Arc<std::sync::Mutex<Vec<ArtifactEvent>>>) {
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink_events = std::sync::Arc::clone(&events);
+    let mgr = ArtifactManager::with_event_sink(Box::new(move |event| {
+        sink_events.lock().expect("lock").push(event);
+    }))
+    .log_reads(log_reads);
+    (mgr, events)
+}
+
+#[test]
+fn test_artifact_events_for_mutations() {
+    let (mgr, events) = recording_manager(false);
+    let id = mgr.store_artifact(&serde_json::json!({"x": 1})).expect("store");
+    mgr.retrieve_artifact(&id).expect("retrieve");
+    mgr.remove_artifact(&id).expect("remove");
+    mgr.clear().expect("clear");
+
+    let events = events.lock().expect("lock");
+    let kinds: Vec<ArtifactEventKind> = events.iter().map(|e| e.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![ArtifactEventKind::Store, ArtifactEventKind::Remove, ArtifactEventKind::Clear]
+    );
+    assert_eq!(events[0].artifact_id.as_deref(), Some(id.as_str()));
+    assert_eq!(events[2].artifact_id, None);
+    assert!(events[0].timestamp_secs > 0.0);
+}
+
+#[test]
+fn test_artifact_events_include_reads_when_enabled() {
+    let (mgr, events) = recording_manager(true);
+    let id = mgr.store_artifact(&serde_json::json!({"x": 1})).expect("store");
+    mgr.retrieve_artifact(&id).expect("retrieve");
+    assert!(mgr.retrieve_artifact("missing").is_err());
+
+    let events = events.lock().expect("lock");
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].kind, ArtifactEventKind::Retrieve);
+}
+
+#[test]
+fn test_artifact_remove_missing() {
+    let mgr = ArtifactManager::new();
+    assert!(matches!(
+        mgr.remove_artifact("missing"),
+        Err(FakError::ArtifactNotFound { .. })
+    ));
+}
+
+// ============================================================================
+// Witness Validation Tests
+// ============================================================================
+
+#[test]
+fn test_witness_counterexample_step_index_out_of_range() {
+    let mut witness = witness_with(&[behavioral_invariant("causal")]);
+    witness.counterexamples.push(CounterExample {
+        invariant_name: "causal".to_string(),
+        error_type: "violation".to_string(),
+        details: serde_json::json!({}),
+        step_index: Some(999_999),
+    });
+
+    let out_of_range = |result: fak::FakResult<()>| {
+        matches!(
+            result,
+            Err(FakError::Validation { field, message })
+                if field == "counterexamples" && message.contains("references step 999999")
+        )
+    };
+    assert!(out_of_range(witness.validate()));
+
+    let witnesses = vec![witness];
+    let id = fak::ProofBundle::compute_id(&witnesses, &serde_json::Map::new());
+    let bundle = fak::ProofBundle::new(id, witnesses, serde_json::Map::new());
+    assert!(out_of_range(bundle.validate()));
+}
+
+#[test]
+fn test_witness_counterexample_step_index_in_range() {
+    let mut witness = witness_with(&[behavioral_invariant("causal")]);
+    witness.counterexamples.push(CounterExample {
+        invariant_name: "causal".to_string(),
+        error_type: "violation".to_string(),
+        details: serde_json::json!({}),
+        step_index: Some(0),
+    });
+    assert!(witness.validate().is_ok());
+}
+
+#[test]
+fn test_witness_orphaned_counterexample() {
+    let mut witness = witness_with(&[behavioral_invariant("causal")]);
+    witness.counterexamples.push(CounterExample {
+        invariant_name: "never_declared".to_string(),
+        error_type: "violation".to_string(),
+        details: serde_json::json!({}),
+        step_index: None,
+    });
+
+    match witness.validate() {
+        Err(FakError::Validation { field, message }) => {
+            assert_eq!(field, "counterexamples");
+            assert!(message.contains("never_declared"));
+        }
+        other => panic!("expected validation error, got {:?}", other),
+    }
+}
+
+// ============================================================================
+// Output Format Tests
+// ============================================================================
+
+fn sample_bundle() -> fak::ProofBundle {
+    ArtifactManager::new()
+        .create_bundle(
+            &sample_trace(),
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+        )
+        .expect("bundle")
+}
+
+#[test]
+fn test_write_result_json_formats() {
+    let bundle = sample_bundle();
+    let verifier = Verifier::new();
+
+    let mut compact = Vec::new();
+    let result = verifier
+        .write_result(&bundle, &mut compact, OutputFormat::Json)
+        .expect("write");
+    assert!(result.success);
+    let parsed: serde_json::Value = serde_json::from_slice(&compact).expect("json");
+    assert_eq!(parsed, verifier.verify_bundle_json(&bundle));
+
+    let mut pretty = Vec::new();
+    verifier
+        .write_result(&bundle, &mut pretty, OutputFormat::JsonPretty)
+        .expect("write");
+    let pretty_parsed: serde_json::Value = serde_json::from_slice(&pretty).expect("json");
+    assert_eq!(parsed, pretty_parsed);
+    assert!(pretty.len() > compact.len());
+}
+
+#[test]
+fn test_write_result_summary_and_junit() {
+    let mut bundle = sample_bundle();
+    let verifier = Verifier::new();
+
+    let mut summary = Vec::new();
+    verifier
+        .write_result(&bundle, &mut summary, OutputFormat::Summary)
+        .expect("write");
+    let summary = String::from_utf8(summary).expect("utf8");
+    assert!(summary.starts_with(&format!("bundle {}: PASS", bundle.id)));
+
+    bundle.id = "tampered<id>".to_string();
+    let mut junit = Vec::new();
+    let result = verifier
+        .write_result(&bundle, &mut junit, OutputFormat::Junit)
+        .expect("write");
+    assert!(!result.success);
+    let junit = String::from_utf8(junit).expect("utf8");
+    assert!(junit.contains(r#"<testsuite name="tampered&lt;id&gt;" tests="1" failures="1">"#));
+    assert!(junit.contains("<failure message=\"Bundle ID mismatch"));
+}
+
+#[test]
+fn test_report_formats_failed_bundle() {
+    let mut failing = behavioral_invariant("never_empty");
+    failing.postcondition = Some("trace.steps.len < 0".to_string());
+    let witness = ProofEngine::new()
+        .verify_invariants(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[failing])
+        .expect("witness");
+    let bundle = ProofEngine::new().generate_bundle(&[witness]).expect("bundle");
+    let result = Verifier::new().verify_bundle(&bundle);
+
+    let report = fak::report::format_bundle_result(&result);
+    assert!(report.starts_with(&format!("bundle {}: FAIL\n", bundle.id)), "{}", report);
+    assert!(report.contains(": FAIL (1 invariants, 1 counterexamples: 1 violations, 0 check errors)"), "{}", report);
+
+    let summary = fak::report::format_bundle_result_summary(&result);
+    assert_eq!(summary, format!("bundle {}: FAIL (0/1 witnesses passed, 1 counterexamples)", bundle.id));
+}
+
+// ============================================================================
+// Idempotent Bundle Tests
+// ============================================================================
+
+fn wide_capabilities() -> CapabilityManifest {
+    let mut graph = HashMap::new();
+    for i in 0..16 {
+        graph.insert(format!("role-{}", i), vec![format!("cap-{}", i)]);
+    }
+    CapabilityManifest::new(
+        "cap-wide".to_string(),
+        "agent-001".to_string(),
+        vec!["cap-0".to_string()],
+        graph,
+        serde_json::Map::new(),
+    )
+}
+
+#[test]
+fn test_generate_bundle_idempotent() {
+    let engine = ProofEngine::new();
+    let witness = |caps: &CapabilityManifest| {
+        engine
+            .verify_invariants(
+                &sample_trace(),
+                caps,
+                &sample_cost_ledger(),
+                &sample_policy_ir(),
+                &[typed_invariant("econ", ProofType::EconomicInvariance)],
+            )
+            .expect("verify")
+    };
+
+    let first = engine.generate_bundle(&[witness(&wide_capabilities())]).expect("bundle");
+    let second = engine.generate_bundle(&[witness(&wide_capabilities())]).expect("bundle");
+
+    assert_eq!(first, second);
+    assert_eq!(first.id, first.content_id());
+    assert_eq!(
+        serde_json::to_string(&first).expect("serialize"),
+        serde_json::to_string(&second).expect("serialize")
+    );
+}
+
+#[test]
+fn test_bundle_id_preimage_explains_id() {
+    let bundle = sample_bundle();
+    let preimage = bundle.id_preimage();
+    assert_eq!(compute_content_hash(&preimage), bundle.id);
+    assert_eq!(preimage["witnesses"], serde_json::json!([bundle.witnesses[0].proof_id]));
+
+    let mut tampered = bundle.clone();
+    tampered.metadata.insert("note".to_string(), serde_json::json!("edited"));
+    let changed = tampered.id_preimage();
+    assert_eq!(changed["witnesses"], preimage["witnesses"]);
+    assert_ne!(changed["metadata"], preimage["metadata"]);
+}
+
+// ============================================================================
+// Capability Diff Tests
+// ============================================================================
+
+#[test]
+fn test_capability_diff() {
+    let old = sample_capabilities();
+    let mut new = sample_capabilities();
+    new.capabilities = vec!["write".to_string()];
+    new.authority_graph.insert("admin".to_string(), vec!["read".to_string(), "delete".to_string()]);
+    new.authority_graph.insert("ops".to_string(), vec!["deploy".to_string()]);
+
+    let diff = old.diff(&new);
+    assert_eq!(diff.added_capabilities, vec!["write"]);
+    assert_eq!(diff.removed_capabilities, vec!["read"]);
+    assert_eq!(
+        diff.added_edges,
+        vec![
+            AuthorityEdge { from: "admin".to_string(), to: "delete".to_string() },
+            AuthorityEdge { from: "ops".to_string(), to: "deploy".to_string() },
+        ]
+    );
+    assert_eq!(
+        diff.removed_edges,
+        vec![AuthorityEdge { from: "admin".to_string(), to: "write".to_string() }]
+    );
+
+    let json = serde_json::to_value(&diff).expect("serialize");
+    let restored: CapabilityDiff = serde_json::from_value(json).expect("deserialize");
+    assert_eq!(restored, diff);
+}
+
+#[test]
+fn test_transitive_capabilities() {
+    let names = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<std::collections::HashSet<_>>();
+    let mut manifest = sample_capabilities();
+    manifest.capabilities = vec!["read".to_string()];
+    manifest.authority_graph = HashMap::from([
+        ("read".to_string(), vec!["write".to_string()]),
+        ("write".to_string(), vec!["deploy".to_string()]),
+        ("admin".to_string(), vec!["root".to_string()]),
+    ]);
+    assert_eq!(manifest.transitive_capabilities(), names(&["read", "write", "deploy"]));
+
+    manifest.authority_graph.insert("deploy".to_string(), vec!["read".to_string(), "admin".to_string()]);
+    assert_eq!(
+        manifest.transitive_capabilities(),
+        names(&["read", "write", "deploy", "admin", "root"])
+    );
+}
+
+#[test]
+fn test_bundle_diff() {
+    let engine = ProofEngine::new();
+    let witness = |trace_id: &str, name: &str| {
+        let trace = ExecutionTrace::new(trace_id.to_string(), vec![serde_json::json!({"step": 1})], serde_json::Map::new());
+        engine
+            .verify_invariants(&trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[behavioral_invariant(name)])
+            .expect("witness")
+    };
+    let (kept, old_b, new_b, added) = (witness("t1", "a"), witness("t1", "b"), witness("t2", "b"), witness("t1", "c"));
+    let old = engine.generate_bundle(&[kept.clone(), old_b.clone()]).unwrap();
+    let new = engine.generate_bundle(&[kept, new_b.clone(), added.clone()]).unwrap();
+
+    let diff = old.diff(&new);
+    assert_eq!(diff.added, vec![added.proof_id.clone()]);
+    assert!(diff.removed.is_empty());
+    assert_eq!(
+        diff.changed,
+        vec![fak::WitnessChange { old_proof_id: old_b.proof_id, new_proof_id: new_b.proof_id }]
+    );
+    assert_eq!(new.diff(&old).removed, vec![added.proof_id]);
+    assert!(new.diff(&new).is_empty());
+}
+
+#[test]
+fn test_capability_diff_identical() {
+    let caps = sample_capabilities();
+    assert!(caps.diff(&caps.clone()).is_empty());
+}
+
+#[test]
+fn test_unreachable_nodes() {
+    let mut caps = sample_capabilities();
+    assert_eq!(caps.unreachable_nodes(), vec!["admin", "write"]);
+
+    caps.authority_graph.insert("read".to_string(), vec!["write".to_string()]);
+    caps.authority_graph.insert("orphan".to_string(), vec!["delete".to_string(), "read".to_string()]);
+    assert_eq!(caps.unreachable_nodes(), vec!["admin", "delete", "orphan"]);
+
+    // The agent ID is not a root of the grant graph.
+    caps.authority_graph.insert("agent-001".to_string(), vec!["admin".to_string()]);
+    assert!(caps.unreachable_nodes().contains(&"agent-001".to_string()));
+    assert!(caps.unreachable_nodes().contains(&"admin".to_string()));
+}
+
+// ============================================================================
+// Compact Bundle Tests
+// ============================================================================
+
+#[test]
+fn test_compact_bundle_shares_artifacts() {
+    let engine = ProofEngine::new();
+    let bundle = engine
+        .generate_bundle(&[
+            witness_with(&[typed_invariant("econ", ProofType::EconomicInvariance)]),
+            witness_with(&[typed_invariant("auth", ProofType::AuthorityNonEscalation)]),
+        ])
+        .expect("bundle");
+
+    let compact = bundle.compact().expect("compact");
+    assert_eq!(compact.witnesses.len(), 2);
+    assert_eq!(compact.artifacts.len(), 4);
+    assert_eq!(compact.witnesses[0].policy_ir, compact.witnesses[1].policy_ir);
+    assert_eq!(compact.witnesses[0].execution_trace, compact.witnesses[1].execution_trace);
+    let traces = compact.artifacts.values().filter(|a| a.get("steps").is_some()).count();
+    assert_eq!(traces, 1);
+
+    let expanded = compact.expand().expect("expand");
+    assert_eq!(expanded, bundle);
+    assert_eq!(expanded.content_id(), bundle.id);
+
+    let verifier = Verifier::new();
+    assert_eq!(verifier.verify_compact_bundle(&compact), verifier.verify_bundle(&bundle));
+}
+
+#[test]
+fn test_compact_bundle_detects_tampered_pool() {
+    let mut compact = sample_bundle().compact().expect("compact");
+    let trace_id = compact.witnesses[0].execution_trace.clone();
+    compact.artifacts.insert(trace_id, serde_json::json!({"id": "forged", "steps": [], "metadata": {}}));
+
+    assert!(matches!(compact.expand(), Err(FakError::IntegrityFailure { .. })));
+    let result = Verifier::new().verify_compact_bundle(&compact);
+    assert!(!result.success);
+
+    compact.artifacts.clear();
+    assert!(matches!(compact.expand(), Err(FakError::ArtifactNotFound { .. })));
+}
+
+#[test]
+fn test_thin_bundle_resolves_from_frozen_snapshot() {
+    let manager = ArtifactManager::new();
+    let bundle = manager
+        .create_bundle(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir())
+        .expect("bundle");
+    let snapshot = manager.snapshot().expect("snapshot");
+    assert_eq!(snapshot.len(), 4);
+
+    manager.clear().expect("clear");
+    manager.store_artifact(&serde_json::json!({"late": true})).expect("store");
+    assert_eq!(snapshot.len(), 4);
+    assert_eq!(manager.snapshot().expect("snapshot").len(), 1);
+
+    let mut thin = bundle.compact().expect("compact");
+    thin.artifacts.clear();
+    let verifier = Verifier::new();
+    assert!(!verifier.verify_compact_bundle(&thin).success);
+    assert_eq!(verifier.verify_compact_bundle_in(&thin, &snapshot), verifier.verify_bundle(&bundle));
+}
+
+#[test]
+fn test_thin_bundle_round_trips_through_salted_snapshot() {
+    let manager = ArtifactManager::new().with_salt("deploy-east").with_hash_algo(HashAlgo::Blake3);
+    let bundle = manager
+        .create_bundle(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir())
+        .expect("bundle");
+    let snapshot = manager.snapshot().expect("snapshot");
+
+    let thin = bundle.compact_against(&snapshot).expect("compact");
+    assert!(thin.artifacts.is_empty());
+    assert!(thin.witnesses[0].execution_trace.starts_with("blake3:"));
+    assert_eq!(thin.expand_from(&snapshot).expect("expand"), bundle);
+    let verifier = Verifier::new();
+    assert_eq!(verifier.verify_compact_bundle_in(&thin, &snapshot), verifier.verify_bundle(&bundle));
+
+    // Plain-hash references cannot be found in a salted store.
+    let mut plain = bundle.compact().expect("compact");
+    plain.artifacts.clear();
+    assert!(matches!(plain.expand_from(&snapshot), Err(FakError::ArtifactNotFound { .. })));
+
+    // A snapshot of a differently salted manager holds none of them.
+    let west = ArtifactManager::new().with_salt("deploy-west");
+    west.create_bundle(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir())
+        .expect("bundle");
+    assert!(thin.expand_from(&west.snapshot().expect("snapshot")).is_err());
+}
+
+// ============================================================================
+// Profile Tests
+// ============================================================================
+
+fn baseline_profile() -> Profile {
+    Profile::new(
+        "baseline-v1".to_string(),
+        vec![
+            ("econ".to_string(), ProofType::EconomicInvariance),
+            ("auth".to_string(), ProofType::AuthorityNonEscalation),
+        ],
+    )
+}
+
+#[test]
+fn test_profile_conforming_bundle() {
+    let bundle = ProofEngine::new()
+        .generate_bundle(&[witness_with(&[
+            typed_invariant("econ", ProofType::EconomicInvariance),
+            typed_invariant("auth", ProofType::AuthorityNonEscalation),
+            typed_invariant("extra", ProofType::SemanticPreservation),
+        ])])
+        .expect("bundle");
+
+    let result = Verifier::new().verify_bundle_against_profile(&bundle, &baseline_profile());
+    assert!(result.success, "{:?}", result);
+}
+
+#[test]
+fn test_profile_nonconforming_witness() {
+    let bundle = ProofEngine::new()
+        .generate_bundle(&[witness_with(&[typed_invariant(
+            "auth",
+            ProofType::SemanticPreservation,
+        )])])
+        .expect("bundle");
+
+    let result = Verifier::new().verify_bundle_against_profile(&bundle, &baseline_profile());
+    assert!(!result.success);
+    let error = result.witness_results[0].error.clone().expect("error");
+    assert!(error.contains("profile 'baseline-v1'"));
+    assert!(error.contains("missing invariant 'econ' (economic_invariance)"));
+    assert!(error.contains("invariant 'auth' is semantic_preservation, expected authority_non_escalation"));
+}
+
+// ============================================================================
+// Remediation Tests
+// ============================================================================
+
+#[test]
+fn test_dsl_parse_remediation() {
+    let spec = r#"
+        invariant cost_bound
+        postcondition: spent <= budget
+        remediation: reduce batch size or raise budget
+    "#;
+
+    let parsed = InvariantDSL::parse_invariant(spec).expect("parse");
+    assert_eq!(parsed.remediation, Some("reduce batch size or raise budget".to_string()));
+}
+
+#[test]
+fn test_remediation_surfaces_for_failing_invariants() {
+    let failing = InvariantSpec::new(
+        "needs_steps".to_string(),
+        String::new(),
+        Some("steps > 0".to_string()),
+        None,
+        vec![],
+        ProofType::BehavioralSoundness,
+    )
+    .with_remediation("record at least one trace step".to_string());
+    let passing = typed_invariant("econ", ProofType::EconomicInvariance)
+        .with_remediation("never shown".to_string());
+
+    let empty_trace = ExecutionTrace::new("trace-empty".to_string(), vec![], serde_json::Map::new());
+    let witness = ProofEngine::new()
+        .verify_invariants(
+            &empty_trace,
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[failing, passing],
+        )
+        .expect("verify");
+    let bundle = ProofEngine::new().generate_bundle(&[witness]).expect("bundle");
+
+    let result = Verifier::new().verify_bundle(&bundle);
+    assert!(!result.success);
+    let remediations = &result.witness_results[0].remediations;
+    assert_eq!(remediations.len(), 1);
+    assert_eq!(remediations["needs_steps"], "record at least one trace step");
+}
+
+// ============================================================================
+// Coverage Tests
+// ============================================================================
+
+fn four_step_trace() -> ExecutionTrace {
+    causal_trace((0..4).map(|i| serde_json::json!({"step": i})).collect())
+}
+
+fn step_invariant(name: &str, postcondition: &str) -> InvariantSpec {
+    let mut inv = behavioral_invariant(name);
+    inv.postcondition = Some(postcondition.to_string());
+    inv
+}
+
+fn coverage_for(invariants: &[InvariantSpec]) -> fak::engine::CoverageReport {
+    ProofEngine::new()
+        .verify_invariants_with_coverage(
+            &four_step_trace(),
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            invariants,
+        )
+        .expect("verify")
+        .1
+}
+
+#[test]
+fn test_coverage_full_when_behavioral_invariant_reads_all_steps() {
+    let coverage = coverage_for(&[step_invariant("non_negative", "step >= 0")]);
+    assert!(coverage.is_complete());
+    assert_eq!(coverage.inspected, vec![(0, 3)]);
+    assert_eq!(coverage.to_string(), "steps 0-3 inspected");
+}
+
+#[test]
+fn test_coverage_reports_unread_steps() {
+    // The first failing step ends the scan.
+    let coverage = coverage_for(&[step_invariant("early", "step < 1")]);
+    assert_eq!(coverage.inspected, vec![(0, 1)]);
+    assert_eq!(coverage.uninspected, vec![(2, 3)]);
+    assert_eq!(coverage.to_string(), "steps 0-1 inspected, 2-3 never read by any invariant");
+
+    // Structural checks alone evaluate no per-step condition.
+    for invariants in [
+        vec![behavioral_invariant("causal")],
+        vec![typed_invariant("econ", ProofType::EconomicInvariance)],
+    ] {
+        let coverage = coverage_for(&invariants);
+        assert!(!coverage.is_complete());
+        assert_eq!(coverage.uninspected, vec![(0, 3)]);
+    }
+}
+
+// ============================================================================
+// Bundle Consistency Tests
+// ============================================================================
+
+#[test]
+fn test_consistency_check_flags_divergent_agents() {
+    let engine = ProofEngine::new();
+    let mut other_agent = sample_capabilities();
+    other_agent.agent_id = "agent-002".to_string();
+    let foreign = engine
+        .verify_invariants(
+            &sample_trace(),
+            &other_agent,
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[typed_invariant("auth", ProofType::AuthorityNonEscalation)],
+        )
+        .expect("verify");
+    let bundle = engine
+        .generate_bundle(&[witness_with(&[typed_invariant("econ", ProofType::EconomicInvariance)]), foreign])
+        .expect("bundle");
+
+    assert!(Verifier::new().verify_bundle(&bundle).success);
+
+    let config = VerifierConfig {
+        consistent_fields: vec![
+            "capability_manifest.agent_id".to_string(),
+            "policy_ir.id".to_string(),
+        ],
+        ..VerifierConfig::default()
+    };
+    let result = Verifier::with_verifier_config(config).verify_bundle(&bundle);
+    assert!(!result.success);
+    let error = result.error.expect("error");
+    assert!(error.contains("witnesses disagree on 'capability_manifest.agent_id'"));
+    assert!(error.contains("\"agent-001\""));
+    assert!(error.contains("\"agent-002\""));
+    assert!(!error.contains("policy_ir.id"));
+}
+
+#[test]
+fn test_unique_trace_ids_flags_reused_ids() {
+    let engine = ProofEngine::new();
+    let witness_for = |trace: &ExecutionTrace, name: &str| {
+        engine
+            .verify_invariants(
+                trace,
+                &sample_capabilities(),
+                &sample_cost_ledger(),
+                &sample_policy_ir(),
+                &[typed_invariant(name, ProofType::EconomicInvariance)],
+            )
+            .expect("verify")
+    };
+    let mut altered = sample_trace();
+    altered.steps.push(serde_json::json!({"step": 99, "action": "extra"}));
+    let strict = Verifier::with_verifier_config(VerifierConfig {
+        unique_trace_ids: true,
+        ..VerifierConfig::default()
+    });
+
+    let shared = engine
+        .generate_bundle(&[witness_for(&sample_trace(), "a"), witness_for(&sample_trace(), "b")])
+        .expect("bundle");
+    assert!(strict.verify_bundle(&shared).success);
+
+    let reused = engine
+        .generate_bundle(&[witness_for(&sample_trace(), "a"), witness_for(&altered, "b")])
+        .expect("bundle");
+    assert!(Verifier::new().verify_bundle(&reused).success);
+    let result = strict.verify_bundle(&reused);
+    assert!(!result.success);
+    let error = result.error.expect("error");
+    assert!(error.contains(&format!("trace ID '{}' names different traces", altered.id)));
+}
+
+// ============================================================================
+// Binary Encoding Tests
+// ============================================================================
+
+#[cfg(feature = "binary")]
+#[test]
+fn test_binary_round_trip() {
+    let bundle = sample_bundle();
+    let bytes = bundle.to_bytes().expect("encode");
+    assert!(bytes.len() < serde_json::to_vec(&bundle).expect("json").len());
+
+    let decoded = fak::ProofBundle::from_bytes(&bytes).expect("decode");
+    assert_eq!(decoded, bundle);
+    assert!(Verifier::new().verify_bundle(&decoded).success);
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn test_binary_rejects_mismatched_id() {
+    let mut bundle = sample_bundle();
+    bundle.id = "0".repeat(64);
+    let bytes = bundle.to_bytes().expect("encode");
+    assert!(matches!(
+        fak::ProofBundle::from_bytes(&bytes),
+        Err(FakError::IntegrityFailure { .. })
+    ));
+    assert!(matches!(
+        fak::ProofBundle::from_bytes(&bytes[..bytes.len() / 2]),
+        Err(FakError::Serialization { .. })
+    ));
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_round_trip_keeps_bundle_id() {
+    let bundle = sample_bundle();
+    let bytes = bundle.to_cbor().expect("encode");
+
+    let decoded = fak::ProofBundle::from_cbor(&bytes).expect("decode");
+    assert_eq!(decoded, bundle);
+    assert_eq!(decoded.content_id(), bundle.id);
+    assert!(Verifier::new().verify_bundle(&decoded).success);
+
+    let mut tampered = bundle;
+    tampered.metadata.insert("edited".to_string(), serde_json::json!(true));
+    assert!(matches!(
+        fak::ProofBundle::from_cbor(&tampered.to_cbor().unwrap()),
+        Err(FakError::IntegrityFailure { .. })
+    ));
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_compressed_round_trip_keeps_bundle_id() {
+    let engine = ProofEngine::new();
+    let witnesses: Vec<_> = (0..50)
+        .map(|i| {
+            let trace = ExecutionTrace::new(format!("trace-{}", i), sample_trace().steps, serde_json::Map::new());
+            engine
+                .verify_invariants(&trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[])
+                .expect("witness")
+        })
+        .collect();
+    let bundle = engine.generate_bundle(&witnesses).expect("bundle");
+    let bytes = bundle.to_compressed().expect("compress");
+    assert!(bytes.len() < bundle.to_json().unwrap().len() / 4);
+
+    let decoded = fak::ProofBundle::from_compressed(&bytes).expect("decompress");
+    assert_eq!(decoded, bundle);
+    assert!(Verifier::new().verify_bundle(&decoded).success);
+    assert!(matches!(
+        fak::ProofBundle::from_compressed(&bytes[..bytes.len() / 2]),
+        Err(FakError::Serialization { .. })
+    ));
+}
+
+// ============================================================================
+// Bundle Archive Tests
+// ============================================================================
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_bundle_archive_round_trip() {
+    let bundle = sample_bundle();
+    for gzip in [false, true] {
+        let mut archive = Vec::new();
+        ArtifactManager::new()
+            .export_bundle_archive(&bundle, &mut archive, gzip)
+            .expect("export");
+
+        let manager = ArtifactManager::new();
+        let imported = manager.import_bundle_archive(archive.as_slice()).expect("import");
+        assert_eq!(imported, bundle);
+        assert!(Verifier::new().verify_bundle(&imported).success);
+        let trace_id = compute_content_hash(&serde_json::to_value(&bundle.witnesses[0].execution_trace).unwrap());
+        assert!(manager.contains(&trace_id).unwrap());
+    }
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_bundle_archive_rejects_corrupt_artifact() {
+    let mut archive = Vec::new();
+    ArtifactManager::new()
+        .export_bundle_archive(&sample_bundle(), &mut archive, false)
+        .expect("export");
+
+    let needle = b"agent-001";
+    let pos = archive.windows(needle.len()).rposition(|w| w == needle).expect("agent id in archive");
+    archive[pos + needle.len() - 1] = b'2';
+
+    let manager = ArtifactManager::new();
+    match manager.import_bundle_archive(archive.as_slice()) {
+        Err(FakError::IntegrityFailure { artifact_id, .. }) => assert!(artifact_id.starts_with("artifacts/")),
+        other => panic!("expected integrity failure, got {:?}", other),
+    }
+    assert!(matches!(
+        manager.import_bundle_archive(&b"not an archive"[..]),
+        Err(FakError::Io { .. }) | Err(FakError::Validation { .. })
+    ));
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_bundle_archive_limits_members() {
+    let tar_of = |members: &[(&str, u64, &[u8])]| {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, size, data) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(*size);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).expect("append");
+        }
+        builder.into_inner().expect("tar")
+    };
+    let manager = ArtifactManager::new();
+
+    let oversized = (ArtifactManager::MAX_ARCHIVE_MEMBER_BYTES + 1) as u64;
+    let archive = tar_of(&[("bundle.json", oversized, &b""[..])]);
+    assert!(matches!(
+        manager.import_bundle_archive(archive.as_slice()),
+        Err(FakError::ResourceLimit { resource, .. }) if resource == "archive_member_bytes"
+    ));
+
+    let artifact = serde_json::json!({"n": 1});
+    let path = format!("artifacts/{}.json", compute_content_hash(&artifact));
+    let data = serde_json::to_vec(&artifact).expect("artifact json");
+    let members = vec![(path.as_str(), data.len() as u64, data.as_slice()); ArtifactManager::MAX_ARCHIVE_MEMBERS + 1];
+    assert!(matches!(
+        manager.import_bundle_archive(tar_of(&members).as_slice()),
+        Err(FakError::ResourceLimit { resource, actual, .. })
+            if resource == "archive_members" && actual == ArtifactManager::MAX_ARCHIVE_MEMBERS + 1
+    ));
+    assert!(manager.snapshot().expect("snapshot").is_empty());
+}
+
+// ============================================================================
+// Entry Cost Sum Tests
+// ============================================================================
+
+fn ledger_with(costs: &[f64], total_cost: f64) -> CostLedger {
+    let entries = costs.iter().map(|c| serde_json::json!({"op": "inference", "cost": c})).collect();
+    CostLedger::new("cost-sum".to_string(), entries, total_cost, serde_json::Map::new())
+}
+
+fn check_economic(ledger: &CostLedger) -> Option<CounterExample> {
+    let trace = sample_trace();
+    let caps = sample_capabilities();
+    let policy = sample_policy_ir();
+    let ctx = VerificationContext::new(&trace, &caps, ledger, &policy);
+    ProofEngine::new()
+        .check_one(&ctx, &typed_invariant("econ", ProofType::EconomicInvariance))
+        .expect("check")
+}
+
+#[test]
+fn test_entry_sum_matches_total() {
+    let costs: Vec<f64> = (0..1000).map(|_| 0.1).collect();
+    assert!(check_economic(&ledger_with(&costs, 100.0)).is_none());
+}
+
+#[test]
+fn test_entry_sum_mismatch() {
+    let ce = check_economic(&ledger_with(&[0.5, 0.25], 5.0)).expect("counterexample");
+    assert_eq!(ce.error_type, "cost_mismatch");
+    assert_eq!(ce.details["entry_sum"], 0.75);
+}
+
+#[test]
+fn test_ledger_reconcile() {
+    let costs: Vec<f64> = (0..1000).map(|_| 0.1).collect();
+    ledger_with(&costs, 100.0).reconcile().expect("reconciles");
+
+    let inflated = ledger_with(&[0.5, 0.25], 0.75 + 1e-6);
+    assert!(matches!(
+        inflated.reconcile(),
+        Err(FakError::Validation { field, .. }) if field == "total_cost"
+    ));
+    inflated.reconcile_within(1e-3).expect("within epsilon");
+    assert_eq!(check_economic(&inflated).expect("counterexample").error_type, "cost_mismatch");
+}
+
+#[test]
+fn test_entry_sum_precision_loss() {
+    let ledger = ledger_with(&[1e16, 1.0, -1e16], 1.0);
+    let sum = ledger.sum_entry_costs().expect("sum");
+    assert_eq!(sum.total, 1.0);
+    assert_eq!(sum.counted, 3);
+
+    let ce = check_economic(&ledger).expect("counterexample");
+    assert_eq!(ce.error_type, "precision_loss");
+}
+
+#[test]
+fn test_entry_sum_large_entries_are_not_precision_loss() {
+    let costs = vec![1e6 + 0.1; 1000];
+    let ledger = ledger_with(&costs, 1_000_000_100.0);
+    let sum = ledger.sum_entry_costs().expect("sum");
+    assert!(sum.rounding_error > 1e-9, "naive drift {}", sum.rounding_error);
+    assert_eq!(check_economic(&ledger), None);
+}
+
+#[test]
+fn test_entry_sum_overflow() {
+    let ce = check_economic(&ledger_with(&[1.7e308, 1.7e308], 1.0)).expect("counterexample");
+    assert_eq!(ce.error_type, "overflow");
+}
+
+#[test]
+fn test_entry_sum_non_numeric_cost() {
+    let ledger = CostLedger::new(
+        "cost-bad".to_string(),
+        vec![serde_json::json!({"cost": "free"})],
+        0.0,
+        serde_json::Map::new(),
+    );
+    assert!(matches!(
+        ledger.sum_entry_costs(),
+        Err(FakError::Validation { field, .. }) if field == "entries"
+    ));
+}
+
+fn cumulative_ledger(running: &[f64], total_cost: f64) -> CostLedger {
+    let entries = running
+        .iter()
+        .map(|c| serde_json::json!({"op": "inference", "cumulative_cost": c}))
+        .chain(std::iter::once(serde_json::json!({"op": "note"})))
+        .collect();
+    CostLedger::new("cost-running".to_string(), entries, total_cost, serde_json::Map::new())
+}
+
+#[test]
+fn test_cumulative_cost_monotonic() {
+    assert!(check_economic(&cumulative_ledger(&[1.0, 1.0, 2.5], 2.5)).is_none());
+
+    let ce = check_economic(&cumulative_ledger(&[1.0, 3.0, 2.0, 4.0], 4.0)).expect("counterexample");
+    assert_eq!(ce.error_type, "cost_decreased");
+    assert_eq!(ce.details["entry_index"], 2);
+
+    let ce = check_economic(&cumulative_ledger(&[1.0, 2.0], 5.0)).expect("counterexample");
+    assert_eq!(ce.error_type, "cumulative_mismatch");
+    assert_eq!(ce.details["cumulative_cost"], 2.0);
+}
+
+#[test]
+fn test_invariant_tolerance_overrides_engine_default() {
+    let spec = "invariant econ\ntype: EconomicInvariance\ntolerance: 0.01";
+    let inv = InvariantDSL::parse_invariant(spec).expect("parse");
+    assert_eq!(inv.tolerance, Some(0.01));
+
+    let trace = sample_trace();
+    let caps = sample_capabilities();
+    let policy = sample_policy_ir();
+    let ledger = ledger_with(&[0.5, 0.495], 1.0);
+    let ctx = VerificationContext::new(&trace, &caps, &ledger, &policy);
+    let engine = ProofEngine::new();
+    assert!(engine.check_one(&ctx, &inv).expect("check").is_none());
+    assert!(check_economic(&ledger).is_some());
+
+    // cost_tolerance only applies to economic conditions by default.
+    let exact_ledger = ledger_with(&[0.5, 0.5], 1.0);
+    let ctx = VerificationContext::new(&trace, &caps, &exact_ledger, &policy);
+    let mut econ = typed_invariant("econ_exact", ProofType::EconomicInvariance);
+    econ.postcondition = Some("total_cost == 1.0000000001".to_string());
+    assert!(engine.check_one(&ctx, &econ).expect("check").is_none());
+    let mut semantic = typed_invariant("semantic_exact", ProofType::SemanticPreservation);
+    semantic.postcondition = Some("cost.total_cost == 1.0000000001".to_string());
+    assert!(engine.check_one(&ctx, &semantic).expect("check").is_some());
+    assert!(engine.check_one(&ctx, &semantic.with_tolerance(1e-9)).expect("check").is_none());
+
+    assert!(InvariantDSL::parse_invariant("invariant econ\ntolerance: lots").is_err());
+    assert!(typed_invariant("econ", ProofType::EconomicInvariance)
+        .with_tolerance(-1.0)
+        .validate()
+        .is_err());
+}
+
+// ============================================================================
+// Pinned Engine Config Tests
+// ============================================================================
+
+#[test]
+fn test_bundle_pins_engine_config() {
+    let bundle = sample_bundle();
+    let pinned = &bundle.metadata[EngineConfig::METADATA_KEY];
+    assert_eq!(pinned["fingerprint"], EngineConfig::default().fingerprint());
+    assert_eq!(pinned["functions"]["abs"], serde_json::Value::Null);
+    assert!(pinned.get("max_invariants").is_none());
+
+    let result = Verifier::new().verify_bundle(&bundle);
+    assert!(result.success);
+    assert!(result.warnings.is_empty());
+}
+
+#[test]
+fn test_material_config_difference_warns_or_fails() {
+    let bundle = sample_bundle();
+    let engine = EngineConfig {
+        cost_tolerance: 1e-3,
+        ..EngineConfig::default()
+    };
+
+    let result = Verifier::with_config(engine.clone()).verify_bundle(&bundle);
+    assert!(result.success);
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].contains("cost_tolerance"));
+
+    let strict = VerifierConfig {
+        engine,
+        strict_config: true,
+        ..VerifierConfig::default()
+    };
+    let result = Verifier::with_verifier_config(strict).verify_bundle(&bundle);
+    assert!(!result.success);
+    assert!(result.error.expect("error").contains("different engine config"));
+}
+
+#[test]
+fn test_timeout_and_limits_are_not_material() {
+    let bundle = sample_bundle();
+    for engine in [
+        EngineConfig { timeout_secs: 5.0, ..EngineConfig::default() },
+        EngineConfig { max_invariants: 10, ..EngineConfig::default() },
+    ] {
+        let result = Verifier::with_config(engine).verify_bundle(&bundle);
+        assert!(result.warnings.is_empty());
+    }
+}
+
+#[test]
+fn test_function_registry_is_material() {
+    let bundle = sample_bundle();
+    let with_functions = |functions: fak::functions::FunctionRegistry| EngineConfig {
+        functions,
+        ..EngineConfig::default()
+    };
+    let base = fak::functions::FunctionRegistry::new();
+
+    let added = with_functions(base.clone().with_function("double", |args| Ok(args[0].clone())));
+    let result = Verifier::with_config(added).verify_bundle(&bundle);
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].contains("functions"));
+
+    let v1 = with_functions(base.clone().with_versioned_function("abs", "1", |args| Ok(args[0].clone())));
+    let v2 = with_functions(base.with_versioned_function("abs", "2", |args| Ok(args[0].clone())));
+    assert_ne!(v1.fingerprint(), v2.fingerprint());
+    assert_ne!(v1.fingerprint(), EngineConfig::default().fingerprint());
+}
+
+// ============================================================================
+// Bundle Coverage Comparison Tests
+// ============================================================================
+
+#[test]
+fn test_bundle_covers_superset() {
+    let engine = ProofEngine::new();
+    let econ = typed_invariant("econ", ProofType::EconomicInvariance);
+    let auth = typed_invariant("auth", ProofType::AuthorityNonEscalation);
+
+    let weak = engine.generate_bundle(&[witness_with(std::slice::from_ref(&econ))]).expect("bundle");
+    let strong = engine
+        .generate_bundle(&[witness_with(&[econ]), witness_with(&[auth])])
+        .expect("bundle");
+
+    assert!(strong.covers(&weak));
+    assert!(!weak.covers(&strong));
+    assert!(weak.covers(&weak));
+}
+
+#[test]
+fn test_bundle_covers_requires_same_artifacts_and_spec() {
+    let engine = ProofEngine::new();
+    let econ = typed_invariant("econ", ProofType::EconomicInvariance);
+    let base = engine.generate_bundle(&[witness_with(std::slice::from_ref(&econ))]).expect("bundle");
+
+    let mut other_trace = sample_trace();
+    other_trace.id = "trace-002".to_string();
+    let elsewhere = engine
+        .verify_invariants(
+            &other_trace,
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            std::slice::from_ref(&econ),
+        )
+        .expect("verify");
+    let moved = engine.generate_bundle(&[elsewhere]).expect("bundle");
+    assert!(!moved.covers(&base));
+
+    let mut weakened = econ;
+    weakened.postcondition = Some("total_cost >= 0".to_string());
+    let changed = engine.generate_bundle(&[witness_with(&[weakened])]).expect("bundle");
+    assert!(!changed.covers(&base));
+}
+
+#[test]
+fn test_bundle_covers_ignores_failed_invariants() {
+    let engine = ProofEngine::new();
+    let failing = InvariantSpec::new(
+        "needs_steps".to_string(),
+        String::new(),
+        Some("steps > 0".to_string()),
+        None,
+        vec![],
+        ProofType::BehavioralSoundness,
+    );
+    let empty_trace = ExecutionTrace::new("trace-empty".to_string(), vec![], serde_json::Map::new());
+    let witness = engine
+        .verify_invariants(
+            &empty_trace,
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[failing],
+        )
+        .expect("verify");
+    let failed = engine.generate_bundle(&[witness]).expect("bundle");
+    let unrelated = sample_bundle();
+
+    assert!(unrelated.covers(&failed));
+}
+
+// ============================================================================
+// Incremental Hash Tests
+// ============================================================================
+
+#[test]
+fn test_incremental_hash_matches_full_hash() {
+    let steps: Vec<serde_json::Value> = (0..5)
+        .map(|i| serde_json::json!({"step": i, "action": "act", "meta": {"b": 2, "a": 1}}))
+        .collect();
+
+    let mut hasher = IncrementalHasher::new();
+    assert_eq!(hasher.current_hash(), compute_content_hash(&serde_json::json!([])));
+
+    for (i, step) in steps.iter().enumerate() {
+        hasher.update(step);
+        let prefix = serde_json::Value::Array(steps[..=i].to_vec());
+        assert_eq!(hasher.current_hash(), compute_content_hash(&prefix));
+    }
+
+    assert_eq!(hasher.len(), 5);
+    assert_eq!(hasher.finalize(), compute_content_hash(&serde_json::Value::Array(steps)));
+}
+
+// ============================================================================
+// Validation Hook Tests
+// ============================================================================
+
+fn hooked_engine(calls: Arc<Mutex<Vec<&'static str>>>) -> ProofEngine {
+    let first = calls.clone();
+    let second = calls;
+    let hooks = ValidationHooks::new()
+        .on_trace(move |trace| {
+            first.lock().expect("lock").push("version");
+            if trace.metadata.contains_key("version") {
+                Ok(())
+            } else {
+                Err(FakError::Validation {
+                    field: "metadata".to_string(),
+                    message: "trace must declare a version".to_string(),
+                })
+            }
+        })
+        .on_trace(move |_| {
+            second.lock().expect("lock").push("second");
+            Ok(())
+        });
+    ProofEngine::with_config(EngineConfig { hooks, ..EngineConfig::default() })
+}
+
+#[test]
+fn test_validation_hooks_run_in_order_and_stop_at_first_error() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let engine = hooked_engine(calls.clone());
+
+    let err = engine
+        .verify_invariants(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[])
+        .unwrap_err();
+    assert!(matches!(err, FakError::Validation { ref field, .. } if field == "metadata"));
+    assert_eq!(*calls.lock().expect("lock"), vec!["version"]);
+
+    calls.lock().expect("lock").clear();
+    let mut trace = sample_trace();
+    trace.metadata.insert("version".to_string(), serde_json::json!("1"));
+    engine
+        .verify_invariants(&trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[])
+        .expect("verify");
+    assert_eq!(*calls.lock().expect("lock"), vec!["version", "second"]);
+}
+
+#[test]
+fn test_create_bundle_runs_hooks_before_storing() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let manager = ArtifactManager::new().with_engine(hooked_engine(calls));
+    let trace = sample_trace();
+
+    assert!(manager
+        .create_bundle(&trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir())
+        .is_err());
+    let trace_id = compute_content_hash(&serde_json::to_value(&trace).expect("to_value"));
+    assert!(!manager.contains(&trace_id).expect("contains"));
+}
+
+// ============================================================================
+// Format Migration Tests
+// ============================================================================
+
+#[test]
+fn test_migrate_current_bundle_is_identity() {
+    let bundle = sample_bundle();
+    assert_eq!(bundle.format_version(), Some(fak::ProofBundle::FORMAT_VERSION));
+    let migrated = bundle.clone().migrate(fak::ProofBundle::FORMAT_VERSION).expect("migrate");
+    assert_eq!(migrated, bundle);
+}
+
+#[test]
+fn test_migrate_rejects_unknown_version_and_tampered_bundle() {
+    let bundle = sample_bundle();
+    let err = bundle.clone().migrate(fak::ProofBundle::FORMAT_VERSION + 1).unwrap_err();
+    assert!(err.to_string().contains("unknown target format version"));
+
+    let mut tampered = bundle;
+    tampered.metadata.insert("note".to_string(), serde_json::json!("edited"));
+    assert_eq!(tampered.format_version(), None);
+    let err = tampered.migrate(fak::ProofBundle::FORMAT_VERSION).unwrap_err();
+    assert!(err.to_string().contains("matches no known format version"));
+}
+
+#[test]
+fn test_auto_migrate_leaves_current_bundles_alone() {
+    let verifier = Verifier::with_verifier_config(VerifierConfig {
+        auto_migrate: true,
+        ..VerifierConfig::default()
+    });
+    let result = verifier.verify_bundle(&sample_bundle());
+    assert!(result.success);
+    assert!(result.warnings.is_empty());
+}
+
+fn two_witnesses() -> (fak::ProofWitness, fak::ProofWitness) {
+    let engine = ProofEngine::new();
+    let other_trace = ExecutionTrace::new("trace-002".to_string(), vec![serde_json::json!({"step": 2})], serde_json::Map::new());
+    let mut witnesses = [sample_trace(), other_trace].map(|trace| {
+        engine
+            .verify_invariants(&trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[])
+            .unwrap()
+    });
+    witnesses.sort_by(|a, b| a.proof_id.cmp(&b.proof_id));
+    let [low, high] = witnesses;
+    (low, high)
+}
+
+#[test]
+fn test_bundle_id_is_independent_of_witness_order() {
+    let (low, high) = two_witnesses();
+    let engine = ProofEngine::new();
+    let forward = engine.generate_bundle(&[low.clone(), high.clone()]).unwrap();
+    let reversed = engine.generate_bundle(&[high.clone(), low.clone()]).unwrap();
+    assert_eq!(forward.id, reversed.id);
+    assert_eq!(forward, reversed);
+    assert_eq!(reversed.witnesses[0].proof_id, low.proof_id);
+
+    // A hand-assembled bundle in another order carries the same ID too.
+    let shuffled = fak::ProofBundle::new(forward.id.clone(), vec![high, low], forward.metadata.clone());
+    assert!(shuffled.verify_id().is_ok());
+    let verifier = Verifier::new();
+    assert!(verifier.verify_bundle(&forward).success);
+    assert!(verifier.verify_bundle(&shuffled).success);
+}
+
+#[test]
+fn test_migrate_ordered_v1_bundle() {
+    let (low, high) = two_witnesses();
+    let metadata = serde_json::Map::new();
+    let v1_id = compute_content_hash(&serde_json::json!({
+        "witnesses": [high.proof_id.clone(), low.proof_id.clone()],
+        "metadata": metadata,
+    }));
+    let v1 = fak::ProofBundle::new(v1_id, vec![high, low], metadata);
+    assert_eq!(v1.format_version(), Some(1));
+    assert!(!Verifier::new().verify_bundle(&v1).success);
+
+    let migrated = v1.clone().migrate(fak::ProofBundle::FORMAT_VERSION).expect("migrate");
+    assert_eq!(migrated.format_version(), Some(2));
+    assert!(migrated.verify_id().is_ok());
+
+    let verifier = Verifier::with_verifier_config(VerifierConfig { auto_migrate: true, ..VerifierConfig::default() });
+    let result = verifier.verify_bundle(&v1);
+    assert!(result.success, "{:?}", result.error);
+    assert!(result.warnings.iter().any(|w| w.contains("format version 1 to 2")));
+}
+
+// ============================================================================
+// Gating Invariant Tests
+// ============================================================================
+
+#[test]
+fn test_dsl_parse_gating() {
+    let parsed = InvariantDSL::parse_invariant("invariant gate\ngating: true").expect("parse");
+    assert!(parsed.gating);
+    let parsed = InvariantDSL::parse_invariant("invariant diag").expect("parse");
+    assert!(!parsed.gating);
+}
+
+#[test]
+fn test_failed_gating_invariant_skips_diagnostics() {
+    let mut gate = behavioral_invariant("trace_present").with_gating(true);
+    gate.precondition = Some("trace.steps.len > 0".to_string());
+    let diagnostic = typed_invariant("econ", ProofType::EconomicInvariance);
+
+    let witness = ProofEngine::new()
+        .verify_invariants(
+            &causal_trace(vec![]),
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[diagnostic.clone(), gate.clone()],
+        )
+        .expect("verify");
+    assert_eq!(witness.counterexamples.len(), 1);
+    assert_eq!(witness.counterexamples[0].invariant_name, "trace_present");
+    assert_eq!(witness.not_evaluated, vec!["econ"]);
+
+    let bundle = ProofEngine::new().generate_bundle(&[witness]).expect("bundle");
+    let mut out = Vec::new();
+    let result = Verifier::new()
+        .write_result(&bundle, &mut out, OutputFormat::Summary)
+        .expect("write");
+    assert!(!result.success);
+    assert_eq!(result.witness_results[0].not_evaluated, vec!["econ"]);
+    assert!(String::from_utf8(out).unwrap().contains("not evaluated: econ"));
+
+    let passing = ProofEngine::new()
+        .verify_invariants(
+            &sample_trace(),
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[diagnostic, gate],
+        )
+        .expect("verify");
+    assert!(passing.counterexamples.is_empty());
+    assert!(passing.not_evaluated.is_empty());
+}
+
+// ============================================================================
+// Redundant Invariant Tests
+// ============================================================================
+
+#[test]
+fn test_find_redundant_reports_normalized_duplicates() {
+    let parse = |spec: &str| InvariantDSL::parse_invariant(spec).expect("parse");
+    let invariants = vec![
+        parse("invariant budget_ok\ntype: EconomicInvariance\npostcondition: spent <= budget"),
+        parse("invariant spend_cap\ntype: EconomicInvariance\npostcondition: spent  <=   budget"),
+        parse("invariant other_type\ntype: SemanticPreservation\npostcondition: spent <= budget"),
+        parse("invariant looser\ntype: EconomicInvariance\npostcondition: spent < budget"),
+        parse("invariant budget_ok\ntype: EconomicInvariance\npostcondition: spent <= budget"),
+    ];
+
+    assert_eq!(
+        InvariantDSL::find_redundant(&invariants).expect("find_redundant"),
+        vec![("budget_ok".to_string(), "spend_cap".to_string())]
+    );
+
+    // Any field that can change the verdict keeps invariants apart.
+    let named = |name: &str, mut inv: InvariantSpec| {
+        inv.name = name.to_string();
+        inv
+    };
+    let base = typed_invariant("base", ProofType::BehavioralSoundness);
+    let mut renamed = named("renamed", base.clone().with_remediation("retry later".to_string()));
+    renamed.description = "same checks".to_string();
+    let variants = vec![
+        base.clone(),
+        renamed,
+        named("gating", base.clone().with_gating(true)),
+        named("tolerant", base.clone().with_tolerance(0.5)),
+        named("limited", rate_limited(1, 10.0)),
+        named("discharged", base.with_external_discharge(fak::types::ExternalProof::new("kani", "verified"))),
+    ];
+    assert_eq!(
+        InvariantDSL::find_redundant(&variants).expect("find_redundant"),
+        vec![("base".to_string(), "renamed".to_string())]
+    );
+}
+
+// ============================================================================
+// Dedup Stats Tests
+// ============================================================================
+
+#[test]
+fn test_dedup_stats_counts_store_hits() {
+    let manager = ArtifactManager::new();
+    let shared = serde_json::json!({"b": 2, "a": 1});
+    for _ in 0..3 {
+        manager.store_artifact(&shared).expect("store");
+    }
+    let other_id = manager.store_artifact(&serde_json::json!("other")).expect("store");
+
+    let stats = manager.dedup_stats().expect("stats");
+    assert_eq!(stats.total_stores, 4);
+    assert_eq!(stats.unique_artifacts, 2);
+    assert_eq!(stats.duplicate_stores, 2);
+    assert_eq!(stats.bytes_saved, 2 * r#"{"a":1,"b":2}"#.len() as u64);
+
+    manager.remove_artifact(&other_id).expect("remove");
+    assert_eq!(manager.dedup_stats().expect("stats").total_stores, 3);
+}
+
+// ============================================================================
+// Check Error Tests
+// ============================================================================
+
+#[test]
+fn test_witness_result_separates_check_errors_from_violations() {
+    let ledger = CostLedger::new(
+        "cost-bad".to_string(),
+        vec![serde_json::json!({"cost": "free"})],
+        0.0,
+        serde_json::Map::new(),
+    );
+    let mut behavioral = behavioral_invariant("trace_present");
+    behavioral.precondition = Some("steps > 0".to_string());
+    let witness = ProofEngine::new()
+        .verify_invariants(
+            &causal_trace(vec![]),
+            &sample_capabilities(),
+            &ledger,
+            &sample_policy_ir(),
+            &[behavioral, typed_invariant("econ", ProofType::EconomicInvariance)],
+        )
+        .expect("verify");
+    assert!(witness.counterexamples[1].is_check_error());
+
+    let bundle = ProofEngine::new().generate_bundle(&[witness]).expect("bundle");
+    let result = &Verifier::new().verify_bundle(&bundle).witness_results[0];
+    assert!(!result.success);
+    assert_eq!(result.counterexample_count, 2);
+    assert_eq!(result.violation_count, 1);
+    assert_eq!(result.error_count, 1);
+}
+
+// ============================================================================
+// Salted Hash Tests
+// ============================================================================
+
+#[test]
+fn test_salted_managers_use_distinct_ids() {
+    let artifact = serde_json::json!({"policy": "shared"});
+    let plain = ArtifactManager::new();
+    let east = ArtifactManager::new().with_salt("deploy-east");
+    let west = ArtifactManager::new().with_salt(b"deploy-west".to_vec());
+
+    let plain_id = plain.store_artifact(&artifact).expect("store");
+    let east_id = east.store_artifact(&artifact).expect("store");
+    let west_id = west.store_artifact(&artifact).expect("store");
+
+    assert_eq!(plain_id, compute_content_hash(&artifact));
+    assert_eq!(plain_id, compute_salted_content_hash(&artifact, b""));
+    assert_eq!(east_id, compute_salted_content_hash(&artifact, b"deploy-east"));
+    assert_ne!(east_id, west_id);
+    assert_ne!(east_id, plain_id);
+
+    assert!(east.validate_artifact_integrity(&east_id, &artifact));
+    assert!(!west.validate_artifact_integrity(&east_id, &artifact));
+    assert!(east
+        .create_bundle(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir())
+        .is_ok());
+}
+
+#[test]
+fn test_salt_boundary_does_not_collide() {
+    // Without a length prefix both pairs would hash the bytes `a12`.
+    assert_ne!(
+        compute_salted_content_hash(&serde_json::json!(12), b"a"),
+        compute_salted_content_hash(&serde_json::json!(2), b"a1")
+    );
+    let short = ArtifactManager::new().with_salt("a");
+    let long = ArtifactManager::new().with_salt("a1");
+    assert_ne!(short.content_hash(&serde_json::json!(12)), long.content_hash(&serde_json::json!(2)));
+}
+
+// ============================================================================
+// Expression Function Tests
+// ============================================================================
+
+#[test]
+fn test_function_registry_builtins_and_custom() {
+    use fak::functions::FunctionRegistry;
+
+    let registry = EngineConfig::default().functions;
+    assert_eq!(registry.names().collect::<Vec<_>>(), vec!["abs", "len", "max", "min"]);
+    assert_eq!(registry.call("abs", &[serde_json::json!(-2.5)]).expect("call"), 2.5);
+    assert_eq!(registry.call("max", &[serde_json::json!(1), serde_json::json!(3)]).expect("call"), 3.0);
+    assert_eq!(registry.call("min", &[serde_json::json!(1), serde_json::json!(3)]).expect("call"), 1.0);
+    assert_eq!(registry.call("len", &[serde_json::json!([1, 2, 3])]).expect("call"), 3);
+    assert!(registry.call("abs", &[serde_json::json!("x")]).is_err());
+    assert!(registry.call("max", &[]).is_err());
+    assert!(registry.call("median", &[]).is_err());
+
+    let registry = FunctionRegistry::new().with_function("double", |args| {
+        Ok(serde_json::json!(args[0].as_f64().unwrap_or(0.0) * 2.0))
+    });
+    let config = EngineConfig { functions: registry, ..EngineConfig::default() };
+    assert_eq!(config.clone().functions.call("double", &[serde_json::json!(4)]).expect("call"), 8.0);
+}
+
+#[test]
+fn test_engine_postconditions_call_registered_functions() {
+    let functions = EngineConfig::default()
+        .functions
+        .with_function("double", |args| Ok(serde_json::json!(args[0].as_f64().unwrap_or(0.0) * 2.0)));
+    let engine = ProofEngine::with_config(EngineConfig { functions, ..EngineConfig::default() });
+    let check = |postcondition: &str| {
+        let mut inv = behavioral_invariant("calls");
+        inv.postcondition = Some(postcondition.to_string());
+        engine
+            .verify_invariants(&sample_trace(), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[inv])
+            .expect("verify")
+            .counterexamples
+    };
+
+    assert!(check("double(2) == 4 && abs(-1) == 1").is_empty());
+    assert!(!check("double(2) == 5").is_empty());
+    let unknown = check("triple(2) == 6");
+    assert_eq!(unknown.len(), 1);
+    assert!(unknown[0].is_check_error());
+    assert!(unknown[0].details.to_string().contains("unknown function"), "{}", unknown[0].details);
+}
+
+// ============================================================================
+// Bundle Chain Tests
+// ============================================================================
+
+#[test]
+fn test_bundle_chain_links_and_detects_breaks() {
+    use fak::verifier::BundleChain;
+
+    let engine = ProofEngine::new();
+    let genesis = sample_bundle();
+    let second = BundleChain::link(
+        engine
+            .generate_bundle(&[witness_with(&[typed_invariant("econ", ProofType::EconomicInvariance)])])
+            .expect("bundle"),
+        &genesis,
+    );
+    let third = BundleChain::link(
+        engine
+            .generate_bundle(&[witness_with(&[typed_invariant("auth", ProofType::AuthorityNonEscalation)])])
+            .expect("bundle"),
+        &second,
+    );
+    assert!(Verifier::new().verify_bundle(&third).success);
+
+    let chain = vec![genesis.clone(), second.clone(), third.clone()];
+    assert!(BundleChain::verify(&chain).is_ok());
+
+    let mut rewritten = second;
+    rewritten.metadata.insert("note".to_string(), serde_json::json!("edited"));
+    rewritten.id = rewritten.content_id();
+    let err = BundleChain::verify(&[genesis.clone(), rewritten, third]).unwrap_err();
+    assert!(err.to_string().contains("bundle 2"));
+
+    let err = BundleChain::verify(&[genesis.clone(), genesis]).unwrap_err();
+    assert!(err.to_string().contains("has no prev_id"));
+}
+
+// ============================================================================
+// Referenced Path Tests
+// ============================================================================
+
+#[test]
+fn test_referenced_paths_extracts_fields() {
+    let mut inv = typed_invariant("paths", ProofType::EconomicInvariance);
+    inv.precondition = Some("len(trace.steps[*].action) > 0 && enabled == true".to_string());
+    inv.postcondition = Some("cost_ledger.total_cost <= max(budget, 1.5) + cost_ledger.total_cost".to_string());
+    assert_eq!(
+        inv.referenced_paths(),
+        vec!["budget", "cost_ledger.total_cost", "enabled", "trace.steps[*].action"]
+    );
+
+    inv.precondition = Some("status == 'open'".to_string());
+    inv.postcondition = Some("spent @ budget".to_string());
+    assert_eq!(inv.referenced_paths(), vec!["status"]);
+    assert!(fak::expr::tokenize("spent @ budget").is_err());
+}
+
+// ============================================================================
+// Timestamp Tests
+// ============================================================================
+
+#[cfg(feature = "timestamping")]
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    match content.len() {
+        n if n < 0x80 => out.push(n as u8),
+        n if n < 0x100 => out.extend([0x81, n as u8]),
+        n => out.extend([0x82, (n >> 8) as u8, n as u8]),
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+#[cfg(feature = "timestamping")]
+fn der_seq(tag: u8, parts: &[Vec<u8>]) -> Vec<u8> {
+    der(tag, &parts.concat())
+}
+
+#[cfg(feature = "timestamping")]
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+#[cfg(feature = "timestamping")]
+const OID_ECDSA_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+
+#[cfg(feature = "timestamping")]
+fn tsa_name() -> Vec<u8> {
+    let cn = der_seq(0x30, &[der(0x06, &[0x55, 0x04, 0x03]), der(0x0c, b"Test TSA")]);
+    der_seq(0x30, &[der_seq(0x31, &[cn])])
+}
+
+#[cfg(feature = "timestamping")]
+fn tsa_key() -> p256::ecdsa::SigningKey {
+    p256::ecdsa::SigningKey::from_slice(&[7u8; 32]).unwrap()
+}
+
+/// Minimal TSA certificate for [`tsa_key`] with the given serial, valid 2020-2030.
+#[cfg(feature = "timestamping")]
+fn tsa_cert(serial: u8) -> Vec<u8> {
+    let ecdsa = der_seq(0x30, &[der(0x06, OID_ECDSA_SHA256)]);
+    let point = tsa_key().verifying_key().to_encoded_point(false);
+    let spki = der_seq(0x30, &[
+        der_seq(0x30, &[
+            der(0x06, &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01]),
+            der(0x06, &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07]),
+        ]),
+        der(0x03, &[&[0x00], point.as_bytes()].concat()),
+    ]);
+    let tbs = der_seq(0x30, &[
+        der_seq(0xa0, &[der(0x02, &[0x02])]),
+        der(0x02, &[serial]),
+        ecdsa.clone(),
+        tsa_name(),
+        der_seq(0x30, &[der(0x17, b"200101000000Z"), der(0x17, b"300101000000Z")]),
+        tsa_name(),
+        spki,
+    ]);
+    der_seq(0x30, &[tbs, ecdsa, der(0x03, &[0x00, 0x00])])
+}
+
+/// Timestamp response over `bundle_id`, signed with [`tsa_key`] and naming
+/// TSA cert `serial` as the signer.
+#[cfg(feature = "timestamping")]
+fn timestamp_response(bundle_id: &str, gen_time: &[u8], serial: u8) -> Vec<u8> {
+    use p256::ecdsa::signature::Signer;
+    use sha2::{Digest, Sha256};
+
+    let imprint: Vec<u8> = (0..bundle_id.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&bundle_id[i..i + 2], 16).unwrap())
+        .collect();
+    let sha256 = der_seq(0x30, &[der(0x06, OID_SHA256)]);
+    let tst_oid = der(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04]);
+    let tst_info = der_seq(0x30, &[
+        der(0x02, &[0x01]),
+        der(0x06, &[0x2a, 0x03, 0x04]),
+        der_seq(0x30, &[sha256.clone(), der(0x04, &imprint)]),
+        der(0x02, &[0x2a]),
+        der(0x18, gen_time),
+    ]);
+    let attr = |last: u8, value: Vec<u8>| {
+        der_seq(0x30, &[
+            der(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, last]),
+            der_seq(0x31, &[value]),
+        ])
+    };
+    let attrs = [
+        attr(0x03, tst_oid.clone()),
+        attr(0x04, der(0x04, &Sha256::digest(&tst_info))),
+    ];
+    let signature: p256::ecdsa::Signature = tsa_key().sign(&der_seq(0x31, &attrs));
+    let signer_info = der_seq(0x30, &[
+        der(0x02, &[0x01]),
+        der_seq(0x30, &[tsa_name(), der(0x02, &[serial])]),
+        sha256.clone(),
+        der_seq(0xa0, &attrs),
+        der_seq(0x30, &[der(0x06, OID_ECDSA_SHA256)]),
+        der(0x04, signature.to_der().as_bytes()),
+    ]);
+    let signed_data = der_seq(0x30, &[
+        der(0x02, &[0x03]),
+        der_seq(0x31, &[sha256]),
+        der_seq(0x30, &[tst_oid, der_seq(0xa0, &[der(0x04, &tst_info)])]),
+        der_seq(0x31, &[signer_info]),
+    ]);
+    let token = der_seq(0x30, &[
+        der(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02]),
+        der_seq(0xa0, &[signed_data]),
+    ]);
+    der_seq(0x30, &[der_seq(0x30, &[der(0x02, &[0x00])]), token])
+}
+
+#[cfg(feature = "timestamping")]
+#[test]
+fn test_timestamp_covers_bundle() {
+    let bundle = sample_bundle();
+    let tsr = timestamp_response(&bundle.id, b"20240101120000.5Z", 7);
+
+    let info = fak::timestamp::check_timestamp(&bundle, &tsr, &tsa_cert(7)).expect("timestamp");
+    assert_eq!(info.gen_time_secs, 1_704_110_400);
+    assert_eq!(info.policy, "1.2.3.4");
+    assert_eq!(info.serial, "2a");
+    assert!(Verifier::new().verify_timestamped_bundle(&bundle, &tsr, &tsa_cert(7)).success);
+}
+
+#[cfg(feature = "timestamping")]
+#[test]
+fn test_timestamp_rejects_mismatches() {
+    let bundle = sample_bundle();
+    let check = |tsr: &[u8], cert: &[u8]| {
+        let result = Verifier::new().verify_timestamped_bundle(&bundle, tsr, cert);
+        assert!(!result.success);
+        result.error.unwrap()
+    };
+
+    let other = "0".repeat(64);
+    assert!(check(&timestamp_response(&other, b"20240101120000Z", 7), &tsa_cert(7)).contains("does not cover"));
+    assert!(check(&timestamp_response(&bundle.id, b"20350101120000Z", 7), &tsa_cert(7)).contains("outside"));
+    assert!(check(&timestamp_response(&bundle.id, b"20240101120000Z", 7), &tsa_cert(8)).contains("not signed"));
+    assert!(check(b"garbage", &tsa_cert(7)).contains("timestamp"));
+}
+
+#[cfg(feature = "timestamping")]
+#[test]
+fn test_timestamp_rejects_tampered_signature() {
+    let bundle = sample_bundle();
+    // The signature value is the last element of the response.
+    let mut tsr = timestamp_response(&bundle.id, b"20240101120000Z", 7);
+    *tsr.last_mut().unwrap() ^= 0x01;
+
+    let err = fak::timestamp::check_timestamp(&bundle, &tsr, &tsa_cert(7)).unwrap_err();
+    assert!(err.to_string().contains("signature does not verify"), "{}", err);
+    assert!(!Verifier::new().verify_timestamped_bundle(&bundle, &tsr, &tsa_cert(7)).success);
+}
+
+// ============================================================================
+// Rate Limit Tests
+// ============================================================================
+
+fn rate_limited(limit: usize, window: f64) -> InvariantSpec {
+    behavioral_invariant("deploy_rate")
+        .with_rate_limit(RateLimit::new("ts", "action", serde_json::json!("deploy"), limit, window))
+}
+
+fn actions_at(events: &[(&str, f64)]) -> ExecutionTrace {
+    causal_trace(
+        events
+            .iter()
+            .map(|(action, ts)| serde_json::json!({"action": action, "ts": ts}))
+            .collect(),
+    )
+}
+
+fn check_rate(trace: &ExecutionTrace, inv: InvariantSpec) -> fak::FakResult<Vec<CounterExample>> {
+    ProofEngine::new()
+        .verify_invariants(
+            trace,
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &[inv],
+        )
+        .map(|w| w.counterexamples)
+}
+
+#[test]
+fn test_rate_limit_within_limit_passes() {
+    // Three deploys, but never more than two within any 60s window; the
+    // unrelated reads do not count.
+    let trace = actions_at(&[("deploy", 0.0), ("read", 1.0), ("read", 2.0), ("deploy", 30.0), ("deploy", 60.0)]);
+    assert!(check_rate(&trace, rate_limited(2, 60.0)).expect("check").is_empty());
+}
+
+#[test]
+fn test_rate_limit_reports_first_offending_window() {
+    let trace = actions_at(&[("deploy", 0.0), ("deploy", 70.0), ("read", 75.0), ("deploy", 100.0), ("deploy", 120.0)]);
+    let counterexamples = check_rate(&trace, rate_limited(2, 60.0)).expect("check");
+
+    assert_eq!(counterexamples.len(), 1);
+    let ce = &counterexamples[0];
+    assert_eq!(ce.error_type, "rate_limit_exceeded");
+    assert_eq!(ce.step_index, Some(4));
+    assert_eq!(ce.details["window_start"], 70.0);
+    assert_eq!(ce.details["window_end"], 130.0);
+    assert_eq!(ce.details["count"], 3);
+    assert_eq!(ce.details["limit"], 2);
+    assert_eq!(ce.details["steps"], serde_json::json!([1, 3, 4]));
+}
+
+#[test]
+fn test_rate_limit_rejects_bad_input() {
+    let trace = causal_trace(vec![serde_json::json!({"action": "deploy"})]);
+    let counterexamples = check_rate(&trace, rate_limited(1, 60.0)).expect("check");
+    assert!(counterexamples[0].is_check_error());
+    assert!(rate_limited(1, 0.0).validate().is_err());
+}
+
+// ============================================================================
+// Verifier Pipeline Tests
+// ============================================================================
+
+fn failing_step(message: &'static str) -> impl Fn(&fak::ProofBundle) -> BundleResult {
+    move |bundle| {
+        let mut result = Verifier::new().verify_bundle(bundle);
+        result.success = false;
+        result.error = Some(message.to_string());
+        result
+    }
+}
+
+#[test]
+fn test_pipeline_merges_passing_steps() {
+    let bundle = sample_bundle();
+    let result = VerifierPipeline::new()
+        .with_verifier("structural", Verifier::new())
+        .with_optional_step("signature", failing_step("no signature"))
+        .with_step("profile", |b| Verifier::new().verify_bundle(b))
+        .verify(&bundle);
+
+    assert!(result.success);
+    assert_eq!(result.bundle_id, bundle.id);
+    assert_eq!(result.witness_results.len(), bundle.witnesses.len());
+    assert!(result.witness_results.iter().all(|w| w.success));
+    assert_eq!(result.warnings, vec!["optional step 'signature' failed: no signature"]);
+}
+
+#[test]
+fn test_pipeline_stops_at_mandatory_failure() {
+    let ran = Arc::new(Mutex::new(false));
+    let ran_in_step = Arc::clone(&ran);
+    let result = VerifierPipeline::new()
+        .with_step("signature", failing_step("bad signature"))
+        .with_step("profile", move |b| {
+            *ran_in_step.lock().unwrap() = true;
+            Verifier::new().verify_bundle(b)
+        })
+        .verify(&sample_bundle());
+
+    assert!(!result.success);
+    assert_eq!(result.error.as_deref(), Some("signature: bad signature"));
+    assert!(!*ran.lock().unwrap());
+}
+
+// ============================================================================
+// Empty Invariant Set Tests
+// ============================================================================
+
+#[test]
+fn test_require_nonempty_invariants() {
+    // create_bundle records witnesses without invariants.
+    let bundle = sample_bundle();
+    assert!(bundle.witnesses.iter().all(|w| w.invariants.is_empty()));
+    assert!(Verifier::new().verify_bundle(&bundle).success);
+
+    let strict = Verifier::with_verifier_config(VerifierConfig {
+        require_nonempty_invariants: true,
+        ..VerifierConfig::default()
+    });
+    let result = strict.verify_bundle(&bundle);
+    assert!(!result.success);
+    assert!(result.witness_results[0].error.as_deref().unwrap().contains("no invariants"));
+}
+
+// ============================================================================
+// Timestamp Normalization Tests
+// ============================================================================
+
+#[test]
+fn test_canonical_timestamp_forms() {
+    use fak::metadata::canonical_timestamp;
+    let canonical = "2024-03-01T12:30:00.250Z";
+    for input in [
+        serde_json::json!(1_709_296_200_250_i64),
+        serde_json::json!("2024-03-01T12:30:00.25Z"),
+        serde_json::json!("2024-03-01T14:30:00.250999+02:00"),
+        serde_json::json!("2024-03-01t07:00:00.250-05:30"),
+    ] {
+        assert_eq!(canonical_timestamp(&input).expect("canonical"), canonical, "{}", input);
+    }
+    assert_eq!(canonical_timestamp(&serde_json::json!(0)).expect("canonical"), "1970-01-01T00:00:00.000Z");
+    assert!(canonical_timestamp(&serde_json::json!("2024-02-30T00:00:00Z")).is_err());
+    assert!(canonical_timestamp(&serde_json::json!("yesterday")).is_err());
+    assert!(canonical_timestamp(&serde_json::json!(1.5)).is_err());
+}
+
+#[test]
+fn test_timestamp_normalization_makes_bundles_hash_alike() {
+    let bundle_with = |created: serde_json::Value| {
+        let mut trace = sample_trace();
+        trace.metadata.insert("created_at".to_string(), created);
+        ArtifactManager::new()
+            .with_timestamp_keys(vec!["created_at".to_string()])
+            .create_bundle(&trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir())
+    };
+
+    let from_millis = bundle_with(serde_json::json!(1_709_296_200_000_i64)).expect("bundle");
+    let from_rfc3339 = bundle_with(serde_json::json!("2024-03-01T13:30:00+01:00")).expect("bundle");
+    assert_eq!(from_millis.id, from_rfc3339.id);
+    assert_eq!(
+        from_millis.witnesses[0].execution_trace.metadata["created_at"],
+        "2024-03-01T12:30:00.000Z"
+    );
+
+    let err = bundle_with(serde_json::json!("soon")).unwrap_err();
+    assert!(matches!(err, FakError::Validation { field, .. } if field == "created_at"));
+}
+
+#[test]
+fn test_timestamp_normalization_applies_to_store_and_verification() {
+    let keys = vec!["created_at".to_string()];
+    let (millis, rfc3339) = (serde_json::json!(1_709_296_200_000_i64), serde_json::json!("2024-03-01T13:30:00+01:00"));
+    let with_created = |created: &serde_json::Value| {
+        let mut trace = sample_trace();
+        trace.metadata.insert("created_at".to_string(), created.clone());
+        trace
+    };
+
+    let manager = ArtifactManager::new().with_timestamp_keys(keys.clone());
+    let id = manager.store_artifact(&serde_json::to_value(with_created(&millis)).expect("to_value")).expect("store");
+    assert_eq!(id, manager.store_artifact(&serde_json::to_value(with_created(&rfc3339)).expect("to_value")).expect("store"));
+
+    let config = EngineConfig { timestamp_keys: keys, ..EngineConfig::default() };
+    let engine = ProofEngine::with_config(config.clone());
+    let mut witness = engine
+        .verify_invariants(&with_created(&millis), &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[])
+        .expect("verify");
+    witness.execution_trace = with_created(&rfc3339);
+    let keyed = Verifier::with_config(config);
+    assert!(keyed.verify_witness(&witness).success);
+    assert!(!Verifier::new().verify_witness(&witness).success);
+
+    let mut metadata = serde_json::Map::new();
+    metadata.insert("created_at".to_string(), millis.clone());
+    let witnesses = vec![witness];
+    let input = fak::ProofBundle::new(fak::ProofBundle::compute_id(&witnesses, &metadata), witnesses, metadata);
+    let mut merged = engine.merge_bundles(&[input]).expect("merge");
+    assert_eq!(merged.metadata["created_at"], "2024-03-01T12:30:00.000Z");
+    merged.metadata.insert("created_at".to_string(), millis);
+    assert!(keyed.verify_bundle(&merged).success);
+    assert!(Verifier::new().verify_bundle(&merged).error.expect("error").contains("Bundle ID mismatch"));
+}
+
+// ============================================================================
+// Protobuf Tests
+// ============================================================================
+
+#[cfg(feature = "proto")]
+#[test]
+fn test_bundle_result_to_proto_round_trips() {
+    use prost::Message;
+
+    let mut result = Verifier::new().verify_bundle(&sample_bundle());
+    result.warnings.push("pinned config differs".to_string());
+    result.witness_results[0].remediations.insert("inv".to_string(), "fix it".to_string());
+
+    let message = result.to_proto();
+    assert_eq!(message.bundle_id, result.bundle_id);
+    assert_eq!(message.witness_results.len(), result.witness_results.len());
+    assert_eq!(message.witness_results[0].remediations["inv"], "fix it");
+    assert_eq!(message.error, None);
+
+    let decoded = fak::proto::BundleResult::decode(message.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded, message);
+}
+
+#[cfg(feature = "proto")]
+#[test]
+fn test_counterexample_to_proto() {
+    let ce = CounterExample {
+        invariant_name: "rate".to_string(),
+        error_type: "rate_limit_exceeded".to_string(),
+        details: serde_json::json!({"limit": 2, "count": 3}),
+        step_index: Some(4),
+    };
+    let message = ce.to_proto();
+    assert_eq!(message.details_json, r#"{"count":3,"limit":2}"#);
+    assert_eq!(message.step_index, Some(4));
+}
+
+// ============================================================================
+// Bundle Budget Tests
+// ============================================================================
+
+#[test]
+fn test_bundle_budget_marks_remaining_witnesses_not_evaluated() {
+    let bundle = ProofEngine::new()
+        .generate_bundle(&[
+            witness_with(&[typed_invariant("a", ProofType::EconomicInvariance)]),
+            witness_with(&[typed_invariant("b", ProofType::EconomicInvariance)]),
+            witness_with(&[typed_invariant("c", ProofType::EconomicInvariance)]),
+        ])
+        .expect("bundle");
+    // Each witness takes at least 50ms, so the budget runs out after the first.
+    let slow = ValidationHooks::new().on_trace(|_| {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        Ok(())
+    });
+    let config = VerifierConfig {
+        engine: EngineConfig { hooks: slow, ..EngineConfig::default() },
+        bundle_timeout_secs: Some(0.02),
+        ..VerifierConfig::default()
+    };
+
+    let result = Verifier::with_verifier_config(config).verify_bundle(&bundle);
+    assert!(!result.success);
+    assert!(result.witness_results[0].success);
+    for wr in &result.witness_results[1..] {
+        assert!(!wr.success);
+        assert_eq!(wr.not_evaluated.len(), 1);
+    }
+    assert!(result.error.unwrap().contains("budget of 0.02s exhausted after 1 of 3 witnesses"));
+
+    assert!(Verifier::new().verify_bundle(&bundle).success);
+}
+
+// ============================================================================
+// External Discharge Tests
+// ============================================================================
+
+#[test]
+fn test_externally_discharged_invariant_is_not_rechecked() {
+    // An empty trace fails this invariant's built-in check.
+    let mut inv = behavioral_invariant("solver_proved")
+        .with_external_discharge(ExternalProof::new("z3 4.12", "unsat"));
+    inv.precondition = Some("trace.steps > 0".to_string());
+    let empty = causal_trace(vec![]);
+    let engine = ProofEngine::new();
+
+    let witness = engine
+        .verify_invariants(&empty, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[inv.clone()])
+        .expect("verify");
+    assert!(witness.counterexamples.is_empty());
+    assert_eq!(witness.invariants[0].external_discharge.as_ref().unwrap().tool, "z3 4.12");
+
+    let bundle = engine.generate_bundle(&[witness]).expect("bundle");
+    let mut out = Vec::new();
+    let result = Verifier::new().write_result(&bundle, &mut out, OutputFormat::Summary).expect("write");
+    assert!(result.success);
+    assert_eq!(result.witness_results[0].externally_verified["solver_proved"].result, "unsat");
+    assert!(String::from_utf8(out).unwrap().contains("solver_proved externally verified by z3 4.12: unsat"));
+
+    inv.external_discharge = None;
+    let witness = engine
+        .verify_invariants(&empty, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[inv])
+        .expect("verify");
+    assert_eq!(witness.counterexamples.len(), 1);
+}
+
+#[test]
+fn test_external_discharge_requires_proving_result() {
+    let mut inv = behavioral_invariant("solver_unknown")
+        .with_external_discharge(ExternalProof::new("z3 4.12", "unknown"));
+    inv.precondition = Some("trace.steps > 0".to_string());
+    let empty = causal_trace(vec![]);
+    let engine = ProofEngine::new();
+    let verify = |inv: &InvariantSpec| {
+        engine
+            .verify_invariants(&empty, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), std::slice::from_ref(inv))
+            .expect("verify")
+    };
+
+    let witness = verify(&inv);
+    assert_eq!(witness.counterexamples.len(), 1);
+    let bundle = engine.generate_bundle(&[witness]).expect("bundle");
+    let result = Verifier::new().verify_bundle(&bundle);
+    assert!(result.witness_results[0].externally_verified.is_empty());
+
+    inv.external_discharge = Some(ExternalProof::new("z3 4.12", " UNSAT "));
+    let bundle = engine.generate_bundle(&[verify(&inv)]).expect("bundle");
+    assert!(Verifier::new().verify_bundle(&bundle).success);
+    let strict = Verifier::with_verifier_config(VerifierConfig {
+        reject_external_discharge: true,
+        ..VerifierConfig::default()
+    });
+    let result = strict.verify_bundle(&bundle);
+    assert!(!result.success);
+    let error = result.witness_results[0].error.as_deref().expect("error");
+    assert!(error.contains("solver_unknown"), "{error}");
+}
+
+// ============================================================================
+// Expression Evaluation Tests
+// ============================================================================
+
+fn check_postcondition(postcondition: &str, budget: f64) -> Vec<CounterExample> {
+    let mut ledger = sample_cost_ledger();
+    ledger.metadata.insert("budget".to_string(), serde_json::json!(budget));
+    let mut inv = typed_invariant("budget_ok", ProofType::EconomicInvariance);
+    inv.postcondition = Some(postcondition.to_string());
+    ProofEngine::new()
+        .verify_invariants(&sample_trace(), &sample_capabilities(), &ledger, &sample_policy_ir(), &[inv])
+        .expect("verify")
+        .counterexamples
+}
+
+#[test]
+fn test_economic_postcondition_is_evaluated() {
+    assert!(check_postcondition("total_cost <= budget", 0.01).is_empty());
+    assert!(check_postcondition("entry_count == 1 && total_cost * 10 < budget", 0.1).is_empty());
+
+    let failed = check_postcondition("total_cost <= budget", 0.0005);
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].error_type, "condition_failed");
+    assert_eq!(failed[0].details["values"]["budget"], 0.0005);
+
+    let unresolved = check_postcondition("total_cost <= allowance", 1.0);
+    assert_eq!(unresolved[0].error_type, "unresolved_identifier");
+    assert_eq!(unresolved[0].details["identifier"], "allowance");
+    assert!(unresolved[0].is_check_error());
+
+    let mut inv = typed_invariant("budget_ok", ProofType::EconomicInvariance);
+    inv.postcondition = Some("total_cost <= allowance".to_string());
+    let result = Verifier::new().verify_witness(&witness_with(&[inv]));
+    assert_eq!((result.error_count, result.violation_count), (1, 0));
+}
+
+#[test]
+fn test_evaluate_precedence_and_errors() {
+    use fak::expr::evaluate;
+    use fak::functions::FunctionRegistry;
+
+    let functions = FunctionRegistry::new();
+    let vars = |name: &str| match name {
+        "spent" => Some(serde_json::json!(12)),
+        "status" => Some(serde_json::json!("open")),
+        _ => None,
+    };
+    let eval = |input: &str| evaluate(input, &vars, &functions, 0.0);
+
+    assert_eq!(eval("2 + 3 * 4 - -1").unwrap(), 15.0);
+    assert_eq!(eval("(2 + 3) * 4 / 2").unwrap(), 10.0);
+    assert_eq!(eval("!(spent > 10) || status == 'open'").unwrap(), true);
+    assert_eq!(eval("max(spent, 20) != 20").unwrap(), false);
+    assert_eq!(evaluate("spent <= 11", &vars, &functions, 1.0).unwrap(), true);
+
+    // At the equality boundary tolerance widens ==/>=/<= but not </>.
+    let loose = |input: &str| evaluate(input, &vars, &functions, 0.5).unwrap();
+    assert_eq!(loose("spent == 12.4"), true);
+    assert_eq!(loose("spent != 12.4"), false);
+    assert_eq!(loose("spent >= 12.4"), true);
+    assert_eq!(loose("spent <= 11.6"), true);
+    assert_eq!(loose("spent > 12"), false);
+    assert_eq!(loose("spent < 12"), false);
+    assert_eq!(loose("spent > 12.4"), false);
+    assert_eq!(loose("spent < 11.6"), false);
+
+    assert!(eval("1 < 2 < 3").is_err());
+    assert!(eval("spent / 0").is_err());
+    assert!(eval("status > 1").is_err());
+    assert!(eval("missing + 1").is_err());
+    assert!(eval("(1 + 2").is_err());
+}
+
+#[test]
+fn test_parse_expr_builds_tree() {
+    use fak::expr::{parse_expr, Expr};
+
+    let ident = |name: &str| Box::new(Expr::Ident(name.to_string()));
+    let expected = Expr::And(
+        Box::new(Expr::Comparison { op: ">", left: ident("budget"), right: Box::new(Expr::Literal(serde_json::json!(0.0))) }),
+        Box::new(Expr::Comparison { op: "<=", left: ident("spent"), right: ident("budget") }),
+    );
+    let parsed = parse_expr("budget > 0 && spent <= budget").expect("parse");
+    assert_eq!(parsed, expected);
+    assert_eq!(parsed.to_string(), "budget > 0.0 && spent <= budget");
+    assert!(matches!(parse_expr("budget >"), Err(FakError::ParseError { .. })));
+}
+
+#[test]
+fn test_invariant_precondition_compiled_once() {
+    let mut inv = behavioral_invariant("compiled");
+    inv.precondition = Some("budget > 0".to_string());
+    let first = inv.compiled_precondition().expect("parse").expect("present").into_owned();
+    assert_eq!(first, fak::expr::parse_expr("budget > 0").unwrap());
+    assert_eq!(inv.clone(), inv);
+
+    inv.precondition = Some("spent < 1".to_string());
+    let changed = inv.compiled_precondition().expect("parse").expect("present");
+    assert_eq!(changed.to_string(), "spent < 1.0");
+
+    inv.precondition = Some("spent <".to_string());
+    assert!(inv.validate().is_err());
+}
+
+#[test]
+fn test_invariant_postcondition_compiled_once() {
+    let mut inv = behavioral_invariant("compiled");
+    inv.postcondition = Some("spent <= budget".to_string());
+    let first = inv.compiled_postcondition().expect("parse").expect("present");
+    assert_eq!(first.to_string(), "spent <= budget");
+    assert!(matches!(first, std::borrow::Cow::Borrowed(_)));
+    assert!(matches!(inv.compiled_postcondition().expect("parse"), Some(std::borrow::Cow::Borrowed(_))));
+
+    inv.postcondition = Some("spent < 1".to_string());
+    let changed = inv.compiled_postcondition().expect("parse").expect("present");
+    assert_eq!(changed.to_string(), "spent < 1.0");
+}
+
+// ============================================================================
+// Owned Context Tests
+// ============================================================================
+
+#[test]
+fn test_owned_context_verifies_like_borrowed() {
+    let owned: OwnedVerificationContext =
+        (sample_trace(), sample_capabilities(), sample_cost_ledger(), sample_policy_ir()).into();
+    let invariants = [typed_invariant("econ", ProofType::EconomicInvariance)];
+    let engine = ProofEngine::new();
+
+    let from_owned = engine.verify_invariants_ctx(&owned, &invariants).expect("verify");
+    let borrowed = owned.as_ref();
+    let from_borrowed = engine.verify_invariants_ctx(borrowed, &invariants).expect("verify");
+    let direct = engine
+        .verify_invariants(&owned.trace, &owned.capabilities, &owned.cost_ledger, &owned.policy_ir, &invariants)
+        .expect("verify");
+
+    assert_eq!(from_owned, direct);
+    assert_eq!(from_borrowed, direct);
+    assert_eq!(OwnedVerificationContext::from(borrowed), owned);
+}
+
+// ============================================================================
+// Expression Context Tests
+// ============================================================================
+
+#[test]
+fn test_context_resolves_namespaced_names() {
+    use fak::expr::ExpressionContext;
+
+    let (trace, caps, ledger, policy) =
+        (sample_trace(), sample_capabilities(), sample_cost_ledger(), sample_policy_ir());
+    let ctx = VerificationContext::new(&trace, &caps, &ledger, &policy);
+
+    assert_eq!(ctx.resolve("trace.steps.len").unwrap(), Some(serde_json::json!(trace.steps.len())));
+    assert_eq!(ctx.resolve("cost.total_cost").unwrap(), Some(serde_json::json!(0.001)));
+    assert_eq!(ctx.resolve("caps.capabilities.len").unwrap(), Some(serde_json::json!(caps.capabilities.len())));
+    assert_eq!(ctx.resolve("cost.entries[0].op").unwrap(), Some(serde_json::json!("inference")));
+    assert_eq!(ctx.resolve("cost.entries[*].cost").unwrap(), Some(serde_json::json!([0.001])));
+    assert_eq!(ctx.resolve("policy.metadata.missing").unwrap(), None);
+    assert!(matches!(ctx.resolve("ledger.total_cost"), Err(FakError::ParseError { .. })));
+    assert!(matches!(ctx.resolve("total_cost"), Err(FakError::ParseError { .. })));
+}
+
+#[test]
+fn test_cross_artifact_conditions() {
+    let engine = ProofEngine::new();
+    let (trace, caps, ledger, policy) =
+        (sample_trace(), sample_capabilities(), sample_cost_ledger(), sample_policy_ir());
+    let ctx = VerificationContext::new(&trace, &caps, &ledger, &policy);
+    let check = |inv: InvariantSpec| engine.check_one(&ctx, &inv).expect("check");
+
+    // Cost per trace step stays below a limit derived from the capabilities.
+    let mut per_step = typed_invariant("per_step", ProofType::AuthorityNonEscalation);
+    per_step.postcondition = Some("cost.total_cost / trace.steps.len < caps.capabilities.len".to_string());
+    assert!(check(per_step.clone()).is_none());
+
+    per_step.postcondition = Some("cost.total_cost / trace.steps.len > caps.capabilities.len".to_string());
+    assert_eq!(check(per_step.clone()).unwrap().error_type, "condition_failed");
+
+    // A false precondition makes the invariant vacuously hold.
+    per_step.precondition = Some("policy.id == 'other'".to_string());
+    assert!(check(per_step).is_none());
+
+    let mut bare = typed_invariant("bare", ProofType::SemanticPreservation);
+    bare.postcondition = Some("total_cost >= 0".to_string());
+    assert!(matches!(engine.check_one(&ctx, &bare), Err(FakError::ParseError { .. })));
+}
+
+// ============================================================================
+// File Artifact Store Tests
+// ============================================================================
+
+#[test]
+fn test_store_detects_id_collision() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let root = tmp.path();
+    let store = FileArtifactStore::new(root).expect("open");
+    let manager = ArtifactManager::new().with_store(store.clone());
+    let artifact = serde_json::json!({"policy": "original"});
+    let artifact_id = manager.store_artifact(&artifact).expect("store");
+    assert_eq!(manager.store_artifact(&artifact).expect("re-store"), artifact_id);
+
+    store.clear().unwrap();
+    store.put(&artifact_id, &serde_json::json!({"policy": "forged"})).unwrap();
+    assert!(matches!(
+        manager.store_artifact(&artifact),
+        Err(FakError::IntegrityFailure { artifact_id: id, .. }) if id == artifact_id
+    ));
+    assert_eq!(store.get(&artifact_id).unwrap(), Some(serde_json::json!({"policy": "forged"})));
+}
+
+#[test]
+fn test_file_store_persists_across_managers() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let root = tmp.path();
+    let artifact = serde_json::json!({"policy": "persisted", "rules": [1, 2]});
+
+    let writer = ArtifactManager::new().with_store(FileArtifactStore::new(root).expect("open"));
+    let artifact_id = writer.store_artifact(&artifact).expect("store");
+    assert!(root.join(format!("{}.json", artifact_id)).exists());
+    drop(writer);
+
+    let reader = ArtifactManager::new().with_store(FileArtifactStore::new(root).expect("reopen"));
+    assert!(reader.contains(&artifact_id).unwrap());
+    let restored = reader.retrieve_artifact(&artifact_id).expect("retrieve");
+    assert_eq!(restored, artifact);
+    assert!(reader.validate_artifact_integrity(&artifact_id, &restored));
+    assert_eq!(reader.snapshot().unwrap().len(), 1);
+    assert!(matches!(
+        reader.retrieve_artifact("../escape"),
+        Err(FakError::Validation { .. })
+    ));
+
+    reader.clear().expect("clear");
+    assert!(!reader.contains(&artifact_id).unwrap());
+}
+
+// ============================================================================
+// Volatile Key Tests
+// ============================================================================
+
+#[test]
+fn test_volatile_keys_share_identity_but_keep_integrity() {
+    use fak::artifacts::MetadataConflict;
+
+    let first = serde_json::json!({"policy": "p", "metadata": {"captured_at": 1, "source": "a"}});
+    let later = serde_json::json!({"policy": "p", "metadata": {"captured_at": 2, "source": "a"}});
+    let other = serde_json::json!({"policy": "p", "metadata": {"captured_at": 1, "source": "b"}});
+
+    let manager = ArtifactManager::new().with_volatile_keys(vec!["metadata.captured_at".to_string()]);
+    let id = manager.store_artifact(&first).expect("store");
+    assert_eq!(manager.store_artifact(&later).expect("store"), id);
+    assert_ne!(manager.store_artifact(&other).expect("store"), id);
+    assert_eq!(manager.retrieve_artifact(&id).unwrap(), first);
+    assert_eq!(manager.dedup_stats().unwrap().duplicate_stores, 1);
+    assert_eq!(manager.recorded_full_hash(&id).unwrap(), Some(manager.full_content_hash(&first)));
+
+    assert!(manager.validate_artifact_integrity(&id, &first));
+    assert!(!manager.validate_artifact_integrity(&id, &later));
+
+    let strict = ArtifactManager::new()
+        .with_volatile_keys(vec!["metadata.captured_at".to_string()])
+        .on_metadata_conflict(MetadataConflict::Reject);
+    strict.store_artifact(&first).expect("store");
+    assert!(matches!(strict.store_artifact(&later), Err(FakError::IntegrityFailure { .. })));
+    assert!(strict.store_artifact(&first).is_ok());
+}
+
+#[test]
+fn test_failed_store_records_no_full_hash() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let store = FileArtifactStore::new(tmp.path()).expect("open");
+    let manager = ArtifactManager::new()
+        .with_store(store.clone())
+        .with_volatile_keys(vec!["metadata.captured_at".to_string()]);
+    let artifact = serde_json::json!({"policy": "p", "metadata": {"captured_at": 1}});
+    let artifact_id = manager.content_hash(&artifact);
+
+    store.put(&artifact_id, &serde_json::json!({"policy": "forged"})).expect("forge");
+    assert!(matches!(manager.store_artifact(&artifact), Err(FakError::IntegrityFailure { .. })));
+    assert_eq!(manager.recorded_full_hash(&artifact_id).expect("recorded"), None);
+}
+
+// ============================================================================
+// Memory Limit Tests
+// ============================================================================
+
+#[test]
+fn test_memory_limited_verification() {
+    let steps = (0..2000).map(|i| serde_json::json!({"step": i, "action": "write"})).collect();
+    let trace = ExecutionTrace::new("trace-big".to_string(), steps, serde_json::Map::new());
+    let bundle = ArtifactManager::new()
+        .create_bundle(&trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir())
+        .expect("bundle");
+    let verifier = Verifier::new();
+
+    assert!(fak::sandbox::allocated_bytes().is_some());
+    let result = verifier
+        .verify_bundle_with_memory_limit(&bundle, 1 << 30)
+        .expect("within limit");
+    assert!(result.success);
+
+    let err = verifier.verify_bundle_with_memory_limit(&bundle, 1).unwrap_err();
+    assert!(matches!(err, FakError::ResourceLimit { ref resource, limit: 1, .. } if resource == "memory"));
+}
+
+// ============================================================================
+// Streaming Hash Tests
+// ============================================================================
+
+fn arb_json() -> impl proptest::strategy::Strategy<Value = serde_json::Value> {
+    use proptest::prelude::*;
+
+    let leaf = prop_oneof![
+        Just(serde_json::Value::Null),
+        any::<bool>().prop_map(serde_json::Value::from),
+        any::<i64>().prop_map(serde_json::Value::from),
+        any::<f64>().prop_filter("finite", |f| f.is_finite()).prop_map(serde_json::Value::from),
+        ".*".prop_map(serde_json::Value::from),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            proptest::collection::vec(inner.clone(), 0..8).prop_map(serde_json::Value::Array),
+            proptest::collection::hash_map(".*", inner, 0..8)
+                .prop_map(|map| serde_json::Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+proptest::proptest! {
+    #[test]
+    fn test_streaming_hash_matches_content_hash(value in arb_json()) {
+        proptest::prop_assert_eq!(compute_content_hash_streaming(&value), compute_content_hash(&value));
+    }
+}
+
+// ============================================================================
+// Hash Algorithm Tests
+// ============================================================================
+
+#[test]
+fn test_content_hash_with_each_algorithm_is_deterministic() {
+    let artifact = serde_json::json!({"b": [1, 2], "a": "x"});
+    let reordered = serde_json::json!({"a": "x", "b": [1, 2]});
+    let mut ids = std::collections::HashSet::new();
+    for (algo, hex_len) in [(HashAlgo::Sha256, 64), (HashAlgo::Sha512, 128), (HashAlgo::Blake3, 64)] {
+        let id = compute_content_hash_with(&artifact, algo);
+        assert_eq!(id, compute_content_hash_with(&reordered, algo));
+        let (tag, hex) = id.split_once(':').expect("tagged");
+        assert_eq!(tag, algo.tag());
+        assert_eq!(hex.len(), hex_len);
+        assert_eq!(HashAlgo::from_tagged(&id), Some(algo));
+        assert!(ids.insert(id));
+    }
+    assert_eq!(
+        compute_content_hash_with(&artifact, HashAlgo::Sha256),
+        format!("sha256:{}", compute_content_hash(&artifact))
+    );
+    assert_eq!(HashAlgo::from_tagged(&compute_content_hash(&artifact)), None);
+}
+
+#[test]
+fn test_artifact_manager_honors_id_tags() {
+    let artifact = serde_json::json!({"kind": "trace", "steps": [1]});
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let dir = tmp.path();
+    let store = FileArtifactStore::new(dir).expect("store");
+    let manager = ArtifactManager::new().with_store(store.clone()).with_hash_algo(HashAlgo::Blake3);
+
+    let id = manager.store_artifact(&artifact).expect("store");
+    assert!(id.starts_with("blake3:"));
+    assert_eq!(manager.retrieve_artifact(&id).expect("retrieve"), artifact);
+    assert!(manager.validate_artifact_integrity(&id, &artifact));
+
+    // IDs from before the algorithm changed still validate under their own tag.
+    let legacy = compute_content_hash(&artifact);
+    assert!(manager.validate_artifact_integrity(&legacy, &artifact));
+    let sha512 = compute_content_hash_with(&artifact, HashAlgo::Sha512);
+    assert!(manager.validate_artifact_integrity(&sha512, &artifact));
+    assert!(!manager.validate_artifact_integrity(&sha512, &serde_json::json!({"kind": "other"})));
+    assert!(!manager.validate_artifact_integrity(&format!("md5:{}", legacy), &artifact));
+
+    let reopened = ArtifactManager::new().with_store(FileArtifactStore::new(dir).expect("store"));
+    assert!(reopened.contains(&id).expect("contains"));
+    assert_eq!(store.ids().expect("ids"), vec![id.clone()]);
+}
+
+// ============================================================================
+// Permitted Grant Tests
+// ============================================================================
+
+#[test]
+fn test_grants_must_be_permitted_by_policy() {
+    let engine = ProofEngine::new();
+    let invariant = typed_invariant("grants", ProofType::AuthorityNonEscalation);
+    let (trace, ledger) = (sample_trace(), sample_cost_ledger());
+    let mut caps = sample_capabilities();
+    caps.capabilities.push("delete".to_string());
+    let mut policy = sample_policy_ir();
+
+    let ctx = VerificationContext::new(&trace, &caps, &ledger, &policy);
+    assert_eq!(engine.check_one(&ctx, &invariant).unwrap(), None);
+
+    policy.ast.insert(
+        PolicyIR::PERMITTED_GRANTS_KEY.to_string(),
+        serde_json::json!({"agent-001": ["read", "write"]}),
+    );
+    let ctx = VerificationContext::new(&trace, &caps, &ledger, &policy);
+    let counterexample = engine.check_one(&ctx, &invariant).unwrap().expect("violation");
+    assert_eq!(counterexample.error_type, "unauthorized_grant");
+    assert_eq!(counterexample.details["capability"], "delete");
+
+    caps.capabilities.pop();
+    let ctx = VerificationContext::new(&trace, &caps, &ledger, &policy);
+    assert_eq!(engine.check_one(&ctx, &invariant).unwrap(), None);
+
+    policy.ast.insert(PolicyIR::PERMITTED_GRANTS_KEY.to_string(), serde_json::json!(["read"]));
+    assert!(matches!(policy.validate(), Err(FakError::Validation { .. })));
+}
+
+// ============================================================================
+// Counterexample Explanation Tests
+// ============================================================================
+
+#[test]
+fn test_explain_counterexample_traces_evaluation() {
+    let engine = ProofEngine::new();
+    let (trace, caps, policy) = (sample_trace(), sample_capabilities(), sample_policy_ir());
+    let mut ledger = sample_cost_ledger();
+    ledger.metadata.insert("budget".to_string(), serde_json::json!(10));
+    ledger.metadata.insert("spent".to_string(), serde_json::json!(12));
+    let ctx = VerificationContext::new(&trace, &caps, &ledger, &policy);
+    let inv = InvariantSpec::new(
+        "within_budget".to_string(),
+        String::new(),
+        Some("budget > 0".to_string()),
+        Some("spent <= budget".to_string()),
+        vec![],
+        ProofType::EconomicInvariance,
+    );
+
+    let counterexample = engine.check_one(&ctx, &inv).unwrap().expect("violation");
+    let explanation = engine.explain_counterexample(&ctx, &inv, &counterexample).expect("explain");
+    assert!(explanation.reproduced);
+    assert_eq!(explanation.conditions.len(), 2);
+    let post = &explanation.conditions[1];
+    let steps: Vec<String> = post.steps.iter().map(ToString::to_string).collect();
+    assert_eq!(steps, vec!["spent -> 12", "budget -> 10", "spent <= budget -> false"]);
+    assert_eq!(post.result, Some(serde_json::json!(false)));
+    assert_eq!(explanation, engine.explain_counterexample(&ctx, &inv, &counterexample).unwrap());
+    assert!(explanation.to_string().contains("spent <= budget -> false"));
+
+    let mut other = counterexample.clone();
+    other.invariant_name = "other".to_string();
+    assert!(engine.explain_counterexample(&ctx, &inv, &other).is_err());
+}
+
+// ============================================================================
+// Authority Escalation Tests
+// ============================================================================
+
+fn manifest_with(capabilities: &[&str], edges: &[(&str, &[&str])]) -> CapabilityManifest {
+    CapabilityManifest::new(
+        "cap-esc".to_string(),
+        "agent-001".to_string(),
+        capabilities.iter().map(|c| c.to_string()).collect(),
+        edges
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.iter().map(|t| t.to_string()).collect()))
+            .collect(),
+        serde_json::Map::new(),
+    )
+}
+
+fn check_escalation(caps: &CapabilityManifest) -> Option<CounterExample> {
+    let (trace, ledger, policy) = (sample_trace(), sample_cost_ledger(), sample_policy_ir());
+    let ctx = VerificationContext::new(&trace, caps, &ledger, &policy);
+    ProofEngine::new()
+        .check_one(&ctx, &typed_invariant("no_escalation", ProofType::AuthorityNonEscalation))
+        .expect("check")
+}
+
+#[test]
+fn test_direct_escalation_detected() {
+    let counterexample = check_escalation(&manifest_with(&["read"], &[("read", &["admin"])])).expect("escalation");
+    assert_eq!(counterexample.error_type, "authority_escalation");
+    assert_eq!(counterexample.details["path"], serde_json::json!(["read", "admin"]));
+    assert_eq!(counterexample.step_index, None);
+}
+
+#[test]
+fn test_transitive_escalation_reports_chain() {
+    let caps = manifest_with(&["read", "write"], &[("read", &["write"]), ("write", &["deploy"]), ("deploy", &["root"])]);
+    let counterexample = check_escalation(&caps).expect("escalation");
+    assert_eq!(counterexample.details["capability"], "deploy");
+    assert_eq!(counterexample.details["path"], serde_json::json!(["write", "deploy"]));
+    assert_eq!(caps.escalation_path(), Some(vec!["write".to_string(), "deploy".to_string()]));
+}
+
+#[test]
+fn test_cycle_within_declared_capabilities_is_not_escalation() {
+    let closed = manifest_with(&["read", "write"], &[("read", &["write"]), ("write", &["read"])]);
+    assert_eq!(check_escalation(&closed), None);
+
+    let leaking = manifest_with(&["read", "write"], &[("read", &["write"]), ("write", &["read", "admin"])]);
+    let counterexample = check_escalation(&leaking).expect("escalation");
+    assert_eq!(counterexample.details["path"], serde_json::json!(["write", "admin"]));
+}
+
+// ============================================================================
+// Bundle Schema Version Tests
+// ============================================================================
+
+#[test]
+fn test_bundle_json_round_trip_and_schema_version() {
+    let bundle = sample_bundle();
+    assert_eq!(bundle.schema_version, fak::ProofBundle::SCHEMA_VERSION);
+    let json = bundle.to_json().expect("to_json");
+    assert_eq!(fak::ProofBundle::from_json(&json).expect("from_json"), bundle);
+
+    let mut legacy: serde_json::Value = serde_json::from_str(&json).unwrap();
+    legacy.as_object_mut().unwrap().remove("schema_version");
+    let loaded = fak::ProofBundle::from_json(&legacy.to_string()).expect("legacy");
+    assert_eq!(loaded.schema_version, 1);
+    assert!(loaded.verify_id().is_ok());
+
+    legacy["schema_version"] = serde_json::json!(99);
+    legacy["witnesses"] = serde_json::json!("reshaped in a future version");
+    match fak::ProofBundle::from_json(&legacy.to_string()) {
+        Err(FakError::Serialization { message }) => assert!(message.contains("schema version 99")),
+        other => panic!("expected serialization error, got {:?}", other),
+    }
+}
+
+// ============================================================================
+// Progress Callback Tests
+// ============================================================================
+
+#[test]
+fn test_progress_callback_reports_each_invariant_in_order() {
+    let invariants: Vec<InvariantSpec> = (0..5)
+        .map(|i| typed_invariant(&format!("inv_{}", i), ProofType::BehavioralSoundness))
+        .collect();
+    let mut calls = Vec::new();
+    let witness = ProofEngine::new()
+        .verify_invariants_with_progress(
+            &sample_trace(),
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &invariants,
+            |index, total, spec| calls.push((index, total, spec.name.clone())),
+        )
+        .expect("verify");
+
+    assert!(witness.counterexamples.is_empty());
+    let expected: Vec<_> = (0..5).map(|i| (i, 5, format!("inv_{}", i))).collect();
+    assert_eq!(calls, expected);
+}
+
+// ============================================================================
+// Cancellation Tests
+// ============================================================================
+
+#[test]
+fn test_cancellation_stops_verification() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&cancel);
+    let functions = EngineConfig::default().functions.with_function("cancel_request", move |_| {
+        flag.store(true, Ordering::Relaxed);
+        Ok(serde_json::json!(true))
+    });
+    let engine = ProofEngine::with_config(EngineConfig { functions, ..EngineConfig::default() });
+    let mut invariants: Vec<InvariantSpec> = (0..3)
+        .map(|i| typed_invariant(&format!("inv_{}", i), ProofType::BehavioralSoundness))
+        .collect();
+    invariants[0].postcondition = Some("cancel_request()".to_string());
+
+    let witness = engine
+        .verify_invariants_cancellable(
+            &sample_trace(),
+            &sample_capabilities(),
+            &sample_cost_ledger(),
+            &sample_policy_ir(),
+            &invariants,
+            &cancel,
+        )
+        .expect("verify");
+    assert_eq!(witness.counterexamples.len(), 1);
+    assert_eq!(witness.counterexamples[0].error_type, "cancelled");
+    assert_eq!(witness.counterexamples[0].invariant_name, "inv_1");
+    assert_eq!(witness.counterexamples[0].details["checked"], 1);
+    assert!(witness.counterexamples[0].is_check_error());
+}
+
+// ============================================================================
+// Capacity Eviction Tests
+// ============================================================================
+
+#[test]
+fn test_capacity_evicts_least_recently_used() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let manager = ArtifactManager::with_event_sink(Box::new(move |e: ArtifactEvent| {
+        sink.lock().expect("lock").push(e.kind);
+    }))
+    .with_capacity(3);
+    let artifacts: Vec<serde_json::Value> = (0..4).map(|i| serde_json::json!({"n": i})).collect();
+
+    let ids: Vec<String> = artifacts[..3].iter().map(|a| manager.store_artifact(a).expect("store")).collect();
+    let fourth = manager.store_artifact(&artifacts[3]).expect("store");
+    assert!(!manager.contains(&ids[0]).expect("contains"));
+    assert!(manager.contains(&fourth).expect("contains"));
+    assert_eq!(events.lock().expect("lock").last(), Some(&ArtifactEventKind::Evict));
+
+    // Retrieving refreshes recency, so the next eviction skips ids[1].
+    manager.retrieve_artifact(&ids[1]).expect("retrieve");
+    assert_eq!(manager.store_artifact(&artifacts[0]).expect("store"), ids[0]);
+    assert!(manager.contains(&ids[1]).expect("contains"));
+    assert!(!manager.contains(&ids[2]).expect("contains"));
+    assert_eq!(manager.snapshot().expect("snapshot").len(), 3);
+}
+
+type Pause = Arc<Mutex<Option<(std::sync::mpsc::Sender<()>, std::sync::mpsc::Receiver<()>)>>>;
+
+/// Memory store whose next `get`, once armed, signals and then waits until
+/// released, so a test can interleave another call.
+#[derive(Debug)]
+struct PausingStore {
+    inner: fak::artifacts::MemoryArtifactStore,
+    pause: Pause,
+}
+
+impl ArtifactStore for PausingStore {
+    fn get(&self, artifact_id: &str) -> fak::FakResult<Option<serde_json::Value>> {
+        let value = self.inner.get(artifact_id)?;
+        if let Some((reached, resume)) = self.pause.lock().unwrap().take() {
+            reached.send(()).unwrap();
+            resume.recv().unwrap();
+        }
+        Ok(value)
+    }
+    fn put(&self, artifact_id: &str, artifact: &serde_json::Value) -> fak::FakResult<bool> {
+        self.inner.put(artifact_id, artifact)
+    }
+    fn contains(&self, artifact_id: &str) -> fak::FakResult<bool> {
+        self.inner.contains(artifact_id)
+    }
+    fn remove(&self, artifact_id: &str) -> fak::FakResult<Option<serde_json::Value>> {
+        self.inner.remove(artifact_id)
+    }
+    fn clear(&self) -> fak::FakResult<()> {
+        self.inner.clear()
+    }
+    fn ids(&self) -> fak::FakResult<Vec<String>> {
+        self.inner.ids()
+    }
+    fn fork(&self) -> Box<dyn ArtifactStore> {
+        self.inner.fork()
+    }
+}
+
+#[test]
+fn test_retrieve_racing_remove_leaves_no_lru_entry() {
+    let pause = Pause::default();
+    let store = PausingStore { inner: Default::default(), pause: Arc::clone(&pause) };
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let manager = ArtifactManager::with_event_sink(Box::new(move |e: ArtifactEvent| {
+        sink.lock().expect("lock").push(e.kind);
+    }))
+    .with_store(store)
+    .with_capacity(3);
+    let id = manager.store_artifact(&serde_json::json!("transient")).expect("store");
+
+    // Remove the artifact between the retrieve's read and its LRU touch.
+    let (reached_tx, reached) = std::sync::mpsc::channel();
+    let (resume, resume_rx) = std::sync::mpsc::channel();
+    *pause.lock().expect("lock") = Some((reached_tx, resume_rx));
+    std::thread::scope(|scope| {
+        let retrieving = scope.spawn(|| manager.retrieve_artifact(&id));
+        reached.recv().expect("recv");
+        manager.remove_artifact(&id).expect("remove");
+        resume.send(()).expect("send");
+        assert!(retrieving.join().expect("join").is_ok());
+    });
+
+    // A removed ID left in the LRU order would take a slot and force a
+    // spurious eviction here.
+    let ids: Vec<String> =
+        (0..3).map(|i| manager.store_artifact(&serde_json::json!({"n": i})).expect("store")).collect();
+    assert!(ids.iter().all(|id| manager.contains(id).expect("contains")));
+    assert!(!events.lock().expect("lock").contains(&ArtifactEventKind::Evict));
+}
+
+#[test]
+fn test_eviction_tie_break_is_smallest_id() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let root = tmp.path();
+    let seed = ArtifactManager::new().with_store(FileArtifactStore::new(root).expect("open"));
+    let mut held: Vec<String> = (0..3)
+        .map(|i| seed.store_artifact(&serde_json::json!({"seed": i})).expect("seed"))
+        .collect();
+    held.sort();
+
+    // Artifacts found in the store tie as least recently used.
+    let manager = ArtifactManager::new()
+        .with_store(FileArtifactStore::new(root).expect("open"))
+        .with_capacity(3);
+    manager.store_artifact(&serde_json::json!({"seed": "new"})).expect("store");
+    assert!(!manager.contains(&held[0]).expect("contains"));
+    assert!(manager.contains(&held[1]).expect("contains"));
+    assert!(manager.contains(&held[2]).expect("contains"));
+}
+
+// ============================================================================
+// Step Condition Tests
+// ============================================================================
+
+#[test]
+fn test_behavioral_condition_reports_failing_step() {
+    let trace = ExecutionTrace::new(
+        "trace-steps".to_string(),
+        vec![
+            serde_json::json!({"action": "read", "cost": 1}),
+            serde_json::json!({"action": "write", "cost": 50}),
+            serde_json::json!({"action": "read", "cost": 2}),
+        ],
+        serde_json::Map::new(),
+    );
+    let (caps, ledger, policy) = (sample_capabilities(), sample_cost_ledger(), sample_policy_ir());
+    let ctx = VerificationContext::new(&trace, &caps, &ledger, &policy);
+    let mut inv = typed_invariant("cheap_steps", ProofType::BehavioralSoundness);
+    inv.postcondition = Some("cost <= 10".to_string());
+
+    let engine = ProofEngine::new();
+    let counterexample = engine.check_one(&ctx, &inv).unwrap().expect("violation");
+    assert_eq!(counterexample.error_type, "condition_failed");
+    assert_eq!(counterexample.step_index, Some(1));
+    assert_eq!(counterexample.details["step"], trace.steps[1]);
+
+    inv.precondition = Some("action == 'read'".to_string());
+    assert_eq!(engine.check_one(&ctx, &inv).unwrap(), None);
+
+    let mut malformed = trace.clone();
+    malformed.steps[2] = serde_json::json!("not an object");
+    let ctx = VerificationContext::new(&malformed, &caps, &ledger, &policy);
+    let counterexample = engine.check_one(&ctx, &inv).unwrap().expect("invalid step");
+    assert_eq!(counterexample.error_type, "invalid_step");
+    assert_eq!(counterexample.step_index, Some(2));
+}
+
+#[test]
+fn test_never_met_precondition_is_reported_vacuous() {
+    let trace = ExecutionTrace::new(
+        "trace-vacuous".to_string(),
+        vec![serde_json::json!({"action": "read", "cost": 1}), serde_json::json!({"action": "read", "cost": 2})],
+        serde_json::Map::new(),
+    );
+    let mut never = typed_invariant("cheap_writes", ProofType::BehavioralSoundness);
+    never.precondition = Some("action == 'write'".to_string());
+    never.postcondition = Some("cost <= 10".to_string());
+    let mut met = never.clone();
+    met.name = "cheap_reads".to_string();
+    met.precondition = Some("action == 'read'".to_string());
+
+    let witness = ProofEngine::new()
+        .verify_invariants(&trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[never, met])
+        .expect("verify");
+    assert!(witness.counterexamples.is_empty());
+    assert_eq!(witness.vacuous, vec!["cheap_writes"]);
+
+    let bundle = ProofEngine::new().generate_bundle(&[witness]).expect("bundle");
+    let mut out = Vec::new();
+    let result = Verifier::new()
+        .write_result(&bundle, &mut out, OutputFormat::Summary)
+        .expect("write");
+    assert!(result.success);
+    assert_eq!(result.witness_results[0].vacuous, vec!["cheap_writes"]);
+    assert!(String::from_utf8(out).expect("utf-8").contains("vacuously true: cheap_writes"));
+}
+
+// ============================================================================
+// Bundle Merge Tests
+// ============================================================================
+
+#[test]
+fn test_merge_bundles_dedups_and_rehashes() {
+    let engine = ProofEngine::new();
+    let first = engine.generate_bundle(&[witness_with(&[])]).unwrap();
+    let other_trace = ExecutionTrace::new("trace-002".to_string(), vec![serde_json::json!({"step": 2})], serde_json::Map::new());
+    let second_witness = engine
+        .verify_invariants(&other_trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[])
+        .unwrap();
+    let mut second = engine.generate_bundle(&[second_witness]).unwrap();
+    second.metadata.insert("worker".to_string(), serde_json::json!("b"));
+    second.id = second.content_id();
+
+    let merged = engine.merge_bundles(&[first.clone(), second.clone(), first.clone()]).expect("merge");
+    assert_eq!(merged.witnesses.len(), 2);
+    assert!(merged.witnesses[0].proof_id < merged.witnesses[1].proof_id);
+    let reversed = engine.merge_bundles(&[second.clone(), first.clone()]).expect("merge");
+    assert_eq!(reversed.witnesses, merged.witnesses);
+    assert_eq!(merged.metadata["worker"], "b");
+    assert!(merged.verify_id().is_ok());
+    assert!(Verifier::new().verify_bundle(&merged).success);
+
+    // Same proof ID and bundle ID, different witness content.
+    let mut conflicting = first.clone();
+    conflicting.witnesses[0].not_evaluated.push("ghost".to_string());
+    assert!(matches!(
+        engine.merge_bundles(&[first.clone(), conflicting]),
+        Err(FakError::IntegrityFailure { artifact_id, .. }) if artifact_id == first.witnesses[0].proof_id
+    ));
+
+    let mut tampered = second;
+    tampered.metadata.insert("worker".to_string(), serde_json::json!("c"));
+    assert!(matches!(engine.merge_bundles(&[first, tampered]), Err(FakError::IntegrityFailure { .. })));
+}
+
+// ============================================================================
+// Error Code Tests
+// ============================================================================
+
+#[test]
+fn test_error_codes_are_distinct_and_categorized() {
+    use fak::ErrorCategory;
+
+    let s = String::new;
+    let cases = [
+        (FakError::Validation { field: s(), message: s() }, ErrorCategory::Input),
+        (FakError::ArtifactNotFound { artifact_id: s() }, ErrorCategory::Input),
+        (FakError::IntegrityFailure { artifact_id: s(), expected: s(), actual: s() }, ErrorCategory::Integrity),
+        (FakError::ParseError { source: s(), message: s(), line: None, column: None }, ErrorCategory::Input),
+        (FakError::VerificationFailure { invariant: s(), reason: s() }, ErrorCategory::Integrity),
+        (FakError::ResourceLimit { resource: s(), limit: 1, actual: 2 }, ErrorCategory::Resource),
+        (FakError::Timeout { operation: s(), limit_secs: 1.0 }, ErrorCategory::Resource),
+        (FakError::Serialization { message: s() }, ErrorCategory::Input),
+        (FakError::UnknownProofType { value: s() }, ErrorCategory::Input),
+        (FakError::BundleVerificationFailed { bundle_id: s(), reason: s() }, ErrorCategory::Integrity),
+        (FakError::LockPoisoned { resource: s() }, ErrorCategory::Internal),
+        (FakError::Io { message: s() }, ErrorCategory::Internal),
+    ];
+
+    let codes: std::collections::HashSet<&str> = cases.iter().map(|(e, _)| e.code()).collect();
+    assert_eq!(codes.len(), cases.len());
+    assert_eq!(cases[0].0.code(), "FAK001");
+    assert_eq!(cases[1].0.code(), "FAK002");
+    for (error, category) in &cases {
+        assert!(error.code().starts_with("FAK"));
+        assert_eq!(error.category(), *category, "{}", error.code());
+    }
+}
+
+// ============================================================================
+// Incremental Verification Tests
+// ============================================================================
+
+#[test]
+fn test_incremental_verification_skips_unchanged_witnesses() {
+    let verified = Arc::new(Mutex::new(Vec::new()));
+    let log = verified.clone();
+    let hooks = ValidationHooks::new().on_trace(move |trace| {
+        log.lock().unwrap().push(trace.id.clone());
+        Ok(())
+    });
+    let verifier = Verifier::with_config(EngineConfig { hooks, ..EngineConfig::default() });
+
+    let engine = ProofEngine::new();
+    let witness_for = |trace: &ExecutionTrace| {
+        engine
+            .verify_invariants(trace, &sample_capabilities(), &sample_cost_ledger(), &sample_policy_ir(), &[])
+            .unwrap()
+    };
+    let trace_b = |step: i64| ExecutionTrace::new("trace-b".to_string(), vec![serde_json::json!({"step": step})], serde_json::Map::new());
+    let unchanged = witness_for(&sample_trace());
+
+    let bundle = engine.generate_bundle(&[unchanged.clone(), witness_for(&trace_b(1))]).unwrap();
+    let first = verifier.verify_bundle(&bundle);
+    assert!(first.success);
+    verified.lock().unwrap().sort();
+    assert_eq!(*verified.lock().unwrap(), vec!["trace-001", "trace-b"]);
+    let position = |bundle: &fak::ProofBundle, trace_id: &str| {
+        bundle.witnesses.iter().position(|w| w.execution_trace.id == trace_id).unwrap()
+    };
+
+    // Same trace ID, different contents: the proof ID covers the contents,
+    // so the witness is new and must be re-verified.
+    verified.lock().unwrap().clear();
+    let changed = engine.generate_bundle(&[unchanged, witness_for(&trace_b(2))]).unwrap();
+    let (old_b, new_b) = (position(&bundle, "trace-b"), position(&changed, "trace-b"));
+    assert_ne!(changed.witnesses[new_b].proof_id, bundle.witnesses[old_b].proof_id);
+    let second = verifier.verify_bundle_incremental(&changed, &first);
+    assert!(second.success);
+    assert_eq!(*verified.lock().unwrap(), vec!["trace-b"]);
+    let (old_a, new_a) = (position(&bundle, "trace-001"), position(&changed, "trace-001"));
+    assert_eq!(second.witness_results[new_a], first.witness_results[old_a]);
+    assert_eq!(second, verifier.verify_bundle(&changed));
+
+    // Failed results are never reused.
+    verified.lock().unwrap().clear();
+    let mut failed = first.clone();
+    failed.witness_results[old_a].success = false;
+    verifier.verify_bundle_incremental(&bundle, &failed);
+    assert_eq!(*verified.lock().unwrap(), vec!["trace-001"]);
+}
+
+// ============================================================================
+// Artifact Tag Index Tests
+// ============================================================================
+
+#[test]
+fn test_query_artifacts_by_tag() {
+    let manager = ArtifactManager::new();
+    let tags = |agent: &str| HashMap::from([("agent".to_string(), agent.to_string()), ("kind".to_string(), "cost_ledger".to_string())]);
+    let first = serde_json::to_value(sample_cost_ledger()).unwrap();
+    let mut second_ledger = sample_cost_ledger();
+    second_ledger.id = "cost-002".to_string();
+    let second = serde_json::to_value(second_ledger).unwrap();
+
+    let first_id = manager.store_artifact_indexed(&first, tags("agent-001")).expect("store");
+    let second_id = manager.store_artifact_indexed(&second, tags("agent-002")).expect("store");
+    assert_eq!(first_id, manager.content_hash(&first));
+
+    assert_eq!(manager.query_by_tag("agent", "agent-001").unwrap(), vec![first_id.clone()]);
+    assert_eq!(manager.query_by_tag("agent", "agent-002").unwrap(), vec![second_id.clone()]);
+    let mut both = vec![first_id.clone(), second_id.clone()];
+    both.sort();
+    assert_eq!(manager.query_by_tag("kind", "cost_ledger").unwrap(), both);
+    assert!(manager.query_by_tag("agent", "agent-003").unwrap().is_empty());
+
+    // Re-tagging replaces the value; removal drops the artifact's tags.
+    manager.store_artifact_indexed(&first, HashMap::from([("agent".to_string(), "agent-003".to_string())])).unwrap();
+    assert!(manager.query_by_tag("agent", "agent-001").unwrap().is_empty());
+    assert_eq!(manager.query_by_tag("agent", "agent-003").unwrap(), vec![first_id.clone()]);
+    manager.remove_artifact(&second_id).unwrap();
+    assert_eq!(manager.query_by_tag("kind", "cost_ledger").unwrap(), vec![first_id]);
+
+    manager.clear().unwrap();
+    assert!(manager.query_by_tag("agent", "agent-003").unwrap().is_empty());
+    assert!(manager.query_by_tag("kind", "cost_ledger").unwrap().is_empty());
+}
+
+// ============================================================================
+// JSON Schema Tests
+// ============================================================================
+
+#[cfg(feature = "schema")]
+#[test]
+fn test_cost_ledger_schema() {
+    let schemas = fak::schema::artifact_schemas();
+    assert!(schemas.contains_key("ProofBundle"));
+    let total_cost = &schemas["CostLedger"]["properties"]["total_cost"];
+    assert_eq!(total_cost["type"], "number");
+    let required = schemas["CostLedger"]["required"].as_array().expect("required");
+    assert!(required.contains(&serde_json::json!("total_cost")));
+}
+
+// ============================================================================
+// Async Artifact Manager Tests
+// ============================================================================
+
+#[cfg(feature = "tokio")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_async_manager_concurrent_store_retrieve() {
+    let manager = Arc::new(fak::async_artifacts::AsyncArtifactManager::new());
+    let tasks: Vec<_> = (0..16)
+        .map(|i| {
+            let manager = Arc::clone(&manager);
+            tokio::spawn(async move {
+                let artifact = serde_json::json!({"policy": "concurrent", "n": i % 8});
+                let artifact_id = manager.store_artifact(&artifact).await.expect("store");
+                assert_eq!(manager.retrieve_artifact(&artifact_id).await.expect("retrieve"), artifact);
+                artifact_id
+            })
+        })
+        .collect();
+    let mut ids = Vec::new();
+    for task in tasks {
+        ids.push(task.await.expect("task"));
+    }
+
+    assert_eq!(manager.len().await, 8);
+    assert_eq!(ids[0], ids[8]);
+    assert_eq!(ids[0], ArtifactManager::new().content_hash(&serde_json::json!({"policy": "concurrent", "n": 0})));
+    let ledger: CostLedger = {
+        let value = serde_json::to_value(sample_cost_ledger()).expect("to_value");
+        let id = manager.store_artifact(&value).await.expect("store");
+        manager.retrieve_artifact_as(&id).await.expect("typed")
+    };
+    assert_eq!(ledger, sample_cost_ledger());
+    assert!(matches!(
+        manager.retrieve_artifact("missing").await,
+        Err(FakError::ArtifactNotFound { .. })
+    ));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_async_manager_shares_id_scheme() {
+    let keys = vec!["metadata.captured_at".to_string()];
+    let manager = fak::async_artifacts::AsyncArtifactManager::new()
+        .with_salt("tenant")
+        .with_volatile_keys(keys.clone());
+    let sync = ArtifactManager::new().with_salt("tenant").with_volatile_keys(keys);
+    let first = serde_json::json!({"policy": "p", "metadata": {"captured_at": "t1"}});
+    let later = serde_json::json!({"policy": "p", "metadata": {"captured_at": "t2"}});
+
+    let artifact_id = manager.store_artifact(&first).await.expect("store");
+    assert_eq!(artifact_id, sync.store_artifact(&first).expect("sync store"));
+    assert_eq!(manager.store_artifact(&later).await.expect("dedup"), artifact_id);
+    assert_eq!(manager.retrieve_artifact(&artifact_id).await.expect("retrieve"), first);
+    assert!(manager.validate_artifact_integrity(&artifact_id, &later));
+
+    let snapshot = manager.snapshot().await;
+    assert_eq!(snapshot.artifact_id(&later).expect("snapshot id"), artifact_id);
+}
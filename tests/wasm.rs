@@ -0,0 +1,22 @@
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use fak::wasm::verify_bundle_json;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn rejects_malformed_bundle_json() {
+    let result: serde_json::Value =
+        serde_json::from_str(&verify_bundle_json("{not json")).expect("result json");
+    assert_eq!(result["success"], false);
+    assert!(result["error"].is_string());
+}
+
+#[wasm_bindgen_test]
+fn verifies_empty_bundle() {
+    let mut bundle = fak::ProofBundle::new(String::new(), vec![], serde_json::Map::new());
+    bundle.id = bundle.content_id();
+    let result: serde_json::Value =
+        serde_json::from_str(&verify_bundle_json(&bundle.to_json().expect("json")))
+            .expect("result json");
+    assert_eq!(result["bundle_id"], bundle.id.as_str());
+}